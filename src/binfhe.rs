@@ -0,0 +1,21 @@
+//! FHEW/TFHE boolean scheme with gate bootstrapping
+
+mod bin_fhe_method;
+mod bin_fhe_param_set;
+mod bin_gate;
+mod context;
+mod lwe;
+mod params;
+mod rgsw;
+mod rgsw_acc;
+mod scheme;
+
+pub use bin_fhe_method::*;
+pub use bin_fhe_param_set::*;
+pub use bin_gate::*;
+pub use context::*;
+pub use lwe::{LweCiphertext, LwePlaintext, LwePlaintextModulus, LwePrivateKey, LweSwitchingKey};
+pub use params::*;
+pub use rgsw::{AutomorphismKey, RingGswCiphertext, RlweCiphertext};
+pub use rgsw_acc::*;
+pub use scheme::BootstrappingKey;
@@ -0,0 +1,73 @@
+use crate::Error;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+/// Blind rotation method used for bootstrapping
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(test, derive(Eq, PartialEq))]
+#[repr(u8)]
+pub enum BinFheMethod {
+    /// Alperin-Sheriff and Peikert (FHEW), largest keys
+    Ap = 1,
+    /// Gama, Izabachene, Nguyen and Xie (TFHE/CGGI)
+    #[default]
+    Ginx = 2,
+    /// Lee, Micciancio, Kim, Choi, Deryabin, Eom and Yoo, smallest keys using
+    /// ring automorphisms
+    Lmkcdey = 3,
+}
+
+impl TryFrom<u8> for BinFheMethod {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(BinFheMethod::Ap),
+            2 => Ok(BinFheMethod::Ginx),
+            3 => Ok(BinFheMethod::Lmkcdey),
+            _ => Err(Error::InvalidBinFheMethod(value as usize)),
+        }
+    }
+}
+
+try_from_int_impl!(
+    BinFheMethod,
+    InvalidBinFheMethod,
+    i8,
+    u16,
+    i16,
+    u32,
+    i32,
+    u64,
+    i64,
+    usize,
+    isize
+);
+into_int_impl!(BinFheMethod, i8, u16, i16, u32, i32, u64, i64, usize, isize);
+enum_serde_impl!(BinFheMethod);
+
+impl Display for BinFheMethod {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            BinFheMethod::Ap => write!(f, "Ap"),
+            BinFheMethod::Ginx => write!(f, "Ginx"),
+            BinFheMethod::Lmkcdey => write!(f, "Lmkcdey"),
+        }
+    }
+}
+
+impl FromStr for BinFheMethod {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Ap" => Ok(BinFheMethod::Ap),
+            "Ginx" => Ok(BinFheMethod::Ginx),
+            "Lmkcdey" => Ok(BinFheMethod::Lmkcdey),
+            _ => Err(Error::ParseError(format!(
+                "invalid string when parsing BinFheMethod: '{}'",
+                s
+            ))),
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use crate::Error;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+/// Predefined parameter sets for FHEW/TFHE boolean circuits
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(test, derive(Eq, PartialEq))]
+#[repr(u8)]
+pub enum BinFheParamSet {
+    /// Small insecure parameters for tests and examples
+    Toy = 0,
+    /// Medium security parameters
+    Medium = 1,
+    /// 128-bit classical security
+    #[default]
+    Std128 = 2,
+}
+
+impl TryFrom<u8> for BinFheParamSet {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(BinFheParamSet::Toy),
+            1 => Ok(BinFheParamSet::Medium),
+            2 => Ok(BinFheParamSet::Std128),
+            _ => Err(Error::InvalidBinFheParamSet(value as usize)),
+        }
+    }
+}
+
+try_from_int_impl!(
+    BinFheParamSet,
+    InvalidBinFheParamSet,
+    i8,
+    u16,
+    i16,
+    u32,
+    i32,
+    u64,
+    i64,
+    usize,
+    isize
+);
+into_int_impl!(
+    BinFheParamSet,
+    i8,
+    u16,
+    i16,
+    u32,
+    i32,
+    u64,
+    i64,
+    usize,
+    isize
+);
+enum_serde_impl!(BinFheParamSet);
+
+impl Display for BinFheParamSet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            BinFheParamSet::Toy => write!(f, "Toy"),
+            BinFheParamSet::Medium => write!(f, "Medium"),
+            BinFheParamSet::Std128 => write!(f, "Std128"),
+        }
+    }
+}
+
+impl FromStr for BinFheParamSet {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Toy" => Ok(BinFheParamSet::Toy),
+            "Medium" => Ok(BinFheParamSet::Medium),
+            "Std128" => Ok(BinFheParamSet::Std128),
+            _ => Err(Error::ParseError(format!(
+                "invalid string when parsing BinFheParamSet: '{}'",
+                s
+            ))),
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use crate::Error;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+/// Two-input boolean gates evaluated with a single bootstrapping
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(test, derive(Eq, PartialEq))]
+#[repr(u8)]
+pub enum BinGate {
+    /// Logical OR
+    Or = 0,
+    /// Logical AND
+    And = 1,
+    /// Negated OR
+    Nor = 2,
+    /// Negated AND
+    Nand = 3,
+    /// Exclusive OR
+    Xor = 4,
+    /// Negated exclusive OR
+    Xnor = 5,
+}
+
+impl TryFrom<u8> for BinGate {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(BinGate::Or),
+            1 => Ok(BinGate::And),
+            2 => Ok(BinGate::Nor),
+            3 => Ok(BinGate::Nand),
+            4 => Ok(BinGate::Xor),
+            5 => Ok(BinGate::Xnor),
+            _ => Err(Error::InvalidBinGate(value as usize)),
+        }
+    }
+}
+
+try_from_int_impl!(
+    BinGate,
+    InvalidBinGate,
+    i8,
+    u16,
+    i16,
+    u32,
+    i32,
+    u64,
+    i64,
+    usize,
+    isize
+);
+into_int_impl!(BinGate, i8, u16, i16, u32, i32, u64, i64, usize, isize);
+enum_serde_impl!(BinGate);
+
+impl Display for BinGate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            BinGate::Or => write!(f, "Or"),
+            BinGate::And => write!(f, "And"),
+            BinGate::Nor => write!(f, "Nor"),
+            BinGate::Nand => write!(f, "Nand"),
+            BinGate::Xor => write!(f, "Xor"),
+            BinGate::Xnor => write!(f, "Xnor"),
+        }
+    }
+}
+
+impl FromStr for BinGate {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Or" => Ok(BinGate::Or),
+            "And" => Ok(BinGate::And),
+            "Nor" => Ok(BinGate::Nor),
+            "Nand" => Ok(BinGate::Nand),
+            "Xor" => Ok(BinGate::Xor),
+            "Xnor" => Ok(BinGate::Xnor),
+            _ => Err(Error::ParseError(format!(
+                "invalid string when parsing BinGate: '{}'",
+                s
+            ))),
+        }
+    }
+}
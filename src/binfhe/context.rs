@@ -0,0 +1,656 @@
+use crate::binfhe::{
+    lwe, scheme, BinFheCryptoParams, BinFheMethod, BinFheParamSet, BinGate, BootstrappingKey,
+    LweCiphertext, LwePlaintext, LwePlaintextModulus, LwePrivateKey,
+};
+use crate::fhe_core::{ContextRng, Modulus, SEED_LEN};
+use crate::{Error, FheResult};
+use rand_chacha::ChaCha20Rng;
+use rand_core::CryptoRngCore;
+
+/// Plaintext modulus used to encrypt bits, m is encoded as m * q/4
+const BIT_PLAINTEXT_MODULUS: u64 = 4;
+
+/// Entry point of the FHEW/TFHE boolean scheme. Bits are encrypted as LWE
+/// ciphertexts and every gate is followed by a bootstrapping, so circuits of any
+/// depth can be evaluated without parameter planning. The randomness of every
+/// sampling is drawn from the generator of the context, shared by its clones.
+#[derive(Debug, Clone)]
+pub struct BinFheContext {
+    params: BinFheCryptoParams,
+    bt_key: Option<BootstrappingKey>,
+    rng: ContextRng,
+    rng_seed: Option<[u8; SEED_LEN]>,
+}
+
+impl BinFheContext {
+    /// Create a context for one of the predefined parameter sets, bootstrapping
+    /// with [`BinFheMethod::Ginx`]
+    pub fn new(set: BinFheParamSet) -> FheResult<Self> {
+        Self::with_method(set, BinFheMethod::default())
+    }
+
+    /// Create a context for one of the predefined parameter sets and the blind
+    /// rotation method `method`
+    pub fn with_method(set: BinFheParamSet, method: BinFheMethod) -> FheResult<Self> {
+        Ok(Self::with_params(BinFheCryptoParams::from_param_set(
+            set, method,
+        )?))
+    }
+
+    /// Create a context from custom parameters
+    pub fn with_params(params: BinFheCryptoParams) -> Self {
+        Self {
+            params,
+            bt_key: None,
+            rng: ContextRng::default(),
+            rng_seed: None,
+        }
+    }
+
+    /// The parameters of the context
+    pub fn params(&self) -> &BinFheCryptoParams {
+        &self.params
+    }
+
+    /// Draw the randomness from `rng` rather than from ChaCha20 keyed by the
+    /// operating system
+    pub fn set_rng<R: CryptoRngCore + Send + 'static>(&mut self, rng: R) {
+        self.rng = ContextRng::new(rng);
+        self.rng_seed = None;
+    }
+
+    /// Draw the randomness from ChaCha20 keyed with `seed`, so the same
+    /// sequence of operations gives the same keys and ciphertexts. Meant for
+    /// tests and known-answer vectors, anyone holding the seed can recompute
+    /// the secrets.
+    pub fn set_rng_seed(&mut self, seed: [u8; SEED_LEN]) {
+        self.rng = ContextRng::from_seed(seed);
+        self.rng_seed = Some(seed);
+    }
+
+    /// The seed of the generator, if set by [`Self::set_rng_seed`]
+    pub fn rng_seed(&self) -> Option<&[u8; SEED_LEN]> {
+        self.rng_seed.as_ref()
+    }
+
+    /// Generate a new LWE secret key
+    pub fn key_gen(&self) -> LwePrivateKey {
+        LwePrivateKey::generate(self.params.lwe().n(), &mut self.rng())
+    }
+
+    /// Generate the bootstrapping key for `sk` and store it in the context
+    pub fn bt_key_gen(&mut self, sk: &LwePrivateKey) -> FheResult<()> {
+        self.check_key(sk)?;
+        let key = BootstrappingKey::generate(&self.params, sk, &mut self.rng());
+        self.bt_key = Some(key);
+        Ok(())
+    }
+
+    /// The bootstrapping key, if one was generated or loaded
+    pub fn bt_key(&self) -> Option<&BootstrappingKey> {
+        self.bt_key.as_ref()
+    }
+
+    /// Load a previously generated bootstrapping key
+    pub fn set_bt_key(&mut self, key: BootstrappingKey) -> FheResult<()> {
+        if u8::from(key.method()) != u8::from(self.params.rgsw().method()) {
+            return Err(Error::InvalidKey(format!(
+                "bootstrapping key for {} used with {}",
+                key.method(),
+                self.params.rgsw().method()
+            )));
+        }
+        self.bt_key = Some(key);
+        Ok(())
+    }
+
+    /// Encrypt the bit `m`
+    pub fn encrypt(&self, sk: &LwePrivateKey, m: LwePlaintext) -> FheResult<LweCiphertext> {
+        self.check_key(sk)?;
+        if m > 1 {
+            return Err(Error::InvalidPlaintext(m));
+        }
+        let lwe = self.params.lwe();
+        Ok(lwe::encrypt(
+            lwe,
+            sk,
+            m,
+            BIT_PLAINTEXT_MODULUS,
+            lwe.q_modulus(),
+            &mut self.rng(),
+        ))
+    }
+
+    /// Decrypt a bit
+    pub fn decrypt(&self, sk: &LwePrivateKey, ct: &LweCiphertext) -> FheResult<LwePlaintext> {
+        self.check_key(sk)?;
+        self.check_ciphertext(ct)?;
+        Ok(lwe::decrypt(sk, ct, BIT_PLAINTEXT_MODULUS) & 1)
+    }
+
+    /// Encrypt `m` in Z_p as m * q/p, the input of [`Self::eval_func`]
+    pub fn encrypt_with_plaintext_modulus(
+        &self,
+        sk: &LwePrivateKey,
+        m: LwePlaintext,
+        p: LwePlaintextModulus,
+    ) -> FheResult<LweCiphertext> {
+        self.encrypt_with_modulus(sk, m, p, self.params.lwe().q())
+    }
+
+    /// Encrypt `m` in Z_p as m * M/p under the ciphertext modulus M, a power of
+    /// two multiple of q up to Q_ks. Such large precision ciphertexts are the
+    /// input of [`Self::eval_floor`] and [`Self::eval_sign`], whose bootstrapped
+    /// values carry a noise proportional to M/Q_ks and M/Q: the predefined sets
+    /// support M = 4q, larger precisions need custom parameters with larger
+    /// moduli.
+    pub fn encrypt_with_modulus(
+        &self,
+        sk: &LwePrivateKey,
+        m: LwePlaintext,
+        p: LwePlaintextModulus,
+        modulus: u64,
+    ) -> FheResult<LweCiphertext> {
+        self.check_key(sk)?;
+        self.check_modulus(modulus)?;
+        check_plaintext_modulus(p, modulus)?;
+        if m >= p {
+            return Err(Error::InvalidPlaintext(m));
+        }
+        Ok(lwe::encrypt(
+            self.params.lwe(),
+            sk,
+            m,
+            p,
+            &Modulus::new(modulus),
+            &mut self.rng(),
+        ))
+    }
+
+    /// Decrypt a ciphertext of any supported modulus into Z_p
+    pub fn decrypt_with_plaintext_modulus(
+        &self,
+        sk: &LwePrivateKey,
+        ct: &LweCiphertext,
+        p: LwePlaintextModulus,
+    ) -> FheResult<LwePlaintext> {
+        self.check_key(sk)?;
+        self.check_large_ciphertext(ct)?;
+        check_plaintext_modulus(p, ct.modulus())?;
+        Ok(lwe::decrypt(sk, ct, p))
+    }
+
+    /// The largest plaintext modulus supported by [`Self::eval_floor`] and
+    /// [`Self::eval_sign`] on ciphertexts modulo `modulus`, leaving a margin of
+    /// beta = q/8 on both sides of every message
+    pub fn max_plaintext_space(&self, modulus: u64) -> LwePlaintextModulus {
+        modulus / (self.params.lwe().q() >> 2)
+    }
+
+    /// Tabulate `f(m, p)` for every m in Z_p, the results being reduced mod p
+    pub fn generate_lut_via_function(
+        &self,
+        f: impl Fn(LwePlaintext, LwePlaintextModulus) -> LwePlaintext,
+        p: LwePlaintextModulus,
+    ) -> FheResult<Vec<LwePlaintext>> {
+        check_plaintext_modulus(p, self.params.lwe().q())?;
+        Ok((0..p).map(|m| f(m, p) % p).collect())
+    }
+
+    /// Evaluate a two-input boolean gate
+    pub fn eval_bin_gate(
+        &self,
+        gate: BinGate,
+        ct1: &LweCiphertext,
+        ct2: &LweCiphertext,
+    ) -> FheResult<LweCiphertext> {
+        self.check_ciphertext(ct1)?;
+        self.check_ciphertext(ct2)?;
+        Ok(scheme::eval_bin_gate(
+            &self.params,
+            self.bootstrapping_key()?,
+            gate,
+            ct1,
+            ct2,
+        ))
+    }
+
+    /// Evaluate NOT, which needs no bootstrapping
+    pub fn eval_not(&self, ct: &LweCiphertext) -> FheResult<LweCiphertext> {
+        self.check_ciphertext(ct)?;
+        let mut out = ct.clone();
+        out.neg_assign();
+        out.add_constant_assign(self.params.lwe().q() >> 2);
+        Ok(out)
+    }
+
+    /// Evaluate the multiplexer `sel ? ct1 : ct0`
+    pub fn eval_mux(
+        &self,
+        sel: &LweCiphertext,
+        ct1: &LweCiphertext,
+        ct0: &LweCiphertext,
+    ) -> FheResult<LweCiphertext> {
+        let high = self.eval_bin_gate(BinGate::And, sel, ct1)?;
+        let low = self.eval_bin_gate(BinGate::And, &self.eval_not(sel)?, ct0)?;
+        self.eval_bin_gate(BinGate::Or, &high, &low)
+    }
+
+    /// Refresh the noise of a bit encryption
+    pub fn bootstrap(&self, ct: &LweCiphertext) -> FheResult<LweCiphertext> {
+        self.check_ciphertext(ct)?;
+        Ok(scheme::bootstrap(
+            &self.params,
+            self.bootstrapping_key()?,
+            ct,
+        ))
+    }
+
+    /// Evaluate the function given by the look-up table `lut` over Z_p, p being
+    /// its length, on an encryption of m * q/p. Negacyclic tables, for which
+    /// f(m + p/2) = -f(m), take one bootstrapping; arbitrary ones take two and
+    /// need q <= N, or q <= N/2 with [`BinFheMethod::Lmkcdey`].
+    pub fn eval_func(&self, ct: &LweCiphertext, lut: &[LwePlaintext]) -> FheResult<LweCiphertext> {
+        self.check_ciphertext(ct)?;
+        let lwe = self.params.lwe();
+        let p = lut.len() as u64;
+        check_plaintext_modulus(p, lwe.q())?;
+        if let Some(&m) = lut.iter().find(|&&m| m >= p) {
+            return Err(Error::InvalidPlaintext(m));
+        }
+        // the second bootstrapping reads the ciphertext modulo 2q, which must
+        // divide 2N, and N for the even exponents of LMKCDEY
+        let method = self.params.rgsw().method();
+        let max_q = match method {
+            BinFheMethod::Lmkcdey => lwe.ring_dim() / 2,
+            _ => lwe.ring_dim(),
+        };
+        if !scheme::is_negacyclic(lut) && lwe.q() > max_q as u64 {
+            return Err(Error::InvalidParameters(format!(
+                "arbitrary functions need q <= {} with {}, got q = {} and N = {}",
+                max_q,
+                method,
+                lwe.q(),
+                lwe.ring_dim()
+            )));
+        }
+        Ok(scheme::eval_func(
+            &self.params,
+            self.bootstrapping_key()?,
+            ct,
+            lut,
+        ))
+    }
+
+    /// Round the phase of a large precision ciphertext down, after adding a
+    /// margin beta = q/8, to a multiple of 2 * beta * 2^round_bits, or of q for
+    /// `round_bits` = 0. For messages encoded with the
+    /// [maximal plaintext space](Self::max_plaintext_space) this clears their
+    /// `round_bits` least significant bits.
+    pub fn eval_floor(&self, ct: &LweCiphertext, round_bits: u32) -> FheResult<LweCiphertext> {
+        self.check_large_ciphertext(ct)?;
+        let q = self.params.lwe().q();
+        if round_bits > 0 && (q >> 2).checked_shl(round_bits).is_none_or(|x| x > q) {
+            return Err(Error::InvalidParameters(format!(
+                "cannot round {round_bits} bits with q = {q}"
+            )));
+        }
+        Ok(scheme::eval_floor(
+            &self.params,
+            self.bootstrapping_key()?,
+            ct,
+            round_bits,
+        ))
+    }
+
+    /// Evaluate the most significant bit of the message of a large precision
+    /// ciphertext, i.e. its sign when read as a signed integer, as a bit
+    /// encryption modulo q
+    pub fn eval_sign(&self, ct: &LweCiphertext) -> FheResult<LweCiphertext> {
+        self.check_large_ciphertext(ct)?;
+        Ok(scheme::eval_sign(
+            &self.params,
+            self.bootstrapping_key()?,
+            ct,
+        ))
+    }
+
+    /// Compare two large precision ciphertexts of the same modulus, returning an
+    /// encryption of 1 if m1 < m2 and of 0 otherwise. Both messages must lie in
+    /// the lower half of the plaintext space.
+    pub fn eval_compare_large(
+        &self,
+        ct1: &LweCiphertext,
+        ct2: &LweCiphertext,
+    ) -> FheResult<LweCiphertext> {
+        self.check_large_ciphertext(ct1)?;
+        self.check_large_ciphertext(ct2)?;
+        if ct1.modulus() != ct2.modulus() {
+            return Err(Error::InvalidCiphertext(format!(
+                "cannot compare ciphertexts modulo {} and {}",
+                ct1.modulus(),
+                ct2.modulus()
+            )));
+        }
+        let mut diff = ct1.clone();
+        diff.sub_assign(ct2);
+        self.eval_sign(&diff)
+    }
+
+    fn bootstrapping_key(&self) -> FheResult<&BootstrappingKey> {
+        self.bt_key.as_ref().ok_or(Error::MissingBootstrappingKey)
+    }
+
+    /// A generator of its own for an operation, keyed from that of the
+    /// context, so that the lock of the latter is not held while the operation
+    /// samples, be it for a whole bootstrapping key
+    fn rng(&self) -> ChaCha20Rng {
+        self.rng.fork_one()
+    }
+
+    fn check_key(&self, sk: &LwePrivateKey) -> FheResult<()> {
+        if sk.dim() != self.params.lwe().n() {
+            return Err(Error::InvalidKey(format!(
+                "expected an LWE key of dimension {}, got {}",
+                self.params.lwe().n(),
+                sk.dim()
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_ciphertext(&self, ct: &LweCiphertext) -> FheResult<()> {
+        let lwe = self.params.lwe();
+        if ct.dim() != lwe.n() || ct.modulus() != lwe.q() {
+            return Err(Error::InvalidCiphertext(format!(
+                "expected an LWE ciphertext of dimension {} modulo {}, got dimension {} modulo {}",
+                lwe.n(),
+                lwe.q(),
+                ct.dim(),
+                ct.modulus()
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_large_ciphertext(&self, ct: &LweCiphertext) -> FheResult<()> {
+        let lwe = self.params.lwe();
+        if ct.dim() != lwe.n() || self.check_modulus(ct.modulus()).is_err() {
+            return Err(Error::InvalidCiphertext(format!(
+                "expected an LWE ciphertext of dimension {} modulo a power of two in [{}, {}], got dimension {} modulo {}",
+                lwe.n(),
+                lwe.q(),
+                lwe.q_ks(),
+                ct.dim(),
+                ct.modulus()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Larger moduli would scale the key switching noise of the bootstrapped
+    /// values past the rounding margin
+    fn check_modulus(&self, modulus: u64) -> FheResult<()> {
+        let lwe = self.params.lwe();
+        if !modulus.is_power_of_two() || modulus < lwe.q() || modulus > lwe.q_ks() {
+            return Err(Error::InvalidParameters(format!(
+                "ciphertext modulus {modulus} must be a power of two in [{}, {}]",
+                lwe.q(),
+                lwe.q_ks()
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn check_plaintext_modulus(p: LwePlaintextModulus, modulus: u64) -> FheResult<()> {
+    if p < 2 || !modulus.is_multiple_of(p) {
+        return Err(Error::InvalidParameters(format!(
+            "plaintext modulus {p} must divide the ciphertext modulus {modulus}"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binfhe::{LweCryptoParams, RingGswCryptoParams};
+    use crate::fhe_core::last_prime;
+
+    const METHODS: [BinFheMethod; 3] =
+        [BinFheMethod::Ap, BinFheMethod::Ginx, BinFheMethod::Lmkcdey];
+
+    /// The toy parameters with the LWE modulus `q`
+    fn toy_with_q(method: BinFheMethod, q: u64) -> BinFheContext {
+        let big_q = last_prime(27, 1024).unwrap();
+        let lwe = LweCryptoParams::new(64, 512, q, big_q, 1 << 14, 3.19, 1 << 3).unwrap();
+        let rgsw = RingGswCryptoParams::new(&lwe, method, 1 << 9, 1 << 2, 10).unwrap();
+        BinFheContext::with_params(BinFheCryptoParams::new(lwe, rgsw))
+    }
+
+    #[test]
+    fn every_method_bootstraps_gates() {
+        for method in METHODS {
+            let mut cc = BinFheContext::with_method(BinFheParamSet::Toy, method).unwrap();
+            let sk = cc.key_gen();
+            cc.bt_key_gen(&sk).unwrap();
+            assert_eq!(u8::from(cc.bt_key().unwrap().method()), u8::from(method));
+            let bits = [cc.encrypt(&sk, 0).unwrap(), cc.encrypt(&sk, 1).unwrap()];
+            for gate in [BinGate::Nand, BinGate::Xor] {
+                for (a, b) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+                    let ct = cc.eval_bin_gate(gate, &bits[a], &bits[b]).unwrap();
+                    let m = cc.decrypt(&sk, &ct).unwrap();
+                    assert_eq!(m, truth(gate, a as u64, b as u64), "{method} {gate}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bootstrapping_keys_of_another_method_are_rejected() {
+        let mut ap = BinFheContext::with_method(BinFheParamSet::Toy, BinFheMethod::Ap).unwrap();
+        let sk = ap.key_gen();
+        ap.bt_key_gen(&sk).unwrap();
+        let key = ap.bt_key().unwrap().clone();
+        let mut ginx = BinFheContext::new(BinFheParamSet::Toy).unwrap();
+        assert!(matches!(
+            ginx.set_bt_key(key.clone()),
+            Err(Error::InvalidKey(_))
+        ));
+        let mut other = BinFheContext::with_method(BinFheParamSet::Toy, BinFheMethod::Ap).unwrap();
+        other.set_bt_key(key).unwrap();
+        let ct = other.encrypt(&sk, 1).unwrap();
+        let ct = other.eval_bin_gate(BinGate::And, &ct, &ct).unwrap();
+        assert_eq!(other.decrypt(&sk, &ct).unwrap(), 1);
+    }
+
+    /// Check `eval_func` on every entry of a negacyclic and of an arbitrary
+    /// table for the plaintext moduli 4 and 8
+    fn check_every_entry(mut cc: BinFheContext) {
+        cc.set_rng_seed([5; SEED_LEN]);
+        let sk = cc.key_gen();
+        cc.bt_key_gen(&sk).unwrap();
+        for p in [4, 8] {
+            let square = cc.generate_lut_via_function(|m, p| m * m % p, p).unwrap();
+            let negacyclic = cc
+                .generate_lut_via_function(|m, p| if m < p / 2 { m } else { p + p / 2 - m }, p)
+                .unwrap();
+            assert!(scheme::is_negacyclic(&negacyclic));
+            assert!(!scheme::is_negacyclic(&square));
+            for lut in [&negacyclic, &square] {
+                for m in 0..p {
+                    let ct = cc.encrypt_with_plaintext_modulus(&sk, m, p).unwrap();
+                    let out = cc.eval_func(&ct, lut).unwrap();
+                    let d = cc.decrypt_with_plaintext_modulus(&sk, &out, p).unwrap();
+                    assert_eq!(
+                        d,
+                        lut[m as usize],
+                        "{} p = {p} m = {m}",
+                        cc.params.rgsw().method()
+                    );
+                }
+            }
+        }
+    }
+
+    /// The plaintext of `gate` on the bits `a` and `b`
+    fn truth(gate: BinGate, a: u64, b: u64) -> u64 {
+        match gate {
+            BinGate::Or => a | b,
+            BinGate::And => a & b,
+            BinGate::Nor => 1 - (a | b),
+            BinGate::Nand => 1 - (a & b),
+            BinGate::Xor => a ^ b,
+            BinGate::Xnor => 1 - (a ^ b),
+        }
+    }
+
+    #[test]
+    fn gates_match_their_truth_tables() {
+        let mut cc = BinFheContext::new(BinFheParamSet::Toy).unwrap();
+        let sk = cc.key_gen();
+        cc.bt_key_gen(&sk).unwrap();
+        let bits = [cc.encrypt(&sk, 0).unwrap(), cc.encrypt(&sk, 1).unwrap()];
+        for gate in [
+            BinGate::Or,
+            BinGate::And,
+            BinGate::Nor,
+            BinGate::Nand,
+            BinGate::Xor,
+            BinGate::Xnor,
+        ] {
+            for (a, b) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+                let ct = cc.eval_bin_gate(gate, &bits[a], &bits[b]).unwrap();
+                let m = cc.decrypt(&sk, &ct).unwrap();
+                assert_eq!(m, truth(gate, a as u64, b as u64), "{gate} {a} {b}");
+            }
+        }
+        for m in 0..2 {
+            let ct = &bits[m as usize];
+            assert_eq!(cc.decrypt(&sk, &cc.eval_not(ct).unwrap()).unwrap(), 1 - m);
+            assert_eq!(cc.decrypt(&sk, &cc.bootstrap(ct).unwrap()).unwrap(), m);
+            let mux = cc.eval_mux(ct, &bits[0], &bits[1]).unwrap();
+            assert_eq!(cc.decrypt(&sk, &mux).unwrap(), 1 - m);
+        }
+    }
+
+    #[test]
+    fn invalid_inputs_are_rejected() {
+        let cc = BinFheContext::new(BinFheParamSet::Toy).unwrap();
+        let sk = cc.key_gen();
+        assert!(matches!(
+            cc.encrypt(&sk, 2),
+            Err(Error::InvalidPlaintext(2))
+        ));
+        let ct = cc.encrypt(&sk, 1).unwrap();
+        assert!(matches!(
+            cc.eval_bin_gate(BinGate::And, &ct, &ct),
+            Err(Error::MissingBootstrappingKey)
+        ));
+        let other = LwePrivateKey::new(vec![0; 3]);
+        assert!(matches!(cc.encrypt(&other, 0), Err(Error::InvalidKey(_))));
+        assert!(matches!(cc.decrypt(&other, &ct), Err(Error::InvalidKey(_))));
+    }
+
+    #[test]
+    fn seeded_contexts_repeat() {
+        let run = || {
+            let mut cc = BinFheContext::new(BinFheParamSet::Toy).unwrap();
+            cc.set_rng_seed([8; SEED_LEN]);
+            let sk = cc.key_gen();
+            (cc.encrypt(&sk, 1).unwrap(), cc.encrypt(&sk, 1).unwrap())
+        };
+        let (a1, a2) = run();
+        let (b1, b2) = run();
+        assert_eq!((&a1, &a2), (&b1, &b2));
+        assert_ne!(a1, a2);
+    }
+
+    #[test]
+    fn eval_func_every_entry() {
+        check_every_entry(toy_with_q(BinFheMethod::Ap, 512));
+        check_every_entry(toy_with_q(BinFheMethod::Ginx, 512));
+        check_every_entry(toy_with_q(BinFheMethod::Lmkcdey, 256));
+    }
+
+    #[test]
+    fn arbitrary_functions_need_2q_dividing_n_with_lmkcdey() {
+        for method in METHODS {
+            let mut cc = BinFheContext::with_method(BinFheParamSet::Toy, method).unwrap();
+            let sk = cc.key_gen();
+            cc.bt_key_gen(&sk).unwrap();
+            let square = cc.generate_lut_via_function(|m, p| m * m % p, 4).unwrap();
+            let ct = cc.encrypt_with_plaintext_modulus(&sk, 3, 4).unwrap();
+            let out = cc.eval_func(&ct, &square);
+            if matches!(method, BinFheMethod::Lmkcdey) {
+                assert!(matches!(out, Err(Error::InvalidParameters(_))));
+            } else {
+                let d = cc
+                    .decrypt_with_plaintext_modulus(&sk, &out.unwrap(), 4)
+                    .unwrap();
+                assert_eq!(d, 1);
+            }
+        }
+    }
+
+    #[test]
+    fn large_precision_sign_floor_and_compare() {
+        let mut cc = BinFheContext::new(BinFheParamSet::Toy).unwrap();
+        cc.set_rng_seed([6; SEED_LEN]);
+        let sk = cc.key_gen();
+        cc.bt_key_gen(&sk).unwrap();
+        let modulus = 4 * cc.params.lwe().q();
+        let p = cc.max_plaintext_space(modulus);
+        let cts: Vec<_> = (0..p)
+            .map(|m| cc.encrypt_with_modulus(&sk, m, p, modulus).unwrap())
+            .collect();
+        for (m, ct) in cts.iter().enumerate() {
+            let m = m as u64;
+            let sign = cc.eval_sign(ct).unwrap();
+            assert_eq!(
+                cc.decrypt(&sk, &sign).unwrap(),
+                (m >= p / 2) as u64,
+                "sign {m}"
+            );
+            let floor = cc.eval_floor(ct, 1).unwrap();
+            let d = cc.decrypt_with_plaintext_modulus(&sk, &floor, p).unwrap();
+            assert_eq!(d, m & !1, "floor {m}");
+        }
+        for (a, b) in [(3, 5), (5, 3), (7, 7), (0, p as usize / 2 - 1)] {
+            let lt = cc.eval_compare_large(&cts[a], &cts[b]).unwrap();
+            assert_eq!(cc.decrypt(&sk, &lt).unwrap(), (a < b) as u64, "{a} < {b}");
+        }
+    }
+
+    #[test]
+    fn invalid_functional_inputs_are_rejected() {
+        let cc = BinFheContext::new(BinFheParamSet::Toy).unwrap();
+        let sk = cc.key_gen();
+        let q = cc.params.lwe().q();
+        for modulus in [q / 2, 3 * q, 1 << 20] {
+            assert!(matches!(
+                cc.encrypt_with_modulus(&sk, 0, 4, modulus),
+                Err(Error::InvalidParameters(_))
+            ));
+        }
+        assert!(matches!(
+            cc.encrypt_with_plaintext_modulus(&sk, 0, 3),
+            Err(Error::InvalidParameters(_))
+        ));
+        let ct = cc.encrypt_with_plaintext_modulus(&sk, 1, 4).unwrap();
+        assert!(matches!(
+            cc.eval_func(&ct, &[0, 1, 2, 4]),
+            Err(Error::InvalidPlaintext(4))
+        ));
+        assert!(matches!(
+            cc.eval_floor(&ct, 10),
+            Err(Error::InvalidParameters(_))
+        ));
+        let large = cc.encrypt_with_modulus(&sk, 1, 4, 2 * q).unwrap();
+        assert!(matches!(
+            cc.eval_compare_large(&ct, &large),
+            Err(Error::InvalidCiphertext(_))
+        ));
+    }
+}
@@ -0,0 +1,259 @@
+use crate::binfhe::LweCryptoParams;
+use crate::fhe_core::{
+    sample_ternary, sample_uniform, signed_digits, DiscreteGaussianGenerator, Modulus,
+};
+use rand_core::CryptoRngCore;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Debug, Formatter};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// Plaintext of an LWE ciphertext
+pub type LwePlaintext = u64;
+
+/// Plaintext modulus of an LWE ciphertext
+pub type LwePlaintextModulus = u64;
+
+/// An LWE secret key with small signed coefficients
+#[derive(Clone, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+pub struct LwePrivateKey {
+    s: Vec<i64>,
+}
+
+impl Debug for LwePrivateKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LwePrivateKey")
+            .field("dim", &self.s.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl LwePrivateKey {
+    /// Create a key from its coefficients
+    pub fn new(s: Vec<i64>) -> Self {
+        Self { s }
+    }
+
+    /// Sample a uniform ternary key of dimension `n` from `rng`
+    pub fn generate<R: CryptoRngCore + ?Sized>(n: usize, rng: &mut R) -> Self {
+        Self::new(sample_ternary(n, rng))
+    }
+
+    /// The dimension of the key
+    pub fn dim(&self) -> usize {
+        self.s.len()
+    }
+
+    pub(crate) fn coefficients(&self) -> &[i64] {
+        &self.s
+    }
+}
+
+/// An LWE ciphertext (a, b) with b = <a, s> + m + e mod q
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LweCiphertext {
+    a: Vec<u64>,
+    b: u64,
+    modulus: Modulus,
+}
+
+impl LweCiphertext {
+    pub(crate) fn new(a: Vec<u64>, b: u64, modulus: Modulus) -> Self {
+        Self { a, b, modulus }
+    }
+
+    /// The vector part of the ciphertext
+    pub fn a(&self) -> &[u64] {
+        &self.a
+    }
+
+    /// The scalar part of the ciphertext
+    pub fn b(&self) -> u64 {
+        self.b
+    }
+
+    /// The ciphertext modulus
+    pub fn modulus(&self) -> u64 {
+        self.modulus.value()
+    }
+
+    /// The lattice dimension of the ciphertext
+    pub fn dim(&self) -> usize {
+        self.a.len()
+    }
+
+    pub(crate) fn add_assign(&mut self, other: &Self) {
+        let q = self.modulus;
+        for (x, y) in self.a.iter_mut().zip(&other.a) {
+            *x = q.add(*x, *y);
+        }
+        self.b = q.add(self.b, other.b);
+    }
+
+    pub(crate) fn sub_assign(&mut self, other: &Self) {
+        let q = self.modulus;
+        for (x, y) in self.a.iter_mut().zip(&other.a) {
+            *x = q.sub(*x, *y);
+        }
+        self.b = q.sub(self.b, other.b);
+    }
+
+    pub(crate) fn neg_assign(&mut self) {
+        let q = self.modulus;
+        for x in self.a.iter_mut() {
+            *x = q.neg(*x);
+        }
+        self.b = q.neg(self.b);
+    }
+
+    pub(crate) fn mul_scalar_assign(&mut self, c: u64) {
+        let q = self.modulus;
+        let c = q.reduce(c);
+        for x in self.a.iter_mut() {
+            *x = q.mul(*x, c);
+        }
+        self.b = q.mul(self.b, c);
+    }
+
+    pub(crate) fn add_constant_assign(&mut self, c: u64) {
+        self.b = self.modulus.add(self.b, self.modulus.reduce(c));
+    }
+
+    /// Reinterpret the ciphertext modulo `to`, which must divide or be a
+    /// multiple of the current modulus
+    pub(crate) fn reinterpret(&self, to: &Modulus) -> Self {
+        Self::new(
+            self.a.iter().map(|&x| to.reduce(x)).collect(),
+            to.reduce(self.b),
+            *to,
+        )
+    }
+
+    /// Switch the ciphertext to the modulus `to` by scaling and rounding
+    pub(crate) fn mod_switch(&self, to: &Modulus) -> Self {
+        let from = self.modulus.value() as u128;
+        let scale = |x: u64| ((x as u128 * to.value() as u128 + (from >> 1)) / from) as u64;
+        Self::new(
+            self.a.iter().map(|&x| to.reduce(scale(x))).collect(),
+            to.reduce(scale(self.b)),
+            *to,
+        )
+    }
+
+    /// The noisy plaintext b - <a, s> mod q
+    pub(crate) fn phase(&self, sk: &LwePrivateKey) -> u64 {
+        self.modulus
+            .sub(self.b, dot(&self.a, sk.coefficients(), &self.modulus))
+    }
+}
+
+/// Encrypt `m` in Z_p under `sk` with ciphertext modulus `q`
+pub(crate) fn encrypt(
+    params: &LweCryptoParams,
+    sk: &LwePrivateKey,
+    m: LwePlaintext,
+    p: LwePlaintextModulus,
+    q: &Modulus,
+    rng: &mut dyn CryptoRngCore,
+) -> LweCiphertext {
+    let dgg = DiscreteGaussianGenerator::new(params.std_dev());
+    let a = sample_uniform(sk.dim(), q, rng);
+    let delta = q.value() / p;
+    let b = q.add(
+        q.add(dot(&a, sk.coefficients(), q), q.reduce_i64(dgg.sample(rng))),
+        q.reduce((m % p) * delta),
+    );
+    LweCiphertext::new(a, b, *q)
+}
+
+/// Decrypt `ct` under `sk` into Z_p by rounding its phase
+pub(crate) fn decrypt(
+    sk: &LwePrivateKey,
+    ct: &LweCiphertext,
+    p: LwePlaintextModulus,
+) -> LwePlaintext {
+    let q = ct.modulus() as u128;
+    let phase = ct.phase(sk) as u128;
+    (((phase * p as u128 + (q >> 1)) / q) % p as u128) as u64
+}
+
+/// <a, s> mod q for a small signed vector s
+fn dot(a: &[u64], s: &[i64], q: &Modulus) -> u64 {
+    let sum: i128 = a.iter().zip(s).map(|(&x, &y)| x as i128 * y as i128).sum();
+    sum.rem_euclid(q.value() as i128) as u64
+}
+
+/// Key switching key from an LWE key of dimension N to one of dimension n,
+/// holding encryptions of v * z_i * B^j for every non-zero digit magnitude v
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LweSwitchingKey {
+    keys: Vec<LweCiphertext>,
+    digits: usize,
+    half_base: usize,
+}
+
+impl LweSwitchingKey {
+    /// Generate the key switching from `sk_from` to `sk_to` modulo Q_ks
+    pub(crate) fn generate(
+        params: &LweCryptoParams,
+        sk_from: &LwePrivateKey,
+        sk_to: &LwePrivateKey,
+        rng: &mut dyn CryptoRngCore,
+    ) -> Self {
+        let q_ks = params.q_ks_modulus();
+        let digits = params.digits_ks();
+        let base = params.base_ks();
+        let half_base = (base >> 1) as usize;
+        let dgg = DiscreteGaussianGenerator::new(params.std_dev());
+
+        let mut keys = Vec::with_capacity(sk_from.dim() * digits * half_base);
+        for &z in sk_from.coefficients() {
+            let mut power = q_ks.reduce_i64(z);
+            for _ in 0..digits {
+                for v in 1..=half_base as u64 {
+                    let a = sample_uniform(sk_to.dim(), q_ks, rng);
+                    let b = q_ks.add(
+                        q_ks.add(
+                            dot(&a, sk_to.coefficients(), q_ks),
+                            q_ks.reduce_i64(dgg.sample(rng)),
+                        ),
+                        q_ks.mul(power, v),
+                    );
+                    keys.push(LweCiphertext::new(a, b, *q_ks));
+                }
+                power = q_ks.mul(power, base);
+            }
+        }
+
+        Self {
+            keys,
+            digits,
+            half_base,
+        }
+    }
+
+    /// Switch `ct`, a ciphertext modulo Q_ks under the source key, to the
+    /// target key
+    pub(crate) fn key_switch(&self, params: &LweCryptoParams, ct: &LweCiphertext) -> LweCiphertext {
+        let q_ks = params.q_ks_modulus();
+        let log_base = params.base_ks().trailing_zeros();
+        let mut out = LweCiphertext::new(vec![0; params.n()], ct.b, *q_ks);
+        let mut digits = vec![0; self.digits];
+
+        for (i, &a) in ct.a.iter().enumerate() {
+            signed_digits(q_ks.center(a), log_base, &mut digits);
+            for (j, &d) in digits.iter().enumerate() {
+                if d == 0 {
+                    continue;
+                }
+                let v = d.unsigned_abs() as usize;
+                let key = &self.keys[(i * self.digits + j) * self.half_base + v - 1];
+                if d > 0 {
+                    out.sub_assign(key);
+                } else {
+                    out.add_assign(key);
+                }
+            }
+        }
+        out
+    }
+}
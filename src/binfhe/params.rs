@@ -0,0 +1,427 @@
+use crate::binfhe::{BinFheMethod, BinFheParamSet};
+use crate::fhe_core::{last_prime, Modulus, NttTables};
+use crate::{Error, FheResult};
+
+/// Parameters of the LWE layer of FHEW/TFHE
+#[derive(Debug, Clone, Copy)]
+pub struct LweCryptoParams {
+    n: usize,
+    ring_dim: usize,
+    q: Modulus,
+    big_q: Modulus,
+    q_ks: Modulus,
+    std_dev: f64,
+    base_ks: u64,
+    digits_ks: usize,
+}
+
+impl LweCryptoParams {
+    /// Create new LWE parameters
+    ///
+    /// * `n` - lattice dimension of the LWE ciphertexts
+    /// * `ring_dim` - ring dimension N of the RLWE accumulator
+    /// * `q` - modulus of the LWE ciphertexts, must divide 2N
+    /// * `big_q` - modulus of the RLWE accumulator, larger than q and below
+    ///   2^62
+    /// * `q_ks` - modulus used for key switching, in [2, 2^62)
+    /// * `std_dev` - standard deviation of the error distribution
+    /// * `base_ks` - base for the key switching gadget, a power of two
+    pub fn new(
+        n: usize,
+        ring_dim: usize,
+        q: u64,
+        big_q: u64,
+        q_ks: u64,
+        std_dev: f64,
+        base_ks: u64,
+    ) -> FheResult<Self> {
+        if n == 0 || !ring_dim.is_power_of_two() {
+            return Err(Error::InvalidParameters(format!(
+                "invalid lattice dimension {} or ring dimension {}",
+                n, ring_dim
+            )));
+        }
+        if q < 8 || !(2 * ring_dim as u64).is_multiple_of(q) {
+            return Err(Error::InvalidParameters(format!(
+                "LWE modulus {} must divide 2N = {}",
+                q,
+                2 * ring_dim
+            )));
+        }
+        if base_ks < 2 || !base_ks.is_power_of_two() {
+            return Err(Error::InvalidParameters(format!(
+                "key switching base {} must be a power of two",
+                base_ks
+            )));
+        }
+        // the moduli leave two bits of the word free for lazy reductions
+        if big_q <= q || big_q >= 1 << 62 {
+            return Err(Error::InvalidParameters(format!(
+                "accumulator modulus {} must lie in (q, 2^62) = ({}, 2^62)",
+                big_q, q
+            )));
+        }
+        if !(2..1 << 62).contains(&q_ks) {
+            return Err(Error::InvalidParameters(format!(
+                "key switching modulus {} must lie in [2, 2^62)",
+                q_ks
+            )));
+        }
+        let digits_ks = bits(q_ks - 1).div_ceil(base_ks.trailing_zeros() as usize);
+
+        Ok(Self {
+            n,
+            ring_dim,
+            q: Modulus::new(q),
+            big_q: Modulus::new(big_q),
+            q_ks: Modulus::new(q_ks),
+            std_dev,
+            base_ks,
+            digits_ks,
+        })
+    }
+
+    /// Lattice dimension n of the LWE ciphertexts
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Ring dimension N of the RLWE accumulator
+    pub fn ring_dim(&self) -> usize {
+        self.ring_dim
+    }
+
+    /// Modulus q of the LWE ciphertexts
+    pub fn q(&self) -> u64 {
+        self.q.value()
+    }
+
+    /// Modulus Q of the RLWE accumulator
+    pub fn big_q(&self) -> u64 {
+        self.big_q.value()
+    }
+
+    /// Modulus used for key switching
+    pub fn q_ks(&self) -> u64 {
+        self.q_ks.value()
+    }
+
+    /// Standard deviation of the error distribution
+    pub fn std_dev(&self) -> f64 {
+        self.std_dev
+    }
+
+    /// Base of the key switching gadget
+    pub fn base_ks(&self) -> u64 {
+        self.base_ks
+    }
+
+    /// Number of digits of the key switching gadget
+    pub fn digits_ks(&self) -> usize {
+        self.digits_ks
+    }
+
+    pub(crate) fn q_modulus(&self) -> &Modulus {
+        &self.q
+    }
+
+    pub(crate) fn big_q_modulus(&self) -> &Modulus {
+        &self.big_q
+    }
+
+    pub(crate) fn q_ks_modulus(&self) -> &Modulus {
+        &self.q_ks
+    }
+}
+
+/// Parameters of the RingGSW accumulator used for blind rotation
+#[derive(Debug, Clone)]
+pub struct RingGswCryptoParams {
+    method: BinFheMethod,
+    base_g: u64,
+    digits_g: usize,
+    base_r: u64,
+    digits_r: usize,
+    num_auto_keys: usize,
+    gadget: Vec<u64>,
+    ntt: NttTables,
+    // NTT of X^k - 1 for k in [0, 2N)
+    monomials: Vec<Vec<u64>>,
+    // for odd k in Z_2N, (sign, l) such that k = sign * g^l
+    log_gen: Vec<(bool, usize)>,
+}
+
+impl RingGswCryptoParams {
+    /// Generator of Z_2N^* / {1, -1} used by [`BinFheMethod::Lmkcdey`]
+    pub const GENERATOR: u64 = 5;
+
+    /// Create new accumulator parameters for the modulus and ring dimension of
+    /// `lwe`
+    ///
+    /// * `method` - blind rotation method
+    /// * `base_g` - base of the gadget decomposition, a power of two
+    /// * `base_r` - base used to decompose the LWE vector for
+    ///   [`BinFheMethod::Ap`], a power of two
+    /// * `num_auto_keys` - number of automorphism keys g^1, ..., g^w for
+    ///   [`BinFheMethod::Lmkcdey`]
+    pub fn new(
+        lwe: &LweCryptoParams,
+        method: BinFheMethod,
+        base_g: u64,
+        base_r: u64,
+        num_auto_keys: usize,
+    ) -> FheResult<Self> {
+        if base_g < 2 || !base_g.is_power_of_two() || base_r < 2 || !base_r.is_power_of_two() {
+            return Err(Error::InvalidParameters(format!(
+                "gadget base {} and refreshing base {} must be powers of two",
+                base_g, base_r
+            )));
+        }
+        let big_q = *lwe.big_q_modulus();
+        let ring_dim = lwe.ring_dim();
+        let two_n = 2 * ring_dim as u64;
+        if matches!(method, BinFheMethod::Lmkcdey)
+            && (num_auto_keys == 0 || !(ring_dim as u64).is_multiple_of(lwe.q()))
+        {
+            return Err(Error::InvalidParameters(format!(
+                "LMKCDEY needs automorphism keys and an LWE modulus dividing N = {}",
+                ring_dim
+            )));
+        }
+        let ntt = NttTables::new(ring_dim, big_q).ok_or_else(|| {
+            Error::InvalidParameters(format!(
+                "modulus {} does not support an NTT of size {}",
+                big_q.value(),
+                ring_dim
+            ))
+        })?;
+
+        let digits_g = bits(big_q.value() - 1).div_ceil(base_g.trailing_zeros() as usize);
+        let digits_r = bits(two_n - 1).div_ceil(base_r.trailing_zeros() as usize);
+        let gadget = (0..digits_g).map(|i| big_q.pow(base_g, i as u64)).collect();
+
+        let monomials = (0..2 * ring_dim)
+            .map(|k| {
+                let mut poly = vec![0; ring_dim];
+                poly[0] = big_q.value() - 1;
+                if k < ring_dim {
+                    poly[k] = big_q.add(poly[k], 1);
+                } else {
+                    poly[k - ring_dim] = big_q.sub(poly[k - ring_dim], 1);
+                }
+                ntt.forward(&mut poly);
+                poly
+            })
+            .collect();
+
+        let mut log_gen = vec![(true, 0); 2 * ring_dim];
+        let mut power = 1;
+        for l in 0..ring_dim / 2 {
+            log_gen[power as usize] = (true, l);
+            log_gen[(two_n - power) as usize] = (false, l);
+            power = power * Self::GENERATOR % two_n;
+        }
+
+        Ok(Self {
+            method,
+            base_g,
+            digits_g,
+            base_r,
+            digits_r,
+            num_auto_keys,
+            gadget,
+            ntt,
+            monomials,
+            log_gen,
+        })
+    }
+
+    /// Blind rotation method
+    pub fn method(&self) -> BinFheMethod {
+        self.method
+    }
+
+    /// Base of the gadget decomposition
+    pub fn base_g(&self) -> u64 {
+        self.base_g
+    }
+
+    /// Number of digits of the gadget decomposition
+    pub fn digits_g(&self) -> usize {
+        self.digits_g
+    }
+
+    /// Base used to decompose the LWE vector for [`BinFheMethod::Ap`]
+    pub fn base_r(&self) -> u64 {
+        self.base_r
+    }
+
+    /// Number of digits of the refreshing decomposition
+    pub fn digits_r(&self) -> usize {
+        self.digits_r
+    }
+
+    /// Number of automorphism keys for [`BinFheMethod::Lmkcdey`]
+    pub fn num_auto_keys(&self) -> usize {
+        self.num_auto_keys
+    }
+
+    pub(crate) fn gadget(&self) -> &[u64] {
+        &self.gadget
+    }
+
+    pub(crate) fn ntt(&self) -> &NttTables {
+        &self.ntt
+    }
+
+    /// NTT of X^k - 1, `k` taken modulo 2N
+    pub(crate) fn monomial(&self, k: usize) -> &[u64] {
+        &self.monomials[k % self.monomials.len()]
+    }
+
+    /// (sign, l) such that the odd `k` equals sign * g^l modulo 2N
+    pub(crate) fn log_gen(&self, k: usize) -> (bool, usize) {
+        self.log_gen[k % self.log_gen.len()]
+    }
+}
+
+/// All parameters of the FHEW/TFHE scheme
+#[derive(Debug, Clone)]
+pub struct BinFheCryptoParams {
+    lwe: LweCryptoParams,
+    rgsw: RingGswCryptoParams,
+}
+
+impl BinFheCryptoParams {
+    /// Create parameters from their LWE and RingGSW parts
+    pub fn new(lwe: LweCryptoParams, rgsw: RingGswCryptoParams) -> Self {
+        Self { lwe, rgsw }
+    }
+
+    /// Create the parameters of a predefined parameter set for the blind
+    /// rotation method `method`
+    pub fn from_param_set(set: BinFheParamSet, method: BinFheMethod) -> FheResult<Self> {
+        const BASE_R: u64 = 1 << 2;
+        const NUM_AUTO_KEYS: usize = 10;
+
+        // (log2 Q, N, n, q, Q_ks, std dev, base_ks, base_g)
+        let (q_bits, ring_dim, n, q, q_ks, std_dev, base_ks, base_g) = match set {
+            BinFheParamSet::Toy => (27, 512, 64, 512, 1 << 14, 3.19, 1 << 3, 1 << 9),
+            BinFheParamSet::Medium => (28, 1024, 422, 1024, 1 << 14, 3.19, 1 << 3, 1 << 7),
+            BinFheParamSet::Std128 => (27, 1024, 512, 1024, 1 << 14, 3.19, 1 << 3, 1 << 7),
+        };
+        let big_q = last_prime(q_bits, 2 * ring_dim as u64).ok_or_else(|| {
+            Error::InvalidParameters(format!("no NTT friendly prime of {} bits", q_bits))
+        })?;
+        let lwe = LweCryptoParams::new(n, ring_dim, q, big_q, q_ks, std_dev, base_ks)?;
+        let rgsw = RingGswCryptoParams::new(&lwe, method, base_g, BASE_R, NUM_AUTO_KEYS)?;
+        Ok(Self::new(lwe, rgsw))
+    }
+
+    /// The LWE parameters
+    pub fn lwe(&self) -> &LweCryptoParams {
+        &self.lwe
+    }
+
+    /// The RingGSW parameters
+    pub fn rgsw(&self) -> &RingGswCryptoParams {
+        &self.rgsw
+    }
+}
+
+/// Number of bits needed to represent `x`
+fn bits(x: u64) -> usize {
+    (u64::BITS - x.leading_zeros()) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lwe(q: u64) -> LweCryptoParams {
+        let big_q = last_prime(27, 1024).unwrap();
+        LweCryptoParams::new(64, 512, q, big_q, 1 << 14, 3.19, 1 << 3).unwrap()
+    }
+
+    #[test]
+    fn lmkcdey_needs_automorphism_keys_and_q_dividing_n() {
+        let new = |q, num_auto_keys| {
+            RingGswCryptoParams::new(
+                &lwe(q),
+                BinFheMethod::Lmkcdey,
+                1 << 9,
+                1 << 2,
+                num_auto_keys,
+            )
+        };
+        assert!(new(512, 10).is_ok());
+        assert!(matches!(new(512, 0), Err(Error::InvalidParameters(_))));
+        assert!(matches!(new(1024, 10), Err(Error::InvalidParameters(_))));
+        for method in [BinFheMethod::Ap, BinFheMethod::Ginx] {
+            assert!(RingGswCryptoParams::new(&lwe(1024), method, 1 << 9, 1 << 2, 0).is_ok());
+        }
+    }
+
+    #[test]
+    fn invalid_lwe_parameters_are_rejected() {
+        let big_q = last_prime(27, 1024).unwrap();
+        for (ring_dim, q, base_ks) in [(500, 512, 8), (512, 768, 8), (512, 2048, 8), (512, 512, 6)]
+        {
+            assert!(matches!(
+                LweCryptoParams::new(64, ring_dim, q, big_q, 1 << 14, 3.19, base_ks),
+                Err(Error::InvalidParameters(_))
+            ));
+        }
+        for (big_q, q_ks) in [
+            (0, 1 << 14),
+            (1, 1 << 14),
+            (512, 1 << 14),
+            (1 << 62, 1 << 14),
+            (u64::MAX, 1 << 14),
+            (big_q, 0),
+            (big_q, 1),
+            (big_q, 1 << 62),
+            (big_q, u64::MAX),
+        ] {
+            assert!(matches!(
+                LweCryptoParams::new(64, 512, 512, big_q, q_ks, 3.19, 8),
+                Err(Error::InvalidParameters(_))
+            ));
+        }
+        // power of two key switching moduli are the usual choice
+        for q_ks in [2, 1 << 14, (1 << 62) - 1] {
+            let lwe = LweCryptoParams::new(64, 512, 512, big_q, q_ks, 3.19, 8).unwrap();
+            assert_eq!(lwe.q_ks(), q_ks);
+        }
+    }
+
+    #[test]
+    fn odd_exponents_are_signed_powers_of_the_generator() {
+        let rgsw =
+            RingGswCryptoParams::new(&lwe(512), BinFheMethod::Lmkcdey, 1 << 9, 1 << 2, 10).unwrap();
+        let two_n = 1024;
+        for k in (1..two_n).step_by(2) {
+            let (positive, l) = rgsw.log_gen(k);
+            let mut power = 1;
+            for _ in 0..l {
+                power = power * RingGswCryptoParams::GENERATOR as usize % two_n;
+            }
+            let expected = if positive { power } else { two_n - power };
+            assert_eq!(expected, k);
+        }
+    }
+
+    #[test]
+    fn predefined_sets_build_for_every_method() {
+        for set in [
+            BinFheParamSet::Toy,
+            BinFheParamSet::Medium,
+            BinFheParamSet::Std128,
+        ] {
+            for method in [BinFheMethod::Ap, BinFheMethod::Ginx, BinFheMethod::Lmkcdey] {
+                let params = BinFheCryptoParams::from_param_set(set, method).unwrap();
+                assert_eq!(u8::from(params.rgsw().method()), u8::from(method));
+            }
+        }
+    }
+}
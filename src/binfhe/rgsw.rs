@@ -0,0 +1,285 @@
+use crate::binfhe::{BinFheCryptoParams, LweCiphertext, LwePrivateKey, RingGswCryptoParams};
+use crate::fhe_core::{sample_uniform, signed_digits, DiscreteGaussianGenerator, Modulus};
+use rand_core::CryptoRngCore;
+use serde::{Deserialize, Serialize};
+
+/// An RLWE ciphertext (a, b) with b = a * z + m + e
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RlweCiphertext {
+    a: Vec<u64>,
+    b: Vec<u64>,
+}
+
+impl RlweCiphertext {
+    /// The trivial encryption (0, m) of the coefficient form polynomial `m`
+    pub(crate) fn trivial(m: Vec<u64>) -> Self {
+        Self {
+            a: vec![0; m.len()],
+            b: m,
+        }
+    }
+
+    /// Extract the LWE encryption of the constant coefficient under the key
+    /// formed by the coefficients of z, the ciphertext must be in coefficient form
+    pub(crate) fn extract_lwe(&self, params: &BinFheCryptoParams) -> LweCiphertext {
+        let q = params.lwe().big_q_modulus();
+        let n = self.a.len();
+        let a = (0..n)
+            .map(|j| {
+                if j == 0 {
+                    self.a[0]
+                } else {
+                    q.neg(self.a[n - j])
+                }
+            })
+            .collect();
+        LweCiphertext::new(a, self.b[0], *q)
+    }
+
+    /// Apply X -> X^t to both coefficient form components, the result is
+    /// encrypted under z(X^t)
+    pub(crate) fn automorphism(&self, t: usize, q: &Modulus) -> Self {
+        Self {
+            a: automorphism(&self.a, t, q),
+            b: automorphism(&self.b, t, q),
+        }
+    }
+
+    /// acc <- acc ⊡ key for a coefficient form accumulator
+    pub(crate) fn external_product_assign(
+        &mut self,
+        rgsw: &RingGswCryptoParams,
+        key: &RingGswCiphertext,
+    ) {
+        let dct = decompose(rgsw, self);
+        let [mut a, mut b] = mul_digits(rgsw, &dct, key);
+        rgsw.ntt().inverse(&mut a);
+        rgsw.ntt().inverse(&mut b);
+        self.a = a;
+        self.b = b;
+    }
+
+    /// Add the NTT form polynomials (a, b) to the coefficient form accumulator
+    pub(crate) fn add_ntt_assign(&mut self, rgsw: &RingGswCryptoParams, sum: [Vec<u64>; 2]) {
+        let ntt = rgsw.ntt();
+        let q = ntt.modulus();
+        for (target, mut poly) in [&mut self.a, &mut self.b].into_iter().zip(sum) {
+            ntt.inverse(&mut poly);
+            for (t, p) in target.iter_mut().zip(poly) {
+                *t = q.add(*t, p);
+            }
+        }
+    }
+}
+
+/// A RingGSW ciphertext, made of 2 * d_g RLWE rows in NTT form: the first d_g rows
+/// add m * B_g^j to the a part, the last d_g rows add it to the b part
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RingGswCiphertext {
+    rows: Vec<RlweCiphertext>,
+}
+
+impl RingGswCiphertext {
+    /// Encrypt the NTT form polynomial `m` under the NTT form RLWE secret `z_ntt`
+    pub(crate) fn encrypt(
+        params: &BinFheCryptoParams,
+        z_ntt: &[u64],
+        m: &[u64],
+        rng: &mut dyn CryptoRngCore,
+    ) -> Self {
+        let rgsw = params.rgsw();
+        let q = params.lwe().big_q_modulus();
+        let digits = rgsw.digits_g();
+
+        let rows = (0..2 * digits)
+            .map(|r| {
+                let RlweCiphertext { mut a, mut b } = encrypt_zero(params, z_ntt, rng);
+                let g = rgsw.gadget()[r % digits];
+                let target = if r < digits { &mut a } else { &mut b };
+                for (x, &m) in target.iter_mut().zip(m) {
+                    *x = q.add(*x, q.mul(m, g));
+                }
+                RlweCiphertext { a, b }
+            })
+            .collect();
+
+        Self { rows }
+    }
+
+    /// Encrypt the constant `m`, whose NTT is `m` in every slot
+    pub(crate) fn encrypt_constant(
+        params: &BinFheCryptoParams,
+        z_ntt: &[u64],
+        m: u64,
+        rng: &mut dyn CryptoRngCore,
+    ) -> Self {
+        Self::encrypt(params, z_ntt, &vec![m; params.lwe().ring_dim()], rng)
+    }
+
+    /// Encrypt the monomial X^k, `k` taken modulo 2N
+    pub(crate) fn encrypt_monomial(
+        params: &BinFheCryptoParams,
+        z_ntt: &[u64],
+        k: i64,
+        rng: &mut dyn CryptoRngCore,
+    ) -> Self {
+        let rgsw = params.rgsw();
+        let q = rgsw.ntt().modulus();
+        let k = k.rem_euclid(2 * params.lwe().ring_dim() as i64) as usize;
+        let m: Vec<u64> = rgsw.monomial(k).iter().map(|&x| q.add(x, 1)).collect();
+        Self::encrypt(params, z_ntt, &m, rng)
+    }
+}
+
+/// Key switching key from z(X^t) to z, used to evaluate the automorphism
+/// X -> X^t on an accumulator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutomorphismKey {
+    t: usize,
+    rows: Vec<RlweCiphertext>,
+}
+
+impl AutomorphismKey {
+    /// Generate the key for X -> X^t given the coefficients `z` of the RLWE secret
+    /// and its NTT form `z_ntt`
+    pub(crate) fn generate(
+        params: &BinFheCryptoParams,
+        z: &LwePrivateKey,
+        z_ntt: &[u64],
+        t: usize,
+        rng: &mut dyn CryptoRngCore,
+    ) -> Self {
+        let rgsw = params.rgsw();
+        let q = params.lwe().big_q_modulus();
+        let z_coefficients: Vec<u64> = z.coefficients().iter().map(|&x| q.reduce_i64(x)).collect();
+        let mut z_t = automorphism(&z_coefficients, t, q);
+        rgsw.ntt().forward(&mut z_t);
+
+        let rows = rgsw
+            .gadget()
+            .iter()
+            .map(|&g| {
+                let mut row = encrypt_zero(params, z_ntt, rng);
+                for (b, &z) in row.b.iter_mut().zip(&z_t) {
+                    *b = q.add(*b, q.mul(z, g));
+                }
+                row
+            })
+            .collect();
+
+        Self { t, rows }
+    }
+
+    /// The exponent t of the automorphism
+    pub fn exponent(&self) -> usize {
+        self.t
+    }
+
+    /// Apply the automorphism to a coefficient form accumulator
+    pub(crate) fn apply(&self, rgsw: &RingGswCryptoParams, acc: &mut RlweCiphertext) {
+        let ntt = rgsw.ntt();
+        let q = ntt.modulus();
+        let ring_dim = ntt.ring_dim();
+        let log_base = rgsw.base_g().trailing_zeros();
+
+        let RlweCiphertext { a, mut b } = acc.automorphism(self.t, q);
+        let mut dct = vec![vec![0; ring_dim]; rgsw.digits_g()];
+        let mut scratch = vec![0; rgsw.digits_g()];
+        for (k, &x) in a.iter().enumerate() {
+            signed_digits(q.center(x), log_base, &mut scratch);
+            for (j, &d) in scratch.iter().enumerate() {
+                dct[j][k] = q.reduce_i64(d);
+            }
+        }
+
+        let mut sum_a = vec![0; ring_dim];
+        ntt.forward(&mut b);
+        for (d, row) in dct.iter_mut().zip(&self.rows) {
+            ntt.forward(d);
+            for k in 0..ring_dim {
+                sum_a[k] = q.sub(sum_a[k], q.mul(d[k], row.a[k]));
+                b[k] = q.sub(b[k], q.mul(d[k], row.b[k]));
+            }
+        }
+        ntt.inverse(&mut sum_a);
+        ntt.inverse(&mut b);
+        acc.a = sum_a;
+        acc.b = b;
+    }
+}
+
+/// Fresh NTT form RLWE encryption of zero under `z_ntt`
+fn encrypt_zero(
+    params: &BinFheCryptoParams,
+    z_ntt: &[u64],
+    rng: &mut dyn CryptoRngCore,
+) -> RlweCiphertext {
+    let q = params.lwe().big_q_modulus();
+    let ring_dim = params.lwe().ring_dim();
+    let dgg = DiscreteGaussianGenerator::new(params.lwe().std_dev());
+
+    let a = sample_uniform(ring_dim, q, rng);
+    let mut b = dgg.sample_vec_mod(ring_dim, q, rng);
+    params.rgsw().ntt().forward(&mut b);
+    for ((b, &a), &z) in b.iter_mut().zip(&a).zip(z_ntt) {
+        *b = q.add(*b, q.mul(a, z));
+    }
+    RlweCiphertext { a, b }
+}
+
+/// Signed gadget decomposition of a coefficient form accumulator (a, b), one NTT
+/// form polynomial per digit, the digits of a first
+pub(crate) fn decompose(rgsw: &RingGswCryptoParams, acc: &RlweCiphertext) -> Vec<Vec<u64>> {
+    let ntt = rgsw.ntt();
+    let q = ntt.modulus();
+    let digits = rgsw.digits_g();
+    let log_base = rgsw.base_g().trailing_zeros();
+
+    let mut dct = vec![vec![0; ntt.ring_dim()]; 2 * digits];
+    let mut scratch = vec![0; digits];
+    for (offset, poly) in [(0, &acc.a), (digits, &acc.b)] {
+        for (k, &x) in poly.iter().enumerate() {
+            signed_digits(q.center(x), log_base, &mut scratch);
+            for (j, &d) in scratch.iter().enumerate() {
+                dct[offset + j][k] = q.reduce_i64(d);
+            }
+        }
+    }
+    for poly in dct.iter_mut() {
+        ntt.forward(poly);
+    }
+    dct
+}
+
+/// Inner product of the decomposed accumulator with the rows of `key`, in NTT form
+pub(crate) fn mul_digits(
+    rgsw: &RingGswCryptoParams,
+    dct: &[Vec<u64>],
+    key: &RingGswCiphertext,
+) -> [Vec<u64>; 2] {
+    let q = rgsw.ntt().modulus();
+    let ring_dim = rgsw.ntt().ring_dim();
+    let mut sum = [vec![0; ring_dim], vec![0; ring_dim]];
+    for (d, row) in dct.iter().zip(&key.rows) {
+        for k in 0..ring_dim {
+            sum[0][k] = q.add(sum[0][k], q.mul(d[k], row.a[k]));
+            sum[1][k] = q.add(sum[1][k], q.mul(d[k], row.b[k]));
+        }
+    }
+    sum
+}
+
+/// p(X^t) in Z_q[X]/(X^N + 1) for a coefficient form polynomial and odd t
+fn automorphism(p: &[u64], t: usize, q: &Modulus) -> Vec<u64> {
+    let ring_dim = p.len();
+    let mut out = vec![0; ring_dim];
+    for (i, &x) in p.iter().enumerate() {
+        let j = (i * t) % (2 * ring_dim);
+        if j < ring_dim {
+            out[j] = x;
+        } else {
+            out[j - ring_dim] = q.neg(x);
+        }
+    }
+    out
+}
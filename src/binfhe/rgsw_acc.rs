@@ -0,0 +1,69 @@
+use crate::binfhe::{BinFheCryptoParams, BinFheMethod, LwePrivateKey, RlweCiphertext};
+use rand_core::CryptoRngCore;
+use serde::{Deserialize, Serialize};
+
+mod ap;
+mod ginx;
+mod lmkcdey;
+
+pub use ap::*;
+pub use ginx::*;
+pub use lmkcdey::*;
+
+/// The blind rotation key of one of the supported [`BinFheMethod`]s
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum RingGswAccKey {
+    /// Key for [`BinFheMethod::Ap`]
+    Ap(ApAccKey),
+    /// Key for [`BinFheMethod::Ginx`]
+    Ginx(GinxAccKey),
+    /// Key for [`BinFheMethod::Lmkcdey`]
+    Lmkcdey(LmkcdeyAccKey),
+}
+
+impl RingGswAccKey {
+    /// Generate the key encrypting the LWE secret `sk` under the RLWE secret
+    /// whose coefficients are `z`, for the method of `params`
+    pub(crate) fn generate(
+        params: &BinFheCryptoParams,
+        sk: &LwePrivateKey,
+        z: &LwePrivateKey,
+        rng: &mut dyn CryptoRngCore,
+    ) -> Self {
+        let q = params.lwe().big_q_modulus();
+        let mut z_ntt: Vec<u64> = z.coefficients().iter().map(|&x| q.reduce_i64(x)).collect();
+        params.rgsw().ntt().forward(&mut z_ntt);
+
+        match params.rgsw().method() {
+            BinFheMethod::Ap => Self::Ap(ApAccKey::generate(params, sk, &z_ntt, rng)),
+            BinFheMethod::Ginx => Self::Ginx(GinxAccKey::generate(params, sk, &z_ntt, rng)),
+            BinFheMethod::Lmkcdey => {
+                Self::Lmkcdey(LmkcdeyAccKey::generate(params, sk, z, &z_ntt, rng))
+            }
+        }
+    }
+
+    /// The method this key was generated for
+    pub fn method(&self) -> BinFheMethod {
+        match self {
+            Self::Ap(_) => BinFheMethod::Ap,
+            Self::Ginx(_) => BinFheMethod::Ginx,
+            Self::Lmkcdey(_) => BinFheMethod::Lmkcdey,
+        }
+    }
+
+    /// Blind rotation: multiply the trivial, coefficient form accumulator by
+    /// X^<a, s>, `a` being already scaled to Z_2N
+    pub(crate) fn eval_acc(
+        &self,
+        params: &BinFheCryptoParams,
+        acc: &mut RlweCiphertext,
+        a: &[usize],
+    ) {
+        match self {
+            Self::Ap(key) => key.eval_acc(params, acc, a),
+            Self::Ginx(key) => key.eval_acc(params, acc, a),
+            Self::Lmkcdey(key) => key.eval_acc(params, acc, a),
+        }
+    }
+}
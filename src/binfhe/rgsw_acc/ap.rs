@@ -0,0 +1,64 @@
+use crate::binfhe::{BinFheCryptoParams, LwePrivateKey, RingGswCiphertext, RlweCiphertext};
+use rand_core::CryptoRngCore;
+use serde::{Deserialize, Serialize};
+
+/// AP (FHEW) blind rotation key: RingGSW encryptions of X^(v * B_r^j * s_i) for
+/// every LWE secret coefficient s_i, digit j and non-zero digit value v
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApAccKey {
+    keys: Vec<RingGswCiphertext>,
+}
+
+impl ApAccKey {
+    pub(crate) fn generate(
+        params: &BinFheCryptoParams,
+        sk: &LwePrivateKey,
+        z_ntt: &[u64],
+        rng: &mut dyn CryptoRngCore,
+    ) -> Self {
+        let rgsw = params.rgsw();
+        let base_r = rgsw.base_r() as i64;
+
+        let mut keys = Vec::with_capacity(sk.dim() * rgsw.digits_r() * (base_r as usize - 1));
+        for &s in sk.coefficients() {
+            let mut power = 1;
+            for _ in 0..rgsw.digits_r() {
+                for v in 1..base_r {
+                    keys.push(RingGswCiphertext::encrypt_monomial(
+                        params,
+                        z_ntt,
+                        v * power * s,
+                        rng,
+                    ));
+                }
+                power *= base_r;
+            }
+        }
+
+        Self { keys }
+    }
+
+    pub(crate) fn eval_acc(
+        &self,
+        params: &BinFheCryptoParams,
+        acc: &mut RlweCiphertext,
+        a: &[usize],
+    ) {
+        let rgsw = params.rgsw();
+        let base_r = rgsw.base_r() as usize;
+        let digits_r = rgsw.digits_r();
+        let two_n = 2 * params.lwe().ring_dim();
+
+        for (i, &a) in a.iter().enumerate() {
+            let mut a = a % two_n;
+            for j in 0..digits_r {
+                let v = a % base_r;
+                a /= base_r;
+                if v != 0 {
+                    let key = &self.keys[(i * digits_r + j) * (base_r - 1) + v - 1];
+                    acc.external_product_assign(rgsw, key);
+                }
+            }
+        }
+    }
+}
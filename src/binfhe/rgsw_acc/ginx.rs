@@ -0,0 +1,74 @@
+use crate::binfhe::rgsw::{decompose, mul_digits};
+use crate::binfhe::{
+    BinFheCryptoParams, LwePrivateKey, RingGswCiphertext, RingGswCryptoParams, RlweCiphertext,
+};
+use rand_core::CryptoRngCore;
+use serde::{Deserialize, Serialize};
+
+/// GINX (CGGI) blind rotation key: for every LWE secret coefficient s_i, RingGSW
+/// encryptions of [s_i = 1] and [s_i = -1]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GinxAccKey {
+    keys: Vec<[RingGswCiphertext; 2]>,
+}
+
+impl GinxAccKey {
+    pub(crate) fn generate(
+        params: &BinFheCryptoParams,
+        sk: &LwePrivateKey,
+        z_ntt: &[u64],
+        rng: &mut dyn CryptoRngCore,
+    ) -> Self {
+        let keys = sk
+            .coefficients()
+            .iter()
+            .map(|&s| {
+                [
+                    RingGswCiphertext::encrypt_constant(params, z_ntt, (s == 1) as u64, rng),
+                    RingGswCiphertext::encrypt_constant(params, z_ntt, (s == -1) as u64, rng),
+                ]
+            })
+            .collect();
+
+        Self { keys }
+    }
+
+    pub(crate) fn eval_acc(
+        &self,
+        params: &BinFheCryptoParams,
+        acc: &mut RlweCiphertext,
+        a: &[usize],
+    ) {
+        let two_n = 2 * params.lwe().ring_dim();
+        for (key, &a) in self.keys.iter().zip(a) {
+            let a = a % two_n;
+            if a != 0 {
+                add_to_acc_cggi(params.rgsw(), acc, key, a, two_n - a);
+            }
+        }
+    }
+}
+
+/// acc += (X^a_pos - 1) * (acc ⊡ key[0]) + (X^a_neg - 1) * (acc ⊡ key[1])
+fn add_to_acc_cggi(
+    rgsw: &RingGswCryptoParams,
+    acc: &mut RlweCiphertext,
+    key: &[RingGswCiphertext; 2],
+    a_pos: usize,
+    a_neg: usize,
+) {
+    let q = rgsw.ntt().modulus();
+    let dct = decompose(rgsw, acc);
+    let [pos_a, pos_b] = mul_digits(rgsw, &dct, &key[0]);
+    let [neg_a, neg_b] = mul_digits(rgsw, &dct, &key[1]);
+
+    let (mono_pos, mono_neg) = (rgsw.monomial(a_pos), rgsw.monomial(a_neg));
+    let combine = |pos: Vec<u64>, neg: Vec<u64>| -> Vec<u64> {
+        pos.iter()
+            .zip(&neg)
+            .zip(mono_pos.iter().zip(mono_neg))
+            .map(|((&p, &n), (&mp, &mn))| q.add(q.mul(p, mp), q.mul(n, mn)))
+            .collect()
+    };
+    acc.add_ntt_assign(rgsw, [combine(pos_a, neg_a), combine(pos_b, neg_b)]);
+}
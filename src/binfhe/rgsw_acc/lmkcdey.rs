@@ -0,0 +1,133 @@
+use crate::binfhe::{
+    AutomorphismKey, BinFheCryptoParams, LwePrivateKey, RingGswCiphertext, RingGswCryptoParams,
+    RlweCiphertext,
+};
+use rand_core::CryptoRngCore;
+use serde::{Deserialize, Serialize};
+
+/// LMKCDEY blind rotation key: RingGSW encryptions of X^s_i, of the correction
+/// X^(-sum s_i), and automorphism keys for g, ..., g^w and -g
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LmkcdeyAccKey {
+    keys: Vec<RingGswCiphertext>,
+    correction: RingGswCiphertext,
+    auto_keys: Vec<AutomorphismKey>,
+    neg_auto_key: AutomorphismKey,
+}
+
+impl LmkcdeyAccKey {
+    pub(crate) fn generate(
+        params: &BinFheCryptoParams,
+        sk: &LwePrivateKey,
+        z: &LwePrivateKey,
+        z_ntt: &[u64],
+        rng: &mut dyn CryptoRngCore,
+    ) -> Self {
+        let rgsw = params.rgsw();
+        let two_n = 2 * params.lwe().ring_dim();
+        let g = RingGswCryptoParams::GENERATOR as usize;
+
+        let keys = sk
+            .coefficients()
+            .iter()
+            .map(|&s| RingGswCiphertext::encrypt_monomial(params, z_ntt, s, rng))
+            .collect();
+        let sum: i64 = sk.coefficients().iter().sum();
+        let correction = RingGswCiphertext::encrypt_monomial(params, z_ntt, -sum, rng);
+
+        let mut power = 1;
+        let auto_keys = (0..rgsw.num_auto_keys())
+            .map(|_| {
+                power = power * g % two_n;
+                AutomorphismKey::generate(params, z, z_ntt, power, rng)
+            })
+            .collect();
+        let neg_auto_key = AutomorphismKey::generate(params, z, z_ntt, two_n - g, rng);
+
+        Self {
+            keys,
+            correction,
+            auto_keys,
+            neg_auto_key,
+        }
+    }
+
+    /// Every a_i is even since q divides N, so a_i + 1 is odd and can be written
+    /// as ±g^l. The rotations by X^((a_i + 1) s_i) are grouped by l and
+    /// interleaved with the automorphism X -> X^g in Horner fashion, first for
+    /// the negative then for the positive exponents, and X^(-sum s_i) fixes the
+    /// result. The automorphisms applied overall amount to X -> X^(-g^-1), which
+    /// the trivial input accumulator is twisted by beforehand.
+    pub(crate) fn eval_acc(
+        &self,
+        params: &BinFheCryptoParams,
+        acc: &mut RlweCiphertext,
+        a: &[usize],
+    ) {
+        let rgsw = params.rgsw();
+        let q = rgsw.ntt().modulus();
+        let ring_dim = params.lwe().ring_dim();
+        let two_n = 2 * ring_dim;
+
+        let mut buckets = [vec![vec![]; ring_dim / 2], vec![vec![]; ring_dim / 2]];
+        for (i, &a) in a.iter().enumerate() {
+            let (positive, l) = rgsw.log_gen((a + 1) % two_n);
+            buckets[positive as usize][l].push(i);
+        }
+
+        *acc = acc.automorphism(self.neg_auto_key.exponent(), q);
+        let mut trivial = true;
+        for (sign, levels) in buckets.iter().enumerate() {
+            let mut pending = 0;
+            for (l, indices) in levels.iter().enumerate().rev() {
+                if !indices.is_empty() {
+                    self.apply_generator(rgsw, acc, pending, trivial);
+                    pending = 0;
+                    for &i in indices {
+                        acc.external_product_assign(rgsw, &self.keys[i]);
+                    }
+                    trivial = false;
+                }
+                if l > 0 {
+                    pending += 1;
+                }
+            }
+            self.apply_generator(rgsw, acc, pending, trivial);
+            if sign == 0 {
+                self.apply(rgsw, acc, &self.neg_auto_key, trivial);
+            }
+        }
+        acc.external_product_assign(rgsw, &self.correction);
+    }
+
+    /// Apply X -> X^(g^k) using the largest available automorphism keys
+    fn apply_generator(
+        &self,
+        rgsw: &RingGswCryptoParams,
+        acc: &mut RlweCiphertext,
+        mut k: usize,
+        trivial: bool,
+    ) {
+        while k > 0 {
+            let step = k.min(self.auto_keys.len());
+            self.apply(rgsw, acc, &self.auto_keys[step - 1], trivial);
+            k -= step;
+        }
+    }
+
+    /// A trivial accumulator is independent of the key, so the automorphism needs
+    /// no key switching
+    fn apply(
+        &self,
+        rgsw: &RingGswCryptoParams,
+        acc: &mut RlweCiphertext,
+        key: &AutomorphismKey,
+        trivial: bool,
+    ) {
+        if trivial {
+            *acc = acc.automorphism(key.exponent(), rgsw.ntt().modulus());
+        } else {
+            key.apply(rgsw, acc);
+        }
+    }
+}
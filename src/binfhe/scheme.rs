@@ -0,0 +1,285 @@
+use crate::binfhe::{
+    BinFheCryptoParams, BinFheMethod, BinGate, LweCiphertext, LwePrivateKey, LweSwitchingKey,
+    RingGswAccKey, RlweCiphertext,
+};
+use crate::fhe_core::Modulus;
+use rand_core::CryptoRngCore;
+use serde::{Deserialize, Serialize};
+
+/// The refreshing key: the blind rotation key and the key switching key back to
+/// the LWE secret
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BootstrappingKey {
+    acc_key: RingGswAccKey,
+    ks_key: LweSwitchingKey,
+}
+
+impl BootstrappingKey {
+    /// Generate the refreshing key for the LWE secret `sk`
+    pub(crate) fn generate(
+        params: &BinFheCryptoParams,
+        sk: &LwePrivateKey,
+        rng: &mut dyn CryptoRngCore,
+    ) -> Self {
+        let z = LwePrivateKey::generate(params.lwe().ring_dim(), rng);
+        Self {
+            acc_key: RingGswAccKey::generate(params, sk, &z, rng),
+            ks_key: LweSwitchingKey::generate(params.lwe(), &z, sk, rng),
+        }
+    }
+
+    /// The blind rotation method the key was generated for
+    pub fn method(&self) -> BinFheMethod {
+        self.acc_key.method()
+    }
+}
+
+/// Constant added to the sum of the inputs so the sign of the result is the
+/// gate output, as a multiple of q/8
+pub(crate) fn gate_constant(gate: BinGate) -> u64 {
+    match gate {
+        BinGate::Or => 7,
+        BinGate::And => 5,
+        BinGate::Nor => 3,
+        BinGate::Nand => 1,
+        BinGate::Xor => 6,
+        BinGate::Xnor => 2,
+    }
+}
+
+/// Evaluate a two-input gate on bit encryptions m * q/4
+pub(crate) fn eval_bin_gate(
+    params: &BinFheCryptoParams,
+    ek: &BootstrappingKey,
+    gate: BinGate,
+    ct1: &LweCiphertext,
+    ct2: &LweCiphertext,
+) -> LweCiphertext {
+    let q = params.lwe().q();
+    let mut ct = ct1.clone();
+    ct.add_assign(ct2);
+    if matches!(gate, BinGate::Xor | BinGate::Xnor) {
+        // 2 * (m1 + m2) * q/4 is 0 for equal bits and q/2 otherwise
+        ct.mul_scalar_assign(2);
+    }
+    ct.add_constant_assign(gate_constant(gate) * (q >> 3));
+    bootstrap_sign(params, ek, &ct)
+}
+
+/// Refresh a bit encryption
+pub(crate) fn bootstrap(
+    params: &BinFheCryptoParams,
+    ek: &BootstrappingKey,
+    ct: &LweCiphertext,
+) -> LweCiphertext {
+    let q = params.lwe().q();
+    let mut ct = ct.clone();
+    // maps {0, q/4} to {-q/8, q/8}
+    ct.add_constant_assign(q - (q >> 3));
+    bootstrap_sign(params, ek, &ct)
+}
+
+/// Bootstrap a ciphertext modulo q to an encryption of q/4 if its phase lies in
+/// [0, q/2) and of 0 otherwise
+fn bootstrap_sign(
+    params: &BinFheCryptoParams,
+    ek: &BootstrappingKey,
+    ct: &LweCiphertext,
+) -> LweCiphertext {
+    let big_q = params.lwe().big_q_modulus();
+    let q8 = (big_q.value() + 4) >> 3;
+    let tv = vec![q8; params.lwe().ring_dim()];
+    let mut ct = bootstrap_core(params, ek, ct, &tv);
+    ct.add_constant_assign(q8);
+    finalize(params, ek, &ct, params.lwe().q_modulus())
+}
+
+/// Bootstrap `ct`, whose modulus q' divides 2N, and N for
+/// [`BinFheMethod::Lmkcdey`], evaluating `f` on its phase x:
+/// the result is an encryption modulo `out` of f(x) for x in [0, q'/2) and of
+/// -f(x - q'/2) otherwise
+pub(crate) fn bootstrap_func(
+    params: &BinFheCryptoParams,
+    ek: &BootstrappingKey,
+    ct: &LweCiphertext,
+    f: impl Fn(u64) -> u64,
+    out: &Modulus,
+) -> LweCiphertext {
+    let big_q = params.lwe().big_q_modulus();
+    let ring_dim = params.lwe().ring_dim();
+    let factor = (2 * ring_dim) as u64 / ct.modulus();
+
+    let tv: Vec<u64> = (0..ring_dim as u64)
+        .map(|k| {
+            let y = out.center(out.reduce(f(k / factor))) as i128;
+            let (num, den) = (y * big_q.value() as i128, out.value() as i128);
+            let scaled = (num + num.signum() * (den >> 1)) / den;
+            big_q.reduce_i64(scaled as i64)
+        })
+        .collect();
+    let ct = bootstrap_core(params, ek, ct, &tv);
+    finalize(params, ek, &ct, out)
+}
+
+/// Evaluate the look-up table `lut` over Z_p on an encryption of m * q/p. A
+/// negacyclic table needs a single bootstrapping; otherwise the ciphertext is
+/// read modulo 2q and a first bootstrapping brings its phase into [0, q), which
+/// requires 2q to divide 2N, and N for [`BinFheMethod::Lmkcdey`].
+pub(crate) fn eval_func(
+    params: &BinFheCryptoParams,
+    ek: &BootstrappingKey,
+    ct: &LweCiphertext,
+    lut: &[u64],
+) -> LweCiphertext {
+    let q = params.lwe().q_modulus();
+    let p = lut.len() as u64;
+    let delta = q.value() / p;
+    let f = |x: u64| lut[(x / delta) as usize] * delta;
+
+    let mut ct = ct.clone();
+    // center the phase in the interval of its message
+    ct.add_constant_assign(delta >> 1);
+    if is_negacyclic(lut) {
+        return bootstrap_func(params, ek, &ct, f, q);
+    }
+
+    let half_q = q.value() >> 1;
+    let double_q = Modulus::new(q.value() << 1);
+    let mut ct = ct.reinterpret(&double_q);
+    // -q/2 for a phase in [0, q) and q/2 for one in [q, 2q)
+    let fix = bootstrap_func(params, ek, &ct, |_| 3 * half_q, &double_q);
+    ct.add_assign(&fix);
+    ct.add_constant_assign(half_q);
+    bootstrap_func(params, ek, &ct, f, q)
+}
+
+/// Whether f(m + p/2) = -f(m) mod p for the look-up table f over Z_p
+pub(crate) fn is_negacyclic(lut: &[u64]) -> bool {
+    let p = lut.len() as u64;
+    let half = lut.len() / 2;
+    p.is_multiple_of(2) && (0..half).all(|m| lut[m + half] == (p - lut[m]) % p)
+}
+
+/// Round the phase of a ciphertext modulo M down to a multiple of q' = q for
+/// `round_bits` = 0 and of q' = 2 * beta * 2^round_bits otherwise, after adding
+/// beta = q/8. The low part modulo q' is first shifted into [q'/4, 3q'/4), then
+/// bootstrapped exactly and subtracted.
+pub(crate) fn eval_floor(
+    params: &BinFheCryptoParams,
+    ek: &BootstrappingKey,
+    ct: &LweCiphertext,
+    round_bits: u32,
+) -> LweCiphertext {
+    let q = params.lwe().q();
+    let beta = q >> 3;
+    let q_small = if round_bits == 0 {
+        q
+    } else {
+        beta << (round_bits + 1)
+    };
+    let small = Modulus::new(q_small);
+    let modulus = Modulus::new(ct.modulus());
+    let m = modulus.value();
+
+    let mut ct = ct.clone();
+    ct.add_constant_assign(beta);
+    let shift = bootstrap_func(
+        params,
+        ek,
+        &ct.reinterpret(&small),
+        |_| m - (q_small >> 2),
+        &modulus,
+    );
+    ct.sub_assign(&shift);
+    let low = bootstrap_func(
+        params,
+        ek,
+        &ct.reinterpret(&small),
+        |x| {
+            if x < q_small >> 2 {
+                m - (q_small >> 1) - x
+            } else {
+                x
+            }
+        },
+        &modulus,
+    );
+    ct.sub_assign(&low);
+    ct
+}
+
+/// Encryption of the most significant bit of the message of a ciphertext modulo
+/// M, a power of two multiple of q, as a bit m * q/4. Each round floors the phase
+/// and switches the modulus down by q/(2 beta) until it reaches q.
+pub(crate) fn eval_sign(
+    params: &BinFheCryptoParams,
+    ek: &BootstrappingKey,
+    ct: &LweCiphertext,
+) -> LweCiphertext {
+    let q = params.lwe().q_modulus();
+    let beta = q.value() >> 3;
+
+    let mut ct = ct.clone();
+    let mut modulus = ct.modulus();
+    while modulus > q.value() {
+        ct = eval_floor(params, ek, &ct, 0);
+        modulus = (modulus / (q.value() / (2 * beta))).max(q.value());
+        ct = ct.mod_switch(&Modulus::new(modulus));
+    }
+    ct.add_constant_assign(beta);
+    // -q/8 for the lower half and q/8 for the upper half, shifted to {0, q/4}
+    let mut ct = bootstrap_func(params, ek, &ct, |_| q.value() - beta, q);
+    ct.add_constant_assign(beta);
+    ct
+}
+
+/// Blind rotate the test vector `tv` by the phase of `ct`, whose modulus q'
+/// divides 2N, and extract the constant coefficient, i.e. an encryption of
+/// tv[phase * 2N/q'] modulo Q under the RLWE secret, the test vector being
+/// extended negacyclically
+pub(crate) fn bootstrap_core(
+    params: &BinFheCryptoParams,
+    ek: &BootstrappingKey,
+    ct: &LweCiphertext,
+    tv: &[u64],
+) -> LweCiphertext {
+    let ring_dim = params.lwe().ring_dim();
+    let factor = (2 * ring_dim) as u64 / ct.modulus();
+
+    let b = (ct.b() * factor) as usize;
+    let a: Vec<usize> = ct.a().iter().map(|&x| (x * factor) as usize).collect();
+
+    let mut acc = RlweCiphertext::trivial(rotate(params, tv, 2 * ring_dim - b));
+    ek.acc_key.eval_acc(params, &mut acc, &a);
+    acc.extract_lwe(params)
+}
+
+/// Bring an extracted ciphertext modulo Q under the RLWE secret back to a
+/// ciphertext modulo `out` under the LWE secret
+pub(crate) fn finalize(
+    params: &BinFheCryptoParams,
+    ek: &BootstrappingKey,
+    ct: &LweCiphertext,
+    out: &Modulus,
+) -> LweCiphertext {
+    let lwe = params.lwe();
+    let ct = ct.mod_switch(lwe.q_ks_modulus());
+    let ct = ek.ks_key.key_switch(lwe, &ct);
+    ct.mod_switch(out)
+}
+
+/// X^k * tv in Z_Q[X]/(X^N + 1) for k in [0, 2N]
+fn rotate(params: &BinFheCryptoParams, tv: &[u64], k: usize) -> Vec<u64> {
+    let q = params.lwe().big_q_modulus();
+    let ring_dim = tv.len();
+    let mut out = vec![0; ring_dim];
+    for (i, &t) in tv.iter().enumerate() {
+        let j = (i + k) % (2 * ring_dim);
+        if j < ring_dim {
+            out[j] = t;
+        } else {
+            out[j - ring_dim] = q.neg(t);
+        }
+    }
+    out
+}
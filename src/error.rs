@@ -1,3 +1,4 @@
+use crate::fhe_core::PkeSchemeFeature;
 use thiserror::Error;
 
 /// Error type for FHE operations
@@ -48,6 +49,59 @@ pub enum Error {
     /// Invalid compression level
     #[error("Invalid compression level: {0}")]
     InvalidCompressionLevel(usize),
+    /// Invalid scheme
+    #[error("Invalid scheme: {0}")]
+    InvalidScheme(usize),
+    /// Invalid secret share type
+    #[error("Invalid secret share type: {0}")]
+    InvalidSecretShareType(usize),
+    /// Invalid FHEW/TFHE parameter set
+    #[error("Invalid FHEW/TFHE parameter set: {0}")]
+    InvalidBinFheParamSet(usize),
+    /// Invalid FHEW/TFHE bootstrapping method
+    #[error("Invalid FHEW/TFHE bootstrapping method: {0}")]
+    InvalidBinFheMethod(usize),
+    /// Invalid boolean gate
+    #[error("Invalid boolean gate: {0}")]
+    InvalidBinGate(usize),
+    /// Invalid scheme parameters
+    #[error("Invalid parameters: {0}")]
+    InvalidParameters(String),
+    /// Invalid key for the context
+    #[error("Invalid key: {0}")]
+    InvalidKey(String),
+    /// Invalid ciphertext for the context
+    #[error("Invalid ciphertext: {0}")]
+    InvalidCiphertext(String),
+    /// Plaintext outside of the plaintext space
+    #[error("Invalid plaintext: {0}")]
+    InvalidPlaintext(u64),
+    /// The bootstrapping key was not generated
+    #[error("Bootstrapping key was not generated")]
+    MissingBootstrappingKey,
+    /// Objects of different keys combined in one operation
+    #[error("Key tag mismatch: '{0}' and '{1}'")]
+    KeyTagMismatch(String, String),
+    /// Malformed serialized data
+    #[error("Serialization error: {0}")]
+    Serialization(String),
+    /// Serialized data of a format version this version cannot decode
+    #[error("Unsupported serialization format version {found}, expected at most {supported}")]
+    UnsupportedFormatVersion {
+        /// The version of the data
+        found: u16,
+        /// The latest version this version decodes
+        supported: u16,
+    },
+    /// Serialized object for other parameters than the context
+    #[error("Serialized object for other parameters")]
+    ParametersMismatch,
+    /// The scheme feature needed by an operation is not enabled
+    #[error("Feature {0} is not enabled")]
+    FeatureNotEnabled(PkeSchemeFeature),
+    /// The evaluation key needed by an operation was not generated
+    #[error("Missing evaluation key: {0}")]
+    MissingEvalKey(String),
 }
 
 /// Result type for FHE operations
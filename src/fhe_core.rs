@@ -1,4 +1,6 @@
 mod compression_level;
+mod context_rng;
+mod dcrt_poly;
 mod decryption_noise_mode;
 mod encryption_technique;
 mod execution_mode;
@@ -9,13 +11,24 @@ mod math;
 mod multiparty_mode;
 mod multiplication_technique;
 pub mod noise_flooding;
+mod ntt;
+pub(crate) mod parallel;
 mod pke_scheme_feature;
 mod plaintext_encodings;
 mod polynomial_format;
+mod prng_from_hash;
 mod proxy_reencryption_mode;
+mod sampling;
 mod scaling_technique;
+mod scheme;
+mod secret_share_type;
+mod simd;
+mod uniform_polys;
 mod utils;
+mod word;
 
+pub use context_rng::*;
+pub use dcrt_poly::*;
 pub use decryption_noise_mode::*;
 pub use encryption_technique::*;
 pub use execution_mode::*;
@@ -25,12 +38,20 @@ pub use large_scaling_factor_constants::*;
 pub use math::*;
 pub use multiparty_mode::*;
 pub use multiplication_technique::*;
+pub use ntt::*;
 pub use pke_scheme_feature::*;
 pub use plaintext_encodings::*;
 pub use polynomial_format::*;
+pub use prng_from_hash::*;
 pub use proxy_reencryption_mode::*;
+pub use sampling::*;
 pub use scaling_technique::*;
+pub use scheme::*;
+pub use secret_share_type::*;
+pub use simd::*;
+pub use uniform_polys::*;
 pub use utils::*;
+pub use word::*;
 
 pub const MAX_MODULUS_SIZE: usize = if cfg!(target_pointer_width = "128") {
     121
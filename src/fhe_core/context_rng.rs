@@ -0,0 +1,67 @@
+use crate::fhe_core::SEED_LEN;
+use rand::rngs::OsRng;
+use rand::Rng;
+use rand_chacha::ChaCha20Rng;
+use rand_core::{CryptoRngCore, SeedableRng};
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// The source of randomness of a context, shared by its clones so that they
+/// never draw the same samples. Defaults to ChaCha20 keyed from the operating
+/// system; [`Self::from_seed`] gives a deterministic stream for tests and
+/// known-answer vectors.
+#[derive(Clone)]
+pub struct ContextRng(Arc<Mutex<Box<dyn CryptoRngCore + Send>>>);
+
+impl ContextRng {
+    /// Draw the randomness from `rng`
+    pub fn new<R: CryptoRngCore + Send + 'static>(rng: R) -> Self {
+        Self(Arc::new(Mutex::new(Box::new(rng))))
+    }
+
+    /// ChaCha20 keyed from the operating system
+    pub fn from_entropy() -> Self {
+        Self::from_seed(OsRng.gen())
+    }
+
+    /// ChaCha20 keyed with `seed`, drawing the same stream for the same seed
+    pub fn from_seed(seed: [u8; SEED_LEN]) -> Self {
+        Self::new(ChaCha20Rng::from_seed(seed))
+    }
+
+    /// Exclusive access to the generator, until the guard is dropped
+    pub(crate) fn lock(&self) -> MutexGuard<'_, Box<dyn CryptoRngCore + Send>> {
+        // A panic while sampling leaves the generator in a valid state
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// An independent ChaCha20 generator keyed from this one, for an operation
+    /// running parallel loops: a worker waiting on them may pick up another
+    /// operation of the same generator, which would block on a lock held by
+    /// its own thread
+    pub(crate) fn fork_one(&self) -> ChaCha20Rng {
+        ChaCha20Rng::from_seed(self.lock().gen())
+    }
+
+    /// `count` independent ChaCha20 generators keyed from this one, one per
+    /// task of a parallel operation so that its result does not depend on the
+    /// scheduling of the tasks
+    pub(crate) fn fork(&self, count: usize) -> Vec<ChaCha20Rng> {
+        let mut rng = self.lock();
+        (0..count)
+            .map(|_| ChaCha20Rng::from_seed(rng.gen()))
+            .collect()
+    }
+}
+
+impl Default for ContextRng {
+    fn default() -> Self {
+        Self::from_entropy()
+    }
+}
+
+impl fmt::Debug for ContextRng {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ContextRng").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,357 @@
+use crate::fhe_core::{
+    parallel, sample_ternary, sample_uniform, sample_uniform_bits, DiscreteGaussianGenerator,
+    Modulus, NttTables, PolynomialFormat, Word,
+};
+use rand_core::CryptoRngCore;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+/// The ring Z_Q[X]/(X^N + 1) for Q a product of NTT friendly primes, holding
+/// the transform tables of every prime
+#[derive(Debug, Clone)]
+pub struct RnsBasis<W: Word = u64> {
+    ring_dim: usize,
+    ntt: Vec<NttTables<W>>,
+}
+
+impl<W: Word> RnsBasis<W> {
+    /// Create the basis for ring dimension `ring_dim` and primes `q_i = 1 mod 2N`
+    pub fn new(ring_dim: usize, moduli: &[W]) -> Option<Self> {
+        let ntt = moduli
+            .iter()
+            .map(|&q| NttTables::new(ring_dim, Modulus::new(q)))
+            .collect::<Option<_>>()?;
+        Some(Self { ring_dim, ntt })
+    }
+
+    /// The ring dimension N
+    pub fn ring_dim(&self) -> usize {
+        self.ring_dim
+    }
+
+    /// The number of primes in the basis
+    pub fn num_moduli(&self) -> usize {
+        self.ntt.len()
+    }
+
+    /// The i-th prime
+    pub fn modulus(&self, i: usize) -> &Modulus<W> {
+        self.ntt[i].modulus()
+    }
+
+    /// The transform tables of the i-th prime
+    pub fn ntt(&self, i: usize) -> &NttTables<W> {
+        &self.ntt[i]
+    }
+}
+
+/// A polynomial of Z_Q[X]/(X^N + 1) in double-CRT form: one residue polynomial,
+/// or tower, for each prime of a prefix of an [`RnsBasis`]
+#[derive(Debug, Clone, Serialize, Deserialize, Zeroize)]
+#[cfg_attr(test, derive(Eq, PartialEq))]
+#[serde(bound = "")]
+pub struct DcrtPoly<W: Word = u64> {
+    towers: Vec<Vec<W>>,
+    #[zeroize(skip)]
+    format: PolynomialFormat,
+}
+
+impl<W: Word> DcrtPoly<W> {
+    /// The zero polynomial over the first `num_towers` primes
+    pub fn zero(basis: &RnsBasis<W>, num_towers: usize, format: PolynomialFormat) -> Self {
+        Self {
+            towers: vec![vec![W::ZERO; basis.ring_dim()]; num_towers],
+            format,
+        }
+    }
+
+    /// The coefficient format polynomial with small signed coefficients
+    /// `coefficients`, padded with zeros up to N
+    pub fn from_signed(basis: &RnsBasis<W>, num_towers: usize, coefficients: &[i64]) -> Self {
+        let towers = (0..num_towers)
+            .map(|i| {
+                let q = basis.modulus(i);
+                let mut tower = vec![W::ZERO; basis.ring_dim()];
+                for (t, &c) in tower.iter_mut().zip(coefficients) {
+                    *t = q.reduce_i64(c);
+                }
+                tower
+            })
+            .collect();
+        Self {
+            towers,
+            format: PolynomialFormat::Coefficient,
+        }
+    }
+
+    pub(crate) fn from_towers(towers: Vec<Vec<W>>, format: PolynomialFormat) -> Self {
+        Self { towers, format }
+    }
+
+    /// A polynomial with discrete Gaussian coefficients, in coefficient format
+    pub fn gaussian<R: CryptoRngCore + ?Sized>(
+        basis: &RnsBasis<W>,
+        num_towers: usize,
+        dgg: &DiscreteGaussianGenerator,
+        rng: &mut R,
+    ) -> Self {
+        let coefficients: Vec<i64> = (0..basis.ring_dim()).map(|_| dgg.sample(rng)).collect();
+        Self::from_signed(basis, num_towers, &coefficients)
+    }
+
+    /// A polynomial with uniform ternary coefficients, in coefficient format
+    pub fn ternary<R: CryptoRngCore + ?Sized>(
+        basis: &RnsBasis<W>,
+        num_towers: usize,
+        rng: &mut R,
+    ) -> Self {
+        Self::from_signed(basis, num_towers, &sample_ternary(basis.ring_dim(), rng))
+    }
+
+    /// Whether every residue is reduced modulo its prime
+    pub fn is_reduced(&self, basis: &RnsBasis<W>) -> bool {
+        self.towers.iter().enumerate().all(|(i, tower)| {
+            i < basis.num_moduli() && tower.iter().all(|&x| x < basis.modulus(i).value())
+        })
+    }
+
+    /// The number of towers
+    pub fn num_towers(&self) -> usize {
+        self.towers.len()
+    }
+
+    /// The current format
+    pub fn format(&self) -> PolynomialFormat {
+        self.format
+    }
+
+    /// The residue polynomial modulo the i-th prime
+    pub fn tower(&self, i: usize) -> &[W] {
+        &self.towers[i]
+    }
+
+    /// Mutable access to the residue polynomial modulo the i-th prime
+    pub fn tower_mut(&mut self, i: usize) -> &mut [W] {
+        &mut self.towers[i]
+    }
+
+    /// Bring the polynomial to `format`, transforming every tower if needed
+    pub fn set_format(&mut self, basis: &RnsBasis<W>, format: PolynomialFormat) {
+        match (self.format, format) {
+            (PolynomialFormat::Coefficient, PolynomialFormat::Evaluation) => {
+                parallel::for_each_mut(&mut self.towers, |i, tower| {
+                    basis.ntt(i).forward(tower);
+                });
+            }
+            (PolynomialFormat::Evaluation, PolynomialFormat::Coefficient) => {
+                parallel::for_each_mut(&mut self.towers, |i, tower| {
+                    basis.ntt(i).inverse(tower);
+                });
+            }
+            _ => {}
+        }
+        self.format = format;
+    }
+
+    /// A copy of the polynomial in `format`
+    pub fn to_format(&self, basis: &RnsBasis<W>, format: PolynomialFormat) -> Self {
+        let mut out = self.clone();
+        out.set_format(basis, format);
+        out
+    }
+
+    /// Keep only the first `num_towers` towers, i.e. reduce modulo their product
+    pub fn truncate(&mut self, num_towers: usize) {
+        self.towers.truncate(num_towers);
+    }
+
+    /// self <- self + other, both in the same format
+    pub fn add_assign(&mut self, basis: &RnsBasis<W>, other: &Self) {
+        self.zip_with(basis, other, W::add_mod_slice);
+    }
+
+    /// self <- self - other, both in the same format
+    pub fn sub_assign(&mut self, basis: &RnsBasis<W>, other: &Self) {
+        self.zip_with(basis, other, W::sub_mod_slice);
+    }
+
+    /// self <- self * other, both in evaluation format
+    pub fn mul_assign(&mut self, basis: &RnsBasis<W>, other: &Self) {
+        debug_assert!(matches!(self.format, PolynomialFormat::Evaluation));
+        self.zip_with(basis, other, W::mul_mod_slice);
+    }
+
+    /// self * other, both in evaluation format
+    pub fn mul(&self, basis: &RnsBasis<W>, other: &Self) -> Self {
+        let mut out = self.clone();
+        out.mul_assign(basis, other);
+        out
+    }
+
+    /// self <- -self
+    pub fn neg_assign(&mut self, basis: &RnsBasis<W>) {
+        for (i, tower) in self.towers.iter_mut().enumerate() {
+            let q = basis.modulus(i);
+            for x in tower.iter_mut() {
+                *x = q.neg(*x);
+            }
+        }
+    }
+
+    /// self <- c * self for an integer constant `c`
+    pub fn mul_scalar_assign(&mut self, basis: &RnsBasis<W>, c: W) {
+        for (i, tower) in self.towers.iter_mut().enumerate() {
+            let q = basis.modulus(i);
+            let c = q.reduce(c);
+            W::mul_shoup_slice(tower, c, q.shoup(c), q, false);
+        }
+    }
+
+    /// self <- c_i * self modulo the i-th prime for one constant per tower
+    pub fn mul_rns_scalar_assign(&mut self, basis: &RnsBasis<W>, c: &[W]) {
+        for (i, (tower, &c)) in self.towers.iter_mut().zip(c).enumerate() {
+            let q = basis.modulus(i);
+            let c = q.reduce(c);
+            W::mul_shoup_slice(tower, c, q.shoup(c), q, false);
+        }
+    }
+
+    /// p(X^k) for a coefficient format polynomial and odd k
+    pub fn automorphism(&self, basis: &RnsBasis<W>, k: usize) -> Self {
+        debug_assert!(matches!(self.format, PolynomialFormat::Coefficient));
+        let ring_dim = basis.ring_dim();
+        let towers = self
+            .towers
+            .iter()
+            .enumerate()
+            .map(|(i, tower)| {
+                let q = basis.modulus(i);
+                let mut out = vec![W::ZERO; ring_dim];
+                for (j, &x) in tower.iter().enumerate() {
+                    let l = (j * k) % (2 * ring_dim);
+                    if l < ring_dim {
+                        out[l] = x;
+                    } else {
+                        out[l - ring_dim] = q.neg(x);
+                    }
+                }
+                out
+            })
+            .collect();
+        Self {
+            towers,
+            format: self.format,
+        }
+    }
+
+    fn zip_with(&mut self, basis: &RnsBasis<W>, other: &Self, f: fn(&mut [W], &[W], &Modulus<W>)) {
+        debug_assert_eq!(
+            u8::from(self.format),
+            u8::from(other.format),
+            "mismatched polynomial formats"
+        );
+        for (i, (tower, other)) in self.towers.iter_mut().zip(&other.towers).enumerate() {
+            f(tower, other, basis.modulus(i));
+        }
+    }
+}
+
+impl DcrtPoly {
+    /// A uniformly random polynomial, in evaluation format
+    pub fn uniform<R: CryptoRngCore + ?Sized>(
+        basis: &RnsBasis,
+        num_towers: usize,
+        rng: &mut R,
+    ) -> Self {
+        Self {
+            towers: (0..num_towers)
+                .map(|i| sample_uniform(basis.ring_dim(), basis.modulus(i), rng))
+                .collect(),
+            format: PolynomialFormat::Evaluation,
+        }
+    }
+
+    /// A polynomial with coefficients uniform in [-2^(bits - 1), 2^(bits - 1)),
+    /// which may exceed a single prime, in coefficient format
+    pub fn uniform_bits<R: CryptoRngCore + ?Sized>(
+        basis: &RnsBasis,
+        num_towers: usize,
+        bits: u32,
+        rng: &mut R,
+    ) -> Self {
+        let samples = sample_uniform_bits(basis.ring_dim(), bits, rng);
+        let towers = (0..num_towers)
+            .map(|i| {
+                let q = basis.modulus(i);
+                let offset = q.pow(2, bits.saturating_sub(1) as u64);
+                let limb_base = q.pow(2, 64);
+                samples
+                    .iter()
+                    .map(|limbs| {
+                        let x = limbs
+                            .iter()
+                            .rev()
+                            .fold(0, |acc, &limb| q.add(q.mul(acc, limb_base), q.reduce(limb)));
+                        q.sub(x, offset)
+                    })
+                    .collect()
+            })
+            .collect();
+        Self {
+            towers,
+            format: PolynomialFormat::Coefficient,
+        }
+    }
+
+    /// log2 of the largest coefficient of the polynomial centered modulo the
+    /// product of its primes, -inf for the zero polynomial. The coefficients
+    /// are reconstructed in mixed radix form, which keeps the result accurate to
+    /// a fraction of a bit over any number of primes.
+    pub fn log2_norm(&self, basis: &RnsBasis) -> f64 {
+        let p = self.to_format(basis, PolynomialFormat::Coefficient);
+        let moduli: Vec<_> = (0..p.num_towers()).map(|i| basis.modulus(i)).collect();
+        // inverses of q_j modulo q_i for j < i, by Fermat as the q_i are prime
+        let inverses: Vec<Vec<u64>> = moduli
+            .iter()
+            .enumerate()
+            .map(|(i, q_i)| {
+                moduli[..i]
+                    .iter()
+                    .map(|q_j| q_i.pow(q_j.value(), q_i.value() - 2))
+                    .collect()
+            })
+            .collect();
+        let log2_mixed_radix = |residues: &[u64]| {
+            // x = v_0 + v_1 q_0 + v_2 q_0 q_1 + ... with v_i in [0, q_i)
+            let mut digits = Vec::with_capacity(residues.len());
+            for (i, (q_i, &x)) in moduli.iter().zip(residues).enumerate() {
+                let v = digits
+                    .iter()
+                    .zip(&inverses[i])
+                    .fold(x, |acc, (&v_j, &inv)| {
+                        q_i.mul(q_i.sub(acc, q_i.reduce(v_j)), inv)
+                    });
+                digits.push(v);
+            }
+            let Some(top) = digits.iter().rposition(|&v| v != 0) else {
+                return f64::NEG_INFINITY;
+            };
+            // x / (q_0 ... q_{top - 1}) = v_top + v_{top - 1} / q_{top - 1} + ...
+            let scaled = (1..=top).fold(digits[0] as f64, |acc, i| {
+                digits[i] as f64 + acc / moduli[i - 1].value() as f64
+            });
+            scaled.log2()
+                + moduli[..top]
+                    .iter()
+                    .map(|q| (q.value() as f64).log2())
+                    .sum::<f64>()
+        };
+        (0..basis.ring_dim())
+            .map(|k| {
+                let x: Vec<u64> = p.towers.iter().map(|tower| tower[k]).collect();
+                let neg: Vec<u64> = moduli.iter().zip(&x).map(|(q, &x)| q.neg(x)).collect();
+                log2_mixed_radix(&x).min(log2_mixed_radix(&neg))
+            })
+            .fold(f64::NEG_INFINITY, f64::max)
+    }
+}
@@ -7,8 +7,10 @@ use std::str::FromStr;
 #[cfg_attr(test, derive(Eq, PartialEq))]
 #[repr(u8)]
 pub enum DecryptionNoiseMode {
+    /// Partial decryptions carry a fresh noise of the error distribution
     #[default]
     FixedNoiseDecrypt = 0,
+    /// Partial decryptions are flooded with a noise of width `MP_SD`
     NoiseFloodingDecrypt,
 }
 
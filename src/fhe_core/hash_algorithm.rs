@@ -1,4 +1,7 @@
 use crate::Error;
+use sha2::{Digest, Sha256, Sha512};
+use sha3::digest::{ExtendableOutput, Update, XofReader};
+use sha3::{Shake256, Shake256Reader};
 use std::fmt::{self, Display, Formatter};
 use std::str::FromStr;
 
@@ -12,6 +15,31 @@ pub enum HashAlgorithm {
     Sha256 = 0,
     /// SHA-512
     Sha512 = 1,
+    /// SHAKE-256, with 64-byte digests and its output stream read directly by
+    /// [`crate::fhe_core::PrngFromHash`]
+    Shake256 = 2,
+}
+
+impl HashAlgorithm {
+    /// The digest of `data`
+    pub fn digest(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Sha256 => Sha256::digest(data).to_vec(),
+            Self::Sha512 => Sha512::digest(data).to_vec(),
+            Self::Shake256 => {
+                let mut out = vec![0; 64];
+                Self::xof(data).read(&mut out);
+                out
+            }
+        }
+    }
+
+    /// The output stream of SHAKE-256 on `data`
+    pub(crate) fn xof(data: &[u8]) -> Shake256Reader {
+        let mut hasher = Shake256::default();
+        hasher.update(data);
+        hasher.finalize_xof()
+    }
 }
 
 impl TryFrom<u8> for HashAlgorithm {
@@ -21,6 +49,7 @@ impl TryFrom<u8> for HashAlgorithm {
         match value {
             0 => Ok(Self::Sha256),
             1 => Ok(Self::Sha512),
+            2 => Ok(Self::Shake256),
             _ => Err(Error::InvalidHashAlgorithm(value as usize)),
         }
     }
@@ -58,6 +87,7 @@ impl Display for HashAlgorithm {
         match self {
             Self::Sha256 => write!(f, "Sha256"),
             Self::Sha512 => write!(f, "Sha512"),
+            Self::Shake256 => write!(f, "Shake256"),
         }
     }
 }
@@ -69,8 +99,9 @@ impl FromStr for HashAlgorithm {
         match s {
             "Sha256" => Ok(Self::Sha256),
             "Sha512" => Ok(Self::Sha512),
+            "Shake256" => Ok(Self::Shake256),
             _ => Err(Error::ParseError(format!(
-                "invalid string when parsing HashAlgorithm: '{}'. Expected 'Sha256' or 'Sha512'",
+                "invalid string when parsing HashAlgorithm: '{}'. Expected 'Sha256', 'Sha512' or 'Shake256'",
                 s
             ))),
         }
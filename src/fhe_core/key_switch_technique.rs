@@ -7,9 +7,12 @@ use std::str::FromStr;
 #[cfg_attr(test, derive(Eq, PartialEq))]
 #[repr(u8)]
 pub enum KeySwitchTechnique {
+    /// No technique selected
     #[default]
     InvalidKsTech = 0,
+    /// Brakerski-Vaikuntanathan digit decomposition
     Bv,
+    /// Hybrid key switching with an auxiliary modulus
     Hybrid,
 }
 
@@ -1 +1,674 @@
+use crate::fhe_core::{barrett_reduction, Word};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+/// A word-sized modulus together with its precomputed Barrett constant and,
+/// for odd values, its Montgomery constants for R = 2^BITS. Operands are
+/// expected to already be reduced modulo `value`, except for the lazy variants
+/// that accept and return values in [0, 2 value) or [0, 4 value). The word
+/// defaults to `u64`, the word of the RNS primes of the schemes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Modulus<W: Word = u64> {
+    value: W,
+    /// floor((2^(2 BITS) - 1) / value) as a double word
+    mu: (W, W),
+    /// -value^-1 mod 2^BITS
+    montgomery_inv: W,
+    /// 2^(2 BITS) mod value
+    montgomery_r2: W,
+}
+
+impl<W: Word> From<W> for Modulus<W> {
+    fn from(value: W) -> Self {
+        Self::new(value)
+    }
+}
+
+macro_rules! modulus_into_word_impl {
+    ($($word:ty),*) => {
+        $(
+            impl From<Modulus<$word>> for $word {
+                fn from(modulus: Modulus<$word>) -> Self {
+                    modulus.value
+                }
+            }
+        )*
+    };
+}
+
+modulus_into_word_impl!(u32, u64, u128);
+
+impl<W: Word> Serialize for Modulus<W> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value.serialize(serializer)
+    }
+}
+
+impl<'de, W: Word> Deserialize<'de> for Modulus<W> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        W::deserialize(deserializer).map(Self::new)
+    }
+}
+
+impl<W: Word> Modulus<W> {
+    /// Create a new modulus, `value` must be in [2, 2^(BITS - 2))
+    pub fn new(value: W) -> Self {
+        debug_assert!(value > W::ONE && value.leading_zeros() >= 2);
+        // Newton iteration for value^-1 mod 2^BITS, each step doubling the
+        // number of correct low bits from the 1 bit of an odd value
+        let mut inv = W::ONE;
+        let mut correct_bits = 1;
+        while correct_bits < W::BITS {
+            let two = W::ONE + W::ONE;
+            inv = inv.wrapping_mul(two.wrapping_sub(value.wrapping_mul(inv)));
+            correct_bits <<= 1;
+        }
+        let max = W::ZERO.wrapping_sub(W::ONE);
+        let (mu_lo, _) = W::div_rem_wide(max % value, max, value);
+        let mut modulus = Self {
+            value,
+            mu: (max / value, mu_lo),
+            montgomery_inv: W::ZERO.wrapping_sub(inv),
+            montgomery_r2: W::ZERO,
+        };
+        let r = (max % value + W::ONE) % value;
+        modulus.montgomery_r2 = modulus.mul(r, r);
+        modulus
+    }
+
+    /// The modulus value
+    #[inline]
+    pub fn value(&self) -> W {
+        self.value
+    }
+
+    /// Reduce an arbitrary word
+    #[inline]
+    pub fn reduce(&self, a: W) -> W {
+        a % self.value
+    }
+
+    /// Reduce a double word (high, low) smaller than `value^2`
+    #[inline]
+    pub fn reduce_wide(&self, a: (W, W)) -> W {
+        barrett_reduction(a, self.value, self.mu)
+    }
+
+    /// Map a signed integer into [0, value)
+    #[inline]
+    pub fn reduce_i64(&self, a: i64) -> W {
+        let r = self.reduce(W::from_u64(a.unsigned_abs()));
+        if a < 0 {
+            self.neg(r)
+        } else {
+            r
+        }
+    }
+
+    /// (a + b) mod value
+    #[inline]
+    pub fn add(&self, a: W, b: W) -> W {
+        let r = a + b;
+        if r >= self.value {
+            r - self.value
+        } else {
+            r
+        }
+    }
+
+    /// (a - b) mod value
+    #[inline]
+    pub fn sub(&self, a: W, b: W) -> W {
+        if a >= b {
+            a - b
+        } else {
+            a + self.value - b
+        }
+    }
+
+    /// -a mod value
+    #[inline]
+    pub fn neg(&self, a: W) -> W {
+        if a == W::ZERO {
+            W::ZERO
+        } else {
+            self.value - a
+        }
+    }
+
+    /// (a * b) mod value
+    #[inline]
+    pub fn mul(&self, a: W, b: W) -> W {
+        self.reduce_wide(a.mul_wide(b))
+    }
+
+    /// The Shoup quotient floor(b * 2^BITS / value) of a fixed operand `b`
+    #[inline]
+    pub fn shoup(&self, b: W) -> W {
+        W::div_rem_wide(b, W::ZERO, self.value).0
+    }
+
+    /// (a * b) mod value for the Shoup quotient `b_shoup` of `b`, in [0, 2
+    /// value) for any word `a`
+    #[inline]
+    pub fn mul_shoup_lazy(&self, a: W, b: W, b_shoup: W) -> W {
+        let (quotient, _) = a.mul_wide(b_shoup);
+        a.wrapping_mul(b)
+            .wrapping_sub(quotient.wrapping_mul(self.value))
+    }
+
+    /// (a * b) mod value for the Shoup quotient `b_shoup` of `b`
+    #[inline]
+    pub fn mul_shoup(&self, a: W, b: W, b_shoup: W) -> W {
+        self.reduce_2q(self.mul_shoup_lazy(a, b, b_shoup))
+    }
+
+    /// a * 2^-BITS mod value for a double word `a < value * 2^BITS`, in [0, 2
+    /// value). Only defined for odd values.
+    #[inline]
+    pub fn montgomery_reduce_lazy(&self, a: (W, W)) -> W {
+        debug_assert!(self.value & W::ONE == W::ONE);
+        let (a_hi, a_lo) = a;
+        let (m_hi, m_lo) = a_lo.wrapping_mul(self.montgomery_inv).mul_wide(self.value);
+        // the low words cancel out, only their carry is left
+        let (_, carry) = a_lo.overflowing_add(m_lo);
+        let carry = if carry { W::ONE } else { W::ZERO };
+        a_hi + m_hi + carry
+    }
+
+    /// a * 2^-BITS mod value for a double word `a < value * 2^BITS`. Only
+    /// defined for odd values.
+    #[inline]
+    pub fn montgomery_reduce(&self, a: (W, W)) -> W {
+        self.reduce_2q(self.montgomery_reduce_lazy(a))
+    }
+
+    /// The Montgomery form a * 2^BITS mod value of `a`
+    #[inline]
+    pub fn montgomery_form(&self, a: W) -> W {
+        self.montgomery_reduce(a.mul_wide(self.montgomery_r2))
+    }
+
+    /// The residue a * 2^-BITS mod value behind the Montgomery form `a`
+    #[inline]
+    pub fn montgomery_residue(&self, a: W) -> W {
+        self.montgomery_reduce((W::ZERO, a))
+    }
+
+    /// The Montgomery form of the product of two Montgomery forms, which may
+    /// be lazy in [0, 2 value)
+    #[inline]
+    pub fn mul_montgomery(&self, a: W, b: W) -> W {
+        self.montgomery_reduce(a.mul_wide(b))
+    }
+
+    /// Map a value in [0, 2 value) into [0, value)
+    #[inline]
+    pub fn reduce_2q(&self, a: W) -> W {
+        if a >= self.value {
+            a - self.value
+        } else {
+            a
+        }
+    }
+
+    /// Map a value in [0, 4 value) into [0, value)
+    #[inline]
+    pub fn reduce_4q(&self, a: W) -> W {
+        let two_q = self.value << 1;
+        self.reduce_2q(if a >= two_q { a - two_q } else { a })
+    }
+
+    /// a + b for `a`, `b` in [0, 2 value), in [0, 2 value)
+    #[inline]
+    pub fn add_lazy(&self, a: W, b: W) -> W {
+        let (r, two_q) = (a + b, self.value << 1);
+        if r >= two_q {
+            r - two_q
+        } else {
+            r
+        }
+    }
+
+    /// a - b + 2 value for `a`, `b` in [0, 2 value), in [0, 4 value)
+    #[inline]
+    pub fn sub_lazy(&self, a: W, b: W) -> W {
+        a + (self.value << 1) - b
+    }
+
+    /// base^exp mod value
+    pub fn pow(&self, base: W, mut exp: W) -> W {
+        let mut result = W::ONE % self.value;
+        let mut base = self.reduce(base);
+        while exp > W::ZERO {
+            if exp & W::ONE == W::ONE {
+                result = self.mul(result, base);
+            }
+            base = self.mul(base, base);
+            exp = exp >> 1;
+        }
+        result
+    }
+
+    /// Multiplicative inverse of `a`, if it exists
+    pub fn inv(&self, a: W) -> Option<W> {
+        // extended Euclid, the Bezout coefficient of `a` kept modulo value
+        let (mut t, mut new_t) = (W::ZERO, W::ONE);
+        let (mut r, mut new_r) = (self.value, self.reduce(a));
+        while new_r != W::ZERO {
+            let quotient = r / new_r;
+            (t, new_t) = (new_t, self.sub(t, self.mul(quotient, new_t)));
+            (r, new_r) = (new_r, r - quotient * new_r);
+        }
+        if r != W::ONE {
+            return None;
+        }
+        Some(t)
+    }
+}
+
+impl Modulus {
+    /// Reduce a 128-bit integer smaller than `value^2`
+    #[inline]
+    pub fn reduce_u128(&self, a: u128) -> u64 {
+        self.reduce_wide(((a >> 64) as u64, a as u64))
+    }
+
+    /// Map a residue into the centered interval (-value/2, value/2]
+    #[inline]
+    pub fn center(&self, a: u64) -> i64 {
+        if a > self.value >> 1 {
+            a as i64 - self.value as i64
+        } else {
+            a as i64
+        }
+    }
+}
+
+/// Miller-Rabin primality test with the first 12 primes as bases, deterministic
+/// below 3.3 * 10^24 and so for every 64-bit integer, a strong probable prime
+/// test above
+pub fn is_prime<W: Word>(n: W) -> bool {
+    const BASES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+    if n < W::from_u64(2) {
+        return false;
+    }
+    for p in BASES.map(W::from_u64) {
+        if n % p == W::ZERO {
+            return n == p;
+        }
+    }
+
+    let n_minus_one = n - W::ONE;
+    let mut d = n_minus_one;
+    let mut s = 0;
+    while d & W::ONE == W::ZERO {
+        d = d >> 1;
+        s += 1;
+    }
+
+    let mul = |a: W, b: W| {
+        let (hi, lo) = a.mul_wide(b);
+        W::div_rem_wide(hi, lo, n).1
+    };
+    let pow = |mut base: W, mut exp: W| {
+        let mut result = W::ONE;
+        while exp > W::ZERO {
+            if exp & W::ONE == W::ONE {
+                result = mul(result, base);
+            }
+            base = mul(base, base);
+            exp = exp >> 1;
+        }
+        result
+    };
+
+    'witness: for a in BASES.map(W::from_u64) {
+        let mut x = pow(a, d);
+        if x == W::ONE || x == n_minus_one {
+            continue;
+        }
+        for _ in 1..s {
+            x = mul(x, x);
+            if x == n_minus_one {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+/// The largest prime `q < 2^bits` with `q = 1 mod m`
+pub fn last_prime<W: Word>(bits: usize, m: W) -> Option<W> {
+    if bits >= W::BITS as usize {
+        return None;
+    }
+    let bound = W::ONE << bits as u32;
+    previous_prime(bound - (bound - W::ONE) % m + m, m)
+}
+
+/// The largest prime `p < q` with `p = 1 mod m`, `q` must be `1 mod m`
+pub fn previous_prime<W: Word>(q: W, m: W) -> Option<W> {
+    let mut candidate = q;
+    loop {
+        if candidate < m {
+            return None;
+        }
+        candidate = candidate - m;
+        if is_prime(candidate) {
+            return Some(candidate);
+        }
+    }
+}
+
+/// A primitive `m`-th root of unity modulo the prime `modulus`, `m` must be a
+/// power of two dividing `modulus - 1`
+pub fn root_of_unity<W: Word>(m: W, modulus: &Modulus<W>) -> Option<W> {
+    let q_minus_one = modulus.value() - W::ONE;
+    if m == W::ZERO || m & (m - W::ONE) != W::ZERO || q_minus_one % m != W::ZERO {
+        return None;
+    }
+    let cofactor = q_minus_one / m;
+    let mut x = W::from_u64(2);
+    while x < modulus.value() {
+        let root = modulus.pow(x, cofactor);
+        if modulus.pow(root, m >> 1) == q_minus_one {
+            return Some(root);
+        }
+        x = x + W::ONE;
+    }
+    None
+}
+
+/// Decompose `x` into `digits.len()` signed digits in base `2^log_base`, all but
+/// the most significant digit lying in [-base/2, base/2)
+pub fn signed_digits(mut x: i64, log_base: u32, digits: &mut [i64]) {
+    let base = 1i64 << log_base;
+    let half = base >> 1;
+    let last = digits.len() - 1;
+    for digit in digits[..last].iter_mut() {
+        let mut d = x & (base - 1);
+        if d >= half {
+            d -= base;
+        }
+        *digit = d;
+        x = (x - d) >> log_base;
+    }
+    digits[last] = x;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+
+    /// Every modulus below 64 and a prime above
+    fn small_moduli() -> impl Iterator<Item = Modulus> {
+        (2..64).chain([97]).map(Modulus::new)
+    }
+
+    /// The primes closest to the bound of 2^62, where the lazy ranges leave
+    /// the fewest spare bits
+    fn large_moduli() -> Vec<Modulus> {
+        let q1 = last_prime(62, 1 << 17).unwrap();
+        let q2 = previous_prime(q1, 1 << 17).unwrap();
+        vec![
+            Modulus::new(q1),
+            Modulus::new(q2),
+            Modulus::new((1 << 62) - 1),
+        ]
+    }
+
+    /// The reference (a * b) mod value, by Barrett reduction of the product
+    fn product<W: Word>(m: &Modulus<W>, a: W, b: W) -> W {
+        let a = barrett_reduction((W::ZERO, a), m.value(), m.mu);
+        let b = barrett_reduction((W::ZERO, b), m.value(), m.mu);
+        barrett_reduction(a.mul_wide(b), m.value(), m.mu)
+    }
+
+    /// 2^BITS mod value
+    fn r<W: Word>(m: &Modulus<W>) -> W {
+        barrett_reduction((W::ONE, W::ZERO), m.value(), m.mu)
+    }
+
+    /// Check every kernel on the operands `a` and `b` in [0, 4 value)
+    fn check<W: Word>(m: &Modulus<W>, a: W, b: W) {
+        let q = m.value();
+        let (two_q, four_q) = (q << 1, q << 2);
+        let (a1, b1) = (a % q, b % q);
+        let (a2, b2) = (a % two_q, b % two_q);
+
+        assert_eq!(m.reduce(a), product(m, a, W::ONE));
+        assert_eq!(m.reduce_wide(a1.mul_wide(b1)), product(m, a1, b1));
+        assert_eq!(m.mul(a1, b1), product(m, a1, b1));
+        assert_eq!(m.add(a1, b1), product(m, a1 + b1, W::ONE));
+        assert_eq!(m.sub(a1, b1), product(m, a1 + q - b1, W::ONE));
+        assert_eq!(m.add(m.neg(a1), a1), W::ZERO);
+        assert_eq!(m.reduce_2q(a2), product(m, a2, W::ONE));
+        assert_eq!(m.reduce_4q(a), product(m, a, W::ONE));
+
+        let sum = m.add_lazy(a2, b2);
+        assert!(sum < two_q);
+        assert_eq!(m.reduce_2q(sum), product(m, a2 + b2, W::ONE));
+        let difference = m.sub_lazy(a2, b2);
+        assert!(difference < four_q);
+        assert_eq!(m.reduce_4q(difference), product(m, a2 + two_q - b2, W::ONE));
+
+        // any word times a reduced operand
+        let max = W::ZERO.wrapping_sub(W::ONE);
+        let odd = W::from_u64(0x9e37_79b9_7f4a_7c15);
+        for x in [a, a.wrapping_mul(odd), max - a] {
+            let lazy = m.mul_shoup_lazy(x, b1, m.shoup(b1));
+            assert!(lazy < two_q);
+            assert_eq!(m.reduce_2q(lazy), product(m, x, b1));
+            assert_eq!(m.mul_shoup(x, b1, m.shoup(b1)), product(m, x, b1));
+        }
+
+        if q & W::ONE == W::ONE {
+            // a * b < value * 2^BITS for the lazy operands
+            let lazy = m.montgomery_reduce_lazy(a2.mul_wide(b2));
+            assert!(lazy < two_q);
+            assert_eq!(product(m, lazy, r(m)), product(m, a2, b2));
+            let lazy = m.montgomery_reduce_lazy((a1, b));
+            assert!(lazy < two_q);
+            assert_eq!(
+                product(m, lazy, r(m)),
+                product(m, product(m, a1, r(m)) + b % q, W::ONE)
+            );
+            let (x, y) = (m.montgomery_form(a1), m.montgomery_form(b1));
+            assert_eq!(x, product(m, a1, r(m)));
+            assert_eq!(m.montgomery_residue(x), a1);
+            assert_eq!(
+                m.montgomery_residue(m.mul_montgomery(x, y)),
+                product(m, a1, b1)
+            );
+            // lazy Montgomery forms
+            assert_eq!(m.mul_montgomery(x + q, y + q), m.mul_montgomery(x, y));
+        }
+    }
+
+    #[test]
+    fn kernels_on_small_moduli() {
+        for m in small_moduli() {
+            let q = m.value();
+            for a in 0..4 * q {
+                for b in 0..4 * q {
+                    check(&m, a, b);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn kernels_near_the_largest_modulus() {
+        let mut rng = ChaCha20Rng::from_seed([1; 32]);
+        for m in large_moduli() {
+            let q = m.value();
+            check_edges(&m);
+            for _ in 0..100_000 {
+                check(&m, rng.gen_range(0..4 * q), rng.gen_range(0..4 * q));
+            }
+        }
+    }
+
+    /// Check every kernel on the pairs of the bounds of the lazy ranges
+    fn check_edges<W: Word>(m: &Modulus<W>) {
+        let q = m.value();
+        let edges = [
+            W::ZERO,
+            W::ONE,
+            q - W::ONE,
+            q,
+            q + W::ONE,
+            (q << 1) - W::ONE,
+            q << 1,
+            (q << 2) - W::ONE,
+        ];
+        for a in edges {
+            for b in edges {
+                check(m, a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn kernels_of_narrow_words() {
+        let mut rng = ChaCha20Rng::from_seed([3; 32]);
+        let moduli = [
+            3,
+            last_prime(28, 1u32 << 11).unwrap(),
+            (1 << 28) - 1,
+            (1 << 30) - 1,
+        ];
+        for m in moduli.map(Modulus::<u32>::new) {
+            let q = m.value();
+            check_edges(&m);
+            for _ in 0..100_000 {
+                check(&m, rng.gen_range(0..4 * q), rng.gen_range(0..4 * q));
+            }
+        }
+    }
+
+    #[test]
+    fn kernels_of_wide_words() {
+        let mut rng = ChaCha20Rng::from_seed([4; 32]);
+        let moduli = [
+            3,
+            (1 << 64) - 59,
+            (1 << 64) + 13,
+            last_prime(100, 1u128 << 17).unwrap(),
+            last_prime(121, 1u128 << 17).unwrap(),
+            (1 << 126) - 1,
+        ];
+        for m in moduli.map(Modulus::<u128>::new) {
+            let q = m.value();
+            check_edges(&m);
+            for _ in 0..20_000 {
+                check(&m, rng.gen_range(0..4 * q), rng.gen_range(0..4 * q));
+            }
+        }
+    }
+
+    #[test]
+    fn barrett_reduction_of_double_words() {
+        let mut rng = ChaCha20Rng::from_seed([2; 32]);
+        for m in small_moduli().chain(large_moduli()) {
+            let q = m.value() as u128;
+            for _ in 0..10_000 {
+                let a = rng.gen_range(0..q * q);
+                assert_eq!(m.reduce_u128(a) as u128, a % q);
+            }
+        }
+    }
+
+    #[test]
+    fn signed_and_inverse() {
+        for m in small_moduli() {
+            let q = m.value();
+            for a in -(2 * q as i64)..2 * q as i64 {
+                let r = m.reduce_i64(a);
+                assert_eq!(r as i64, a.rem_euclid(q as i64));
+                assert_eq!(m.reduce_i64(m.center(r)), r);
+                assert!(2 * m.center(r).unsigned_abs() <= q);
+            }
+            for a in 0..q {
+                match m.inv(a) {
+                    Some(inv) => assert_eq!(m.mul(a, inv), 1 % q),
+                    None => assert!((1..q).all(|b| m.mul(a, b) != 1)),
+                }
+            }
+        }
+        let m = &large_moduli()[0];
+        let q = m.value();
+        assert_eq!(m.pow(3, q - 1), 1);
+        assert_eq!(m.mul(m.pow(5, q - 2), 5), 1);
+    }
+
+    #[test]
+    fn primes_and_roots() {
+        let primes: Vec<u64> = (0..100).filter(|&n| is_prime(n)).collect();
+        assert_eq!(primes.len(), 25);
+        assert!(!is_prime(3_215_031_751u64));
+        let q = last_prime(50, 1u64 << 13).unwrap();
+        assert!(is_prime(q) && q < 1 << 50 && q % (1 << 13) == 1);
+        let m = Modulus::new(q);
+        let root = root_of_unity(1 << 13, &m).unwrap();
+        assert_eq!(m.pow(root, 1 << 12), q - 1);
+        assert!(root_of_unity(3, &m).is_none());
+    }
+
+    /// Check that `q` is the largest prime below 2^bits that is 1 mod `m`
+    fn check_last_prime<W: Word>(bits: usize, m: W, q: W) {
+        let bound = W::ONE << bits as u32;
+        assert!(is_prime(q) && q < bound && q % m == W::ONE);
+        let mut candidate = q + m;
+        while candidate < bound {
+            assert!(!is_prime(candidate));
+            candidate = candidate + m;
+        }
+        assert_eq!(previous_prime(candidate, m), Some(q));
+    }
+
+    #[test]
+    fn primes_and_roots_of_every_word() {
+        assert!(is_prime((1u32 << 17) - 1) && is_prime((1u32 << 19) - 1));
+        assert!(!is_prime(561u32) && !is_prime(3_215_031_751u32));
+        assert!(is_prime((1u128 << 89) - 1) && is_prime((1u128 << 107) - 1));
+        assert!(!is_prime((1u128 << 89) + 1));
+        assert!(!is_prime(((1u128 << 61) - 1) * ((1 << 31) - 1)));
+        assert!(!is_prime(3_215_031_751u128));
+
+        let m = 1u32 << 11;
+        check_last_prime(28, m, last_prime(28, m).unwrap());
+        assert_eq!(last_prime(32, m), None);
+        let m = 1u128 << 17;
+        check_last_prime(121, m, last_prime(121, m).unwrap());
+        check_last_prime(64, m, last_prime(64, m).unwrap());
+        assert_eq!(last_prime(128, m), None);
+        assert_eq!(previous_prime(1u128, 1), None);
+
+        let q = last_prime(121, 1u128 << 17).unwrap();
+        let modulus = Modulus::new(q);
+        let root = root_of_unity(1 << 17, &modulus).unwrap();
+        assert_eq!(modulus.pow(root, 1 << 16), q - 1);
+        assert_eq!(modulus.mul(modulus.inv(root).unwrap(), root), 1);
+        let q = last_prime(28, 1u32 << 11).unwrap();
+        let modulus = Modulus::new(q);
+        let root = root_of_unity(1 << 11, &modulus).unwrap();
+        assert_eq!(modulus.pow(root, 1 << 10), q - 1);
+    }
+
+    #[test]
+    fn signed_digits_recompose() {
+        let mut digits = [0; 5];
+        for x in [0, 1, -1, 12345, -98765, i32::MAX as i64, i32::MIN as i64] {
+            signed_digits(x, 8, &mut digits);
+            let recomposed = digits.iter().rev().fold(0, |acc, &d| (acc << 8) + d);
+            assert_eq!(recomposed, x);
+            assert!(digits[..4].iter().all(|d| (-128..128).contains(d)));
+        }
+    }
+}
@@ -2,13 +2,17 @@ use crate::Error;
 use std::fmt::{self, Display, Formatter};
 use std::str::FromStr;
 
+/// The noise management of the threshold protocols
 #[derive(Debug, Default, Clone, Copy)]
 #[cfg_attr(test, derive(Eq, PartialEq))]
 #[repr(u8)]
 pub enum MultipartyMode {
+    /// No multiparty mode selected
     #[default]
     InvalidMultipartyMode = 0,
+    /// Protocols with a fixed noise, secure for a bounded number of parties
     FixedNoiseMultiparty,
+    /// Protocols flooding the noise, with extra moduli to absorb it
     NoiseFloodingMultiparty,
 }
 
@@ -0,0 +1,193 @@
+use crate::fhe_core::{root_of_unity, Modulus, Word};
+
+/// Precomputed tables for the negacyclic number theoretic transform over
+/// Z_q[X]/(X^N + 1). The forward transform takes coefficients in natural order
+/// to evaluations in bit-reversed order, the inverse transform undoes it.
+/// Both use Harvey's butterflies, multiplying by the twiddle factors with
+/// their Shoup quotients and keeping the values lazily reduced in [0, 4q).
+/// The butterflies of a group run on the slice kernels of the [`Word`], the
+/// vector kernels of [`SimdLevel`](crate::fhe_core::SimdLevel) for `u64`.
+#[derive(Debug, Clone)]
+pub struct NttTables<W: Word = u64> {
+    ring_dim: usize,
+    modulus: Modulus<W>,
+    psi_rev: Vec<W>,
+    psi_rev_shoup: Vec<W>,
+    psi_inv_rev: Vec<W>,
+    psi_inv_rev_shoup: Vec<W>,
+    ring_dim_inv: W,
+    ring_dim_inv_shoup: W,
+}
+
+impl<W: Word> NttTables<W> {
+    /// Create the tables for ring dimension `ring_dim` (a power of two) and a
+    /// prime modulus `q = 1 mod 2 * ring_dim`
+    pub fn new(ring_dim: usize, modulus: Modulus<W>) -> Option<Self> {
+        if !ring_dim.is_power_of_two() {
+            return None;
+        }
+        let psi = root_of_unity(W::from_u64(2 * ring_dim as u64), &modulus)?;
+        let psi_inv = modulus.inv(psi)?;
+        let ring_dim_inv = modulus.inv(W::from_u64(ring_dim as u64))?;
+
+        let log_n = ring_dim.trailing_zeros();
+        let mut psi_rev = vec![W::ZERO; ring_dim];
+        let mut psi_inv_rev = vec![W::ZERO; ring_dim];
+        let (mut power, mut power_inv) = (W::ONE, W::ONE);
+        for i in 0..ring_dim {
+            let j = bit_reverse(i, log_n);
+            psi_rev[j] = power;
+            psi_inv_rev[j] = power_inv;
+            power = modulus.mul(power, psi);
+            power_inv = modulus.mul(power_inv, psi_inv);
+        }
+
+        let shoup = |table: &[W]| table.iter().map(|&w| modulus.shoup(w)).collect();
+        Some(Self {
+            ring_dim,
+            modulus,
+            psi_rev_shoup: shoup(&psi_rev),
+            psi_rev,
+            psi_inv_rev_shoup: shoup(&psi_inv_rev),
+            psi_inv_rev,
+            ring_dim_inv,
+            ring_dim_inv_shoup: modulus.shoup(ring_dim_inv),
+        })
+    }
+
+    /// The ring dimension N
+    pub fn ring_dim(&self) -> usize {
+        self.ring_dim
+    }
+
+    /// The modulus q
+    pub fn modulus(&self) -> &Modulus<W> {
+        &self.modulus
+    }
+
+    /// In-place forward transform (Cooley-Tukey butterflies)
+    pub fn forward(&self, a: &mut [W]) {
+        debug_assert_eq!(a.len(), self.ring_dim);
+        let q = &self.modulus;
+        let mut t = self.ring_dim;
+        let mut m = 1;
+        while m < self.ring_dim {
+            t >>= 1;
+            for i in 0..m {
+                let j1 = 2 * i * t;
+                let (x, y) = a[j1..j1 + 2 * t].split_at_mut(t);
+                let (s, s_shoup) = (self.psi_rev[m + i], self.psi_rev_shoup[m + i]);
+                W::ct_butterflies(x, y, s, s_shoup, q);
+            }
+            m <<= 1;
+        }
+        W::reduce_4q_slice(a, q);
+    }
+
+    /// In-place inverse transform (Gentleman-Sande butterflies)
+    pub fn inverse(&self, a: &mut [W]) {
+        debug_assert_eq!(a.len(), self.ring_dim);
+        let q = &self.modulus;
+        let mut t = 1;
+        let mut m = self.ring_dim;
+        while m > 1 {
+            let h = m >> 1;
+            for i in 0..h {
+                let j1 = 2 * i * t;
+                let (x, y) = a[j1..j1 + 2 * t].split_at_mut(t);
+                let (s, s_shoup) = (self.psi_inv_rev[h + i], self.psi_inv_rev_shoup[h + i]);
+                W::gs_butterflies(x, y, s, s_shoup, q);
+            }
+            t <<= 1;
+            m = h;
+        }
+        W::mul_shoup_slice(a, self.ring_dim_inv, self.ring_dim_inv_shoup, q, false);
+    }
+}
+
+fn bit_reverse(x: usize, bits: u32) -> usize {
+    if bits == 0 {
+        0
+    } else {
+        x.reverse_bits() >> (usize::BITS - bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fhe_core::last_prime;
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::{RngCore, SeedableRng};
+
+    /// A uniform residue modulo `q`, up to a negligible bias
+    fn uniform<W: Word>(rng: &mut ChaCha20Rng, q: &Modulus<W>) -> W {
+        let mut x = W::from_u64(rng.next_u64());
+        if W::BITS > 64 {
+            x = x << 64 | W::from_u64(rng.next_u64());
+        }
+        q.reduce(x)
+    }
+
+    /// The negacyclic product of `a` and `b` by schoolbook multiplication
+    fn negacyclic_product<W: Word>(a: &[W], b: &[W], q: &Modulus<W>) -> Vec<W> {
+        let n = a.len();
+        let mut c = vec![W::ZERO; n];
+        for (i, &x) in a.iter().enumerate() {
+            for (j, &y) in b.iter().enumerate() {
+                let p = q.mul(x, y);
+                let k = (i + j) % n;
+                c[k] = if i + j < n {
+                    q.add(c[k], p)
+                } else {
+                    q.sub(c[k], p)
+                };
+            }
+        }
+        c
+    }
+
+    /// Check the transforms of the largest prime of `bits` bits for every ring
+    /// dimension up to 2^log_max
+    fn check<W: Word>(bits: usize, log_max: u32) {
+        let mut rng = ChaCha20Rng::from_seed([6; 32]);
+        for ring_dim in (0..=log_max).map(|k| 1 << k) {
+            let m = W::from_u64(2 * ring_dim as u64);
+            let q = Modulus::new(last_prime(bits, m).unwrap());
+            let tables = NttTables::new(ring_dim, q).unwrap();
+            assert_eq!(tables.ring_dim(), ring_dim);
+            let a: Vec<W> = (0..ring_dim).map(|_| uniform(&mut rng, &q)).collect();
+            let b: Vec<W> = (0..ring_dim).map(|_| uniform(&mut rng, &q)).collect();
+            let (mut a_hat, mut b_hat) = (a.clone(), b.clone());
+            tables.forward(&mut a_hat);
+            tables.forward(&mut b_hat);
+            assert!(a_hat.iter().all(|&x| x < q.value()));
+            let mut c: Vec<W> = a_hat
+                .iter()
+                .zip(&b_hat)
+                .map(|(&x, &y)| q.mul(x, y))
+                .collect();
+            tables.inverse(&mut c);
+            assert_eq!(c, negacyclic_product(&a, &b, &q));
+            tables.inverse(&mut a_hat);
+            assert_eq!(a_hat, a);
+        }
+    }
+
+    #[test]
+    fn transforms_of_every_word() {
+        check::<u32>(28, 8);
+        check::<u64>(60, 8);
+        check::<u128>(121, 8);
+        check::<u128>(80, 8);
+    }
+
+    #[test]
+    fn invalid_tables_are_rejected() {
+        // 12289 = 3 * 2^12 + 1
+        let q = Modulus::new(12289u64);
+        assert!(NttTables::new(1 << 11, q).is_some());
+        assert!(NttTables::new(3 << 8, q).is_none());
+        assert!(NttTables::new(1 << 12, q).is_none());
+    }
+}
@@ -0,0 +1,145 @@
+//! Data parallelism over the towers of a polynomial, the digits of a key
+//! switch and batches of keys or ciphertexts. With the `parallel` feature the
+//! items are processed on the current rayon pool, that of the context inside
+//! [`ThreadPool::install`]; without it they are processed in order. Either way
+//! the results are the same.
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+#[cfg(feature = "parallel")]
+use std::sync::Arc;
+
+/// f(i, x_i) for every item x_i of `items`
+pub(crate) fn for_each_mut<T, F>(items: &mut [T], f: F)
+where
+    T: Send,
+    F: Fn(usize, &mut T) + Sync + Send,
+{
+    #[cfg(feature = "parallel")]
+    items.par_iter_mut().enumerate().for_each(|(i, x)| f(i, x));
+    #[cfg(not(feature = "parallel"))]
+    items.iter_mut().enumerate().for_each(|(i, x)| f(i, x));
+}
+
+/// [f(i, x_i)] for the items x_i of `items`, in order
+pub(crate) fn map<T, U, F>(items: &[T], f: F) -> Vec<U>
+where
+    T: Sync,
+    U: Send,
+    F: Fn(usize, &T) -> U + Sync + Send,
+{
+    #[cfg(feature = "parallel")]
+    let out = items.par_iter().enumerate().map(|(i, x)| f(i, x)).collect();
+    #[cfg(not(feature = "parallel"))]
+    let out = items.iter().enumerate().map(|(i, x)| f(i, x)).collect();
+    out
+}
+
+/// combine(... combine(identity(), f(0, x_0)) ..., f(n - 1, x_{n - 1})) for an
+/// associative and commutative `combine` of neutral element `identity()`
+pub(crate) fn map_reduce<T, U, F, I, C>(items: &[T], identity: I, f: F, combine: C) -> U
+where
+    T: Sync,
+    U: Send,
+    F: Fn(usize, &T) -> U + Sync + Send,
+    I: Fn() -> U + Sync + Send,
+    C: Fn(U, U) -> U + Sync + Send,
+{
+    #[cfg(feature = "parallel")]
+    let out = items
+        .par_iter()
+        .enumerate()
+        .map(|(i, x)| f(i, x))
+        .reduce(identity, combine);
+    #[cfg(not(feature = "parallel"))]
+    let out = items
+        .iter()
+        .enumerate()
+        .map(|(i, x)| f(i, x))
+        .fold(identity(), combine);
+    out
+}
+
+/// The threads running the parallel operations of a context: the global rayon
+/// pool unless a pool of its own was configured, shared by the clones of the
+/// context. Without the `parallel` feature every operation runs on the calling
+/// thread.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ThreadPool {
+    #[cfg(feature = "parallel")]
+    pool: Option<Arc<rayon::ThreadPool>>,
+}
+
+impl ThreadPool {
+    /// A pool of `num_threads` threads, as many as the CPU has logical cores
+    /// if 0
+    #[cfg(feature = "parallel")]
+    pub(crate) fn new(num_threads: usize) -> Result<Self, rayon::ThreadPoolBuildError> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()?;
+        Ok(Self {
+            pool: Some(Arc::new(pool)),
+        })
+    }
+
+    /// The number of threads of the pool
+    #[cfg(feature = "parallel")]
+    pub(crate) fn num_threads(&self) -> usize {
+        self.pool
+            .as_ref()
+            .map_or_else(rayon::current_num_threads, |pool| {
+                pool.current_num_threads()
+            })
+    }
+
+    /// Run `op`, the parallel loops it contains running on the pool
+    pub(crate) fn install<R, F>(&self, op: F) -> R
+    where
+        R: Send,
+        F: FnOnce() -> R + Send,
+    {
+        #[cfg(feature = "parallel")]
+        if let Some(pool) = &self.pool {
+            return pool.install(op);
+        }
+        op()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pools() -> Vec<ThreadPool> {
+        #[cfg(feature = "parallel")]
+        let pools = vec![
+            ThreadPool::default(),
+            ThreadPool::new(1).unwrap(),
+            ThreadPool::new(5).unwrap(),
+        ];
+        #[cfg(not(feature = "parallel"))]
+        let pools = vec![ThreadPool::default()];
+        pools
+    }
+
+    #[test]
+    fn results_keep_the_order_of_the_items() {
+        let items: Vec<u64> = (0..1000).collect();
+        for pool in pools() {
+            let mapped = pool.install(|| map(&items, |i, &x| (i as u64, x * x)));
+            assert!(mapped.iter().zip(&items).all(|(&y, &x)| y == (x, x * x)));
+
+            let mut updated = items.clone();
+            pool.install(|| for_each_mut(&mut updated, |i, x| *x += i as u64));
+            assert!(updated.iter().zip(&items).all(|(&y, &x)| y == 2 * x));
+
+            let sum = pool.install(|| map_reduce(&items, || 0, |i, &x| i as u64 + x, |a, b| a + b));
+            assert_eq!(sum, 999 * 1000);
+            assert_eq!(
+                map_reduce(&Vec::<u64>::new(), || 7, |_, &x| x, |a, b| a + b),
+                7
+            );
+        }
+    }
+}
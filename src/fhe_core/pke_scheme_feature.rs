@@ -15,10 +15,15 @@ pub enum PkeSchemeFeature {
     KeySwitch = 0x02,
     /// Proxy Re-Encryption
     Pre = 0x04,
+    /// Leveled Somewhat Homomorphic Encryption
     LeveledShe = 0x08,
+    /// Advanced Somewhat Homomorphic Encryption
     AdvancedShe = 0x10,
+    /// Threshold Multiparty Encryption
     MultiParty = 0x20,
+    /// Fully Homomorphic Encryption
     Fhe = 0x40,
+    /// Scheme Switching
     SchemeSwitch = 0x80,
 }
 
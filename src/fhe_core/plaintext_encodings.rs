@@ -7,11 +7,16 @@ use std::str::FromStr;
 #[cfg_attr(test, derive(Eq, PartialEq))]
 #[repr(u8)]
 pub enum PlaintextEncodings {
+    /// No encoding
     #[default]
     Invalid = 0,
+    /// Values as polynomial coefficients
     CoefPacked,
+    /// Values in the slots of the CRT decomposition
     Packed,
+    /// Bytes of a string as coefficients
     String,
+    /// Complex values in CKKS slots
     CkksPacked,
 }
 
@@ -0,0 +1,222 @@
+use crate::fhe_core::{HashAlgorithm, Modulus};
+use rand_core::{CryptoRng, RngCore};
+use sha3::digest::XofReader;
+use sha3::Shake256Reader;
+use std::fmt::{self, Debug, Formatter};
+
+/// The length in bytes of the seeds expanded by [`PrngFromHash`]
+pub const SEED_LEN: usize = 32;
+
+/// A deterministic generator expanding a seed with a hash function, the domain
+/// separating the streams of a single seed. SHA-256 and SHA-512 are used in
+/// counter mode, the output stream being H(seed || domain || 0) || H(seed ||
+/// domain || 1) || ... with the counters as little-endian u64, while SHAKE-256
+/// outputs its stream on seed || domain directly. Anyone holding the seed and
+/// the domain can recompute the stream, which makes it suitable for common
+/// reference strings.
+#[derive(Clone)]
+pub struct PrngFromHash {
+    stream: Stream,
+}
+
+#[derive(Clone)]
+enum Stream {
+    Counter {
+        hash_algorithm: HashAlgorithm,
+        prefix: Vec<u8>,
+        counter: u64,
+        block: Vec<u8>,
+        position: usize,
+    },
+    Xof(Box<Shake256Reader>),
+}
+
+impl PrngFromHash {
+    /// The stream of `seed` in `domain`
+    pub fn new(hash_algorithm: HashAlgorithm, seed: &[u8; SEED_LEN], domain: &[u8]) -> Self {
+        let mut prefix = seed.to_vec();
+        prefix.extend_from_slice(domain);
+        let stream = match hash_algorithm {
+            HashAlgorithm::Shake256 => Stream::Xof(Box::new(HashAlgorithm::xof(&prefix))),
+            _ => Stream::Counter {
+                hash_algorithm,
+                prefix,
+                counter: 0,
+                block: Vec::new(),
+                position: 0,
+            },
+        };
+        Self { stream }
+    }
+
+    /// The hash function expanding the seed
+    pub fn hash_algorithm(&self) -> HashAlgorithm {
+        match &self.stream {
+            Stream::Counter { hash_algorithm, .. } => *hash_algorithm,
+            Stream::Xof(_) => HashAlgorithm::Shake256,
+        }
+    }
+
+    fn next_byte(&mut self) -> u8 {
+        match &mut self.stream {
+            Stream::Counter {
+                hash_algorithm,
+                prefix,
+                counter,
+                block,
+                position,
+            } => {
+                if *position == block.len() {
+                    let mut input = prefix.clone();
+                    input.extend_from_slice(&counter.to_le_bytes());
+                    *block = hash_algorithm.digest(&input);
+                    *counter += 1;
+                    *position = 0;
+                }
+                *position += 1;
+                block[*position - 1]
+            }
+            Stream::Xof(reader) => {
+                let mut byte = [0];
+                reader.read(&mut byte);
+                byte[0]
+            }
+        }
+    }
+
+    /// The next 64 bits of the stream, as a little-endian integer
+    pub fn next_u64(&mut self) -> u64 {
+        (0..8).fold(0, |acc, i| acc | (self.next_byte() as u64) << (8 * i))
+    }
+
+    /// Draw `n` integers uniformly from [0, modulus), rejecting the samples of
+    /// the bit length of the modulus that exceed it
+    pub fn sample_uniform(&mut self, n: usize, modulus: &Modulus) -> Vec<u64> {
+        let q = modulus.value();
+        let mask = u64::MAX >> q.leading_zeros();
+        (0..n)
+            .map(|_| loop {
+                let x = self.next_u64() & mask;
+                if x < q {
+                    break x;
+                }
+            })
+            .collect()
+    }
+}
+
+impl Debug for PrngFromHash {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrngFromHash")
+            .field("hash_algorithm", &self.hash_algorithm())
+            .finish_non_exhaustive()
+    }
+}
+
+impl RngCore for PrngFromHash {
+    fn next_u32(&mut self) -> u32 {
+        (0..4).fold(0, |acc, i| acc | (self.next_byte() as u32) << (8 * i))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        PrngFromHash::next_u64(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for byte in dest {
+            *byte = self.next_byte();
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for PrngFromHash {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALGORITHMS: [HashAlgorithm; 3] = [
+        HashAlgorithm::Sha256,
+        HashAlgorithm::Sha512,
+        HashAlgorithm::Shake256,
+    ];
+
+    fn stream(hash_algorithm: HashAlgorithm, seed: u8, domain: &[u8]) -> Vec<u8> {
+        let mut out = vec![0; 200];
+        PrngFromHash::new(hash_algorithm, &[seed; SEED_LEN], domain).fill_bytes(&mut out);
+        out
+    }
+
+    #[test]
+    fn streams_are_deterministic_and_domain_separated() {
+        for hash_algorithm in ALGORITHMS {
+            let a = stream(hash_algorithm, 1, b"a");
+            assert_eq!(a, stream(hash_algorithm, 1, b"a"));
+            assert_ne!(a, stream(hash_algorithm, 1, b"b"));
+            assert_ne!(a, stream(hash_algorithm, 2, b"a"));
+        }
+        assert_ne!(
+            stream(HashAlgorithm::Sha256, 1, b"a"),
+            stream(HashAlgorithm::Sha512, 1, b"a")
+        );
+        assert_ne!(
+            stream(HashAlgorithm::Sha512, 1, b"a"),
+            stream(HashAlgorithm::Shake256, 1, b"a")
+        );
+    }
+
+    #[test]
+    fn streams_follow_their_construction() {
+        let mut input = [3; SEED_LEN].to_vec();
+        input.extend_from_slice(b"dom");
+        for hash_algorithm in [HashAlgorithm::Sha256, HashAlgorithm::Sha512] {
+            let mut expected = Vec::new();
+            for counter in 0u64..8 {
+                let mut block = input.clone();
+                block.extend_from_slice(&counter.to_le_bytes());
+                expected.extend(hash_algorithm.digest(&block));
+            }
+            assert_eq!(stream(hash_algorithm, 3, b"dom"), expected[..200]);
+        }
+        let mut expected = vec![0; 200];
+        HashAlgorithm::xof(&input).read(&mut expected);
+        assert_eq!(stream(HashAlgorithm::Shake256, 3, b"dom"), expected);
+        assert_eq!(
+            stream(HashAlgorithm::Shake256, 3, b"dom")[..64],
+            HashAlgorithm::Shake256.digest(&input)
+        );
+    }
+
+    #[test]
+    fn words_and_bytes_read_the_same_stream() {
+        for hash_algorithm in ALGORITHMS {
+            let bytes = stream(hash_algorithm, 4, b"");
+            let mut prng = PrngFromHash::new(hash_algorithm, &[4; SEED_LEN], b"");
+            assert_eq!(prng.hash_algorithm(), hash_algorithm);
+            let x = PrngFromHash::next_u64(&mut prng);
+            let y = prng.next_u32();
+            assert_eq!(x.to_le_bytes(), bytes[..8]);
+            assert_eq!(y.to_le_bytes(), bytes[8..12]);
+        }
+    }
+
+    #[test]
+    fn uniform_samples_are_reduced() {
+        for q in [2, 3, 17, 65537, (1 << 61) - 1] {
+            let modulus = Modulus::new(q);
+            for hash_algorithm in ALGORITHMS {
+                let mut prng = PrngFromHash::new(hash_algorithm, &[5; SEED_LEN], b"");
+                let samples = prng.sample_uniform(1000, &modulus);
+                assert!(samples.iter().all(|&x| x < q));
+                if q <= 17 {
+                    assert!((0..q).all(|v| samples.contains(&v)));
+                }
+            }
+        }
+    }
+}
@@ -7,10 +7,14 @@ use std::str::FromStr;
 #[cfg_attr(test, derive(Eq, PartialEq))]
 #[repr(u8)]
 pub enum ProxyReEncryptionMode {
+    /// Proxy re-encryption is not used
     #[default]
     NotSet = 0,
+    /// Secure against chosen plaintext attacks
     IndCpa,
+    /// Secure against honest re-encryption attacks with a fixed noise
     FixedNoiseHra,
+    /// Secure against honest re-encryption attacks by flooding the noise
     NoiseFloodingHra,
 }
 
@@ -0,0 +1,91 @@
+use crate::fhe_core::{Modulus, SEED_LEN};
+use rand::Rng;
+use rand_core::CryptoRngCore;
+
+/// Samples integers from a discrete Gaussian centered at zero using rejection
+/// sampling over the interval [-tail, tail]
+#[derive(Debug, Clone, Copy)]
+pub struct DiscreteGaussianGenerator {
+    std_dev: f64,
+    tail: i64,
+}
+
+impl DiscreteGaussianGenerator {
+    /// Number of standard deviations after which the distribution is cut
+    const TAIL_CUT: f64 = 6.0;
+
+    /// Create a new generator with standard deviation `std_dev`
+    pub fn new(std_dev: f64) -> Self {
+        Self {
+            std_dev,
+            tail: (std_dev * Self::TAIL_CUT).ceil() as i64,
+        }
+    }
+
+    /// Draw a single sample from `rng`
+    pub fn sample<R: CryptoRngCore + ?Sized>(&self, rng: &mut R) -> i64 {
+        if self.tail == 0 {
+            return 0;
+        }
+        let denominator = -2.0 * self.std_dev * self.std_dev;
+        loop {
+            let x = rng.gen_range(-self.tail..=self.tail);
+            let p = ((x * x) as f64 / denominator).exp();
+            if rng.gen::<f64>() < p {
+                return x;
+            }
+        }
+    }
+
+    /// Draw `n` samples from `rng` reduced modulo `modulus`
+    pub fn sample_vec_mod<R: CryptoRngCore + ?Sized>(
+        &self,
+        n: usize,
+        modulus: &Modulus,
+        rng: &mut R,
+    ) -> Vec<u64> {
+        (0..n)
+            .map(|_| modulus.reduce_i64(self.sample(rng)))
+            .collect()
+    }
+}
+
+/// Draw `n` integers uniformly at random from [0, modulus)
+pub fn sample_uniform<R: CryptoRngCore + ?Sized>(
+    n: usize,
+    modulus: &Modulus,
+    rng: &mut R,
+) -> Vec<u64> {
+    (0..n).map(|_| rng.gen_range(0..modulus.value())).collect()
+}
+
+/// Draw `n` integers uniformly at random from {-1, 0, 1}
+pub fn sample_ternary<R: CryptoRngCore + ?Sized>(n: usize, rng: &mut R) -> Vec<i64> {
+    (0..n).map(|_| rng.gen_range(-1..=1)).collect()
+}
+
+/// Draw `n` integers uniformly at random from [0, 2^bits), each as its
+/// little-endian 64-bit limbs
+pub fn sample_uniform_bits<R: CryptoRngCore + ?Sized>(
+    n: usize,
+    bits: u32,
+    rng: &mut R,
+) -> Vec<Vec<u64>> {
+    let num_limbs = bits.div_ceil(64) as usize;
+    (0..n)
+        .map(|_| {
+            let mut limbs: Vec<u64> = (0..num_limbs).map(|_| rng.gen()).collect();
+            if let Some(last) = limbs.last_mut() {
+                if !bits.is_multiple_of(64) {
+                    *last &= (1 << (bits % 64)) - 1;
+                }
+            }
+            limbs
+        })
+        .collect()
+}
+
+/// Draw a seed for a [`crate::fhe_core::UniformPolys`]
+pub fn sample_seed<R: CryptoRngCore + ?Sized>(rng: &mut R) -> [u8; SEED_LEN] {
+    rng.gen()
+}
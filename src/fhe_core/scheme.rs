@@ -0,0 +1,77 @@
+use crate::Error;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+/// The public key encryption schemes, numbered as OpenFHE does
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(test, derive(Eq, PartialEq))]
+#[repr(u8)]
+pub enum Scheme {
+    /// No scheme
+    #[default]
+    InvalidScheme = 0,
+    /// CKKS over RNS polynomials
+    CkksRns,
+    /// BFV over RNS polynomials
+    BfvRns,
+    /// BGV over RNS polynomials
+    BgvRns,
+}
+
+impl TryFrom<u8> for Scheme {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Scheme::InvalidScheme),
+            1 => Ok(Scheme::CkksRns),
+            2 => Ok(Scheme::BfvRns),
+            3 => Ok(Scheme::BgvRns),
+            _ => Err(Error::InvalidScheme(value as usize)),
+        }
+    }
+}
+
+try_from_int_impl!(
+    Scheme,
+    InvalidScheme,
+    i8,
+    u16,
+    i16,
+    u32,
+    i32,
+    u64,
+    i64,
+    usize,
+    isize
+);
+into_int_impl!(Scheme, i8, u16, i16, u32, i32, u64, i64, usize, isize);
+enum_serde_impl!(Scheme);
+
+impl Display for Scheme {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Scheme::InvalidScheme => write!(f, "InvalidScheme"),
+            Scheme::CkksRns => write!(f, "CkksRns"),
+            Scheme::BfvRns => write!(f, "BfvRns"),
+            Scheme::BgvRns => write!(f, "BgvRns"),
+        }
+    }
+}
+
+impl FromStr for Scheme {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "InvalidScheme" => Ok(Scheme::InvalidScheme),
+            "CkksRns" => Ok(Scheme::CkksRns),
+            "BfvRns" => Ok(Scheme::BfvRns),
+            "BgvRns" => Ok(Scheme::BgvRns),
+            _ => Err(Error::ParseError(format!(
+                "invalid string when parsing Scheme: '{}'",
+                s
+            ))),
+        }
+    }
+}
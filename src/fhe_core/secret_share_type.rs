@@ -0,0 +1,78 @@
+use crate::Error;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+/// How a secret key is split between parties
+#[derive(Debug, Default, Clone, Copy)]
+#[cfg_attr(test, derive(Eq, PartialEq))]
+#[repr(u8)]
+pub enum SecretShareType {
+    /// n-of-n sharing, the key is the sum of all the shares
+    #[default]
+    Additive = 0,
+    /// t-of-n Shamir sharing, any t shares recover the key
+    Shamir,
+}
+
+impl TryFrom<u8> for SecretShareType {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(SecretShareType::Additive),
+            1 => Ok(SecretShareType::Shamir),
+            _ => Err(Error::InvalidSecretShareType(value as usize)),
+        }
+    }
+}
+
+try_from_int_impl!(
+    SecretShareType,
+    InvalidSecretShareType,
+    i8,
+    u16,
+    i16,
+    u32,
+    i32,
+    u64,
+    i64,
+    usize,
+    isize
+);
+into_int_impl!(
+    SecretShareType,
+    i8,
+    u16,
+    i16,
+    u32,
+    i32,
+    u64,
+    i64,
+    usize,
+    isize
+);
+enum_serde_impl!(SecretShareType);
+
+impl Display for SecretShareType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SecretShareType::Additive => write!(f, "Additive"),
+            SecretShareType::Shamir => write!(f, "Shamir"),
+        }
+    }
+}
+
+impl FromStr for SecretShareType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Additive" => Ok(SecretShareType::Additive),
+            "Shamir" => Ok(SecretShareType::Shamir),
+            _ => Err(Error::ParseError(format!(
+                "invalid string when parsing SecretShareType: '{}'",
+                s
+            ))),
+        }
+    }
+}
@@ -0,0 +1,526 @@
+//! Vector modular arithmetic and NTT butterflies, with AVX2 and AVX-512
+//! kernels selected at runtime and a portable scalar fallback. The kernels
+//! reducing fully compute the same values whatever the level, so the choice
+//! never changes a result; the lazily reduced outputs of the IFMA butterflies
+//! may differ from the others by a multiple of the modulus.
+
+#[cfg(target_arch = "x86_64")]
+mod avx2;
+#[cfg(target_arch = "x86_64")]
+mod avx512;
+
+use crate::fhe_core::Modulus;
+use std::sync::OnceLock;
+
+/// The vector instruction sets of the kernels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimdLevel {
+    /// Portable code
+    Scalar,
+    /// AVX2, 4 lanes of 64 bits
+    Avx2,
+    /// AVX-512 F and DQ, 8 lanes of 64 bits
+    Avx512,
+    /// AVX-512 with the 52-bit multiplications of IFMA, used for moduli
+    /// below 2^50 and falling back to [`Self::Avx512`] for larger ones
+    Avx512Ifma,
+}
+
+impl SimdLevel {
+    /// The best level supported by the running CPU, detected once
+    pub fn detect() -> Self {
+        static LEVEL: OnceLock<SimdLevel> = OnceLock::new();
+        *LEVEL.get_or_init(Self::detect_uncached)
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    fn detect_uncached() -> Self {
+        if is_x86_feature_detected!("avx512f") && is_x86_feature_detected!("avx512dq") {
+            if is_x86_feature_detected!("avx512ifma") {
+                Self::Avx512Ifma
+            } else {
+                Self::Avx512
+            }
+        } else if is_x86_feature_detected!("avx2") {
+            Self::Avx2
+        } else {
+            Self::Scalar
+        }
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    fn detect_uncached() -> Self {
+        Self::Scalar
+    }
+
+    /// The number of 64-bit lanes of a vector register
+    pub fn lanes(&self) -> usize {
+        match self {
+            Self::Scalar => 1,
+            Self::Avx2 => 4,
+            Self::Avx512 | Self::Avx512Ifma => 8,
+        }
+    }
+}
+
+/// The largest modulus the IFMA kernels handle, so that the lazy values in
+/// [0, 4q) fit in 52 bits
+#[cfg(target_arch = "x86_64")]
+const IFMA_MODULUS_BOUND: u64 = 1 << 50;
+
+/// Barrett constants for a modulus q of `bits` bits: x mod q for x < q^2 is
+/// x - floor(floor(x / 2^(bits - 1)) * mu / 2^(bits + 1)) * q up to two
+/// subtractions of q, with mu = floor(2^(2 bits) / q)
+#[cfg(target_arch = "x86_64")]
+fn barrett_constants(q: &Modulus) -> (u32, u64) {
+    let bits = u64::BITS - q.value().leading_zeros();
+    (bits, ((1u128 << (2 * bits)) / q.value() as u128) as u64)
+}
+
+/// a <- a + b mod q
+pub fn add_mod(a: &mut [u64], b: &[u64], q: &Modulus) {
+    debug_assert_eq!(a.len(), b.len());
+    match SimdLevel::detect() {
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx512 | SimdLevel::Avx512Ifma => {
+            // SAFETY: the CPU supports AVX-512 F and DQ
+            unsafe { avx512::add_mod(a, b, q) }
+        }
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx2 => {
+            // SAFETY: the CPU supports AVX2
+            unsafe { avx2::add_mod(a, b, q) }
+        }
+        _ => scalar::add_mod(a, b, q),
+    }
+}
+
+/// a <- a - b mod q
+pub fn sub_mod(a: &mut [u64], b: &[u64], q: &Modulus) {
+    debug_assert_eq!(a.len(), b.len());
+    match SimdLevel::detect() {
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx512 | SimdLevel::Avx512Ifma => {
+            // SAFETY: the CPU supports AVX-512 F and DQ
+            unsafe { avx512::sub_mod(a, b, q) }
+        }
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx2 => {
+            // SAFETY: the CPU supports AVX2
+            unsafe { avx2::sub_mod(a, b, q) }
+        }
+        _ => scalar::sub_mod(a, b, q),
+    }
+}
+
+/// a <- a * b mod q
+pub fn mul_mod(a: &mut [u64], b: &[u64], q: &Modulus) {
+    debug_assert_eq!(a.len(), b.len());
+    match SimdLevel::detect() {
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx512Ifma if q.value() < IFMA_MODULUS_BOUND => {
+            // SAFETY: the CPU supports AVX-512 F, DQ and IFMA
+            unsafe { avx512::mul_mod_ifma(a, b, q) }
+        }
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx512 | SimdLevel::Avx512Ifma => {
+            // SAFETY: the CPU supports AVX-512 F and DQ
+            unsafe { avx512::mul_mod(a, b, q) }
+        }
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx2 => {
+            // SAFETY: the CPU supports AVX2
+            unsafe { avx2::mul_mod(a, b, q) }
+        }
+        _ => scalar::mul_mod(a, b, q),
+    }
+}
+
+/// a <- a * w mod q for a constant `w` with Shoup quotient `w_shoup`, `a` in
+/// [0, 2^64) if `lazy` and the output in [0, 2q), else both in [0, q)
+pub fn mul_shoup(a: &mut [u64], w: u64, w_shoup: u64, q: &Modulus, lazy: bool) {
+    let level = SimdLevel::detect();
+    match level {
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx512Ifma if q.value() < IFMA_MODULUS_BOUND && !lazy => {
+            // SAFETY: the CPU supports AVX-512 F, DQ and IFMA
+            unsafe { avx512::mul_shoup_ifma(a, w, w_shoup, q) }
+        }
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx512 | SimdLevel::Avx512Ifma => {
+            // SAFETY: the CPU supports AVX-512 F and DQ
+            unsafe { avx512::mul_shoup(a, w, w_shoup, q, lazy) }
+        }
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx2 => {
+            // SAFETY: the CPU supports AVX2
+            unsafe { avx2::mul_shoup(a, w, w_shoup, q, lazy) }
+        }
+        _ => scalar::mul_shoup(a, w, w_shoup, q, lazy),
+    }
+}
+
+/// a <- a mod q for `a` in [0, 4q)
+pub fn reduce_4q(a: &mut [u64], q: &Modulus) {
+    match SimdLevel::detect() {
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx512 | SimdLevel::Avx512Ifma => {
+            // SAFETY: the CPU supports AVX-512 F and DQ
+            unsafe { avx512::reduce_4q(a, q) }
+        }
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx2 => {
+            // SAFETY: the CPU supports AVX2
+            unsafe { avx2::reduce_4q(a, q) }
+        }
+        _ => scalar::reduce_4q(a, q),
+    }
+}
+
+/// Harvey's Cooley-Tukey butterflies (x, y) <- (x + w y, x - w y) on values in
+/// [0, 4q), at the level `level` obtained from [`SimdLevel::detect`]
+pub(crate) fn ct_butterflies(
+    level: SimdLevel,
+    x: &mut [u64],
+    y: &mut [u64],
+    w: u64,
+    w_shoup: u64,
+    q: &Modulus,
+) {
+    debug_assert_eq!(x.len(), y.len());
+    if x.len() < level.lanes() {
+        return scalar::ct_butterflies(x, y, w, w_shoup, q);
+    }
+    match level {
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx512Ifma if q.value() < IFMA_MODULUS_BOUND => {
+            // SAFETY: the level was detected on this CPU
+            unsafe { avx512::ct_butterflies_ifma(x, y, w, w_shoup, q) }
+        }
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx512 | SimdLevel::Avx512Ifma => {
+            // SAFETY: the level was detected on this CPU
+            unsafe { avx512::ct_butterflies(x, y, w, w_shoup, q) }
+        }
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx2 => {
+            // SAFETY: the level was detected on this CPU
+            unsafe { avx2::ct_butterflies(x, y, w, w_shoup, q) }
+        }
+        _ => scalar::ct_butterflies(x, y, w, w_shoup, q),
+    }
+}
+
+/// Harvey's Gentleman-Sande butterflies (x, y) <- (x + y, w (x - y)) on values
+/// in [0, 2q), at the level `level` obtained from [`SimdLevel::detect`]
+pub(crate) fn gs_butterflies(
+    level: SimdLevel,
+    x: &mut [u64],
+    y: &mut [u64],
+    w: u64,
+    w_shoup: u64,
+    q: &Modulus,
+) {
+    debug_assert_eq!(x.len(), y.len());
+    if x.len() < level.lanes() {
+        return scalar::gs_butterflies(x, y, w, w_shoup, q);
+    }
+    match level {
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx512Ifma if q.value() < IFMA_MODULUS_BOUND => {
+            // SAFETY: the level was detected on this CPU
+            unsafe { avx512::gs_butterflies_ifma(x, y, w, w_shoup, q) }
+        }
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx512 | SimdLevel::Avx512Ifma => {
+            // SAFETY: the level was detected on this CPU
+            unsafe { avx512::gs_butterflies(x, y, w, w_shoup, q) }
+        }
+        #[cfg(target_arch = "x86_64")]
+        SimdLevel::Avx2 => {
+            // SAFETY: the level was detected on this CPU
+            unsafe { avx2::gs_butterflies(x, y, w, w_shoup, q) }
+        }
+        _ => scalar::gs_butterflies(x, y, w, w_shoup, q),
+    }
+}
+
+/// The portable kernels, which also process the tails shorter than a vector
+/// and are the kernels of the words without vector ones
+pub(crate) mod scalar {
+    use crate::fhe_core::{Modulus, Word};
+
+    pub(crate) fn add_mod<W: Word>(a: &mut [W], b: &[W], q: &Modulus<W>) {
+        for (x, &y) in a.iter_mut().zip(b) {
+            *x = q.add(*x, y);
+        }
+    }
+
+    pub(crate) fn sub_mod<W: Word>(a: &mut [W], b: &[W], q: &Modulus<W>) {
+        for (x, &y) in a.iter_mut().zip(b) {
+            *x = q.sub(*x, y);
+        }
+    }
+
+    pub(crate) fn mul_mod<W: Word>(a: &mut [W], b: &[W], q: &Modulus<W>) {
+        for (x, &y) in a.iter_mut().zip(b) {
+            *x = q.mul(*x, y);
+        }
+    }
+
+    pub(crate) fn mul_shoup<W: Word>(a: &mut [W], w: W, w_shoup: W, q: &Modulus<W>, lazy: bool) {
+        for x in a.iter_mut() {
+            *x = if lazy {
+                q.mul_shoup_lazy(*x, w, w_shoup)
+            } else {
+                q.mul_shoup(*x, w, w_shoup)
+            };
+        }
+    }
+
+    pub(crate) fn reduce_4q<W: Word>(a: &mut [W], q: &Modulus<W>) {
+        for x in a.iter_mut() {
+            *x = q.reduce_4q(*x);
+        }
+    }
+
+    pub(crate) fn ct_butterflies<W: Word>(
+        x: &mut [W],
+        y: &mut [W],
+        w: W,
+        w_shoup: W,
+        q: &Modulus<W>,
+    ) {
+        let two_q = q.value() << 1;
+        for (x, y) in x.iter_mut().zip(y.iter_mut()) {
+            // u in [0, 2q) and v in [0, 2q) give outputs in [0, 4q)
+            let u = if *x >= two_q { *x - two_q } else { *x };
+            let v = q.mul_shoup_lazy(*y, w, w_shoup);
+            *x = u + v;
+            *y = u + two_q - v;
+        }
+    }
+
+    pub(crate) fn gs_butterflies<W: Word>(
+        x: &mut [W],
+        y: &mut [W],
+        w: W,
+        w_shoup: W,
+        q: &Modulus<W>,
+    ) {
+        for (x, y) in x.iter_mut().zip(y.iter_mut()) {
+            let (u, v) = (*x, *y);
+            *x = q.add_lazy(u, v);
+            *y = q.mul_shoup_lazy(q.sub_lazy(u, v), w, w_shoup);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fhe_core::{last_prime, NttTables};
+    use rand::Rng;
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+
+    const LEVELS: [SimdLevel; 4] = [
+        SimdLevel::Scalar,
+        SimdLevel::Avx2,
+        SimdLevel::Avx512,
+        SimdLevel::Avx512Ifma,
+    ];
+
+    /// The levels the running CPU supports, each implying those before it
+    fn levels() -> Vec<SimdLevel> {
+        let best = SimdLevel::detect();
+        let count = LEVELS.iter().position(|&l| l == best).unwrap_or(0) + 1;
+        LEVELS[..count].to_vec()
+    }
+
+    /// Moduli on both sides of the IFMA bound and near the largest modulus
+    fn moduli() -> Vec<Modulus> {
+        [
+            3,
+            65537,
+            last_prime(40, 1u64 << 13).unwrap(),
+            last_prime(50, 1u64 << 13).unwrap(),
+            (1 << 50) + 1,
+            last_prime(60, 1u64 << 13).unwrap(),
+            (1 << 62) - 1,
+        ]
+        .into_iter()
+        .map(Modulus::new)
+        .collect()
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    enum Op {
+        Add,
+        Sub,
+        Mul,
+        MulShoup,
+        MulShoupLazy,
+        Reduce4q,
+    }
+
+    const OPS: [Op; 6] = [
+        Op::Add,
+        Op::Sub,
+        Op::Mul,
+        Op::MulShoup,
+        Op::MulShoupLazy,
+        Op::Reduce4q,
+    ];
+
+    /// Apply `op` to `a` with the kernel of `level`, `b` being the second
+    /// operand of the element-wise operations and `w` the constant of the
+    /// Shoup multiplications. The CPU must support `level`.
+    fn apply(level: SimdLevel, op: Op, a: &mut [u64], b: &[u64], w: u64, q: &Modulus) {
+        let w_shoup = q.shoup(w);
+        match level {
+            // SAFETY: the level is one of [`levels`]
+            #[cfg(target_arch = "x86_64")]
+            SimdLevel::Avx2 => unsafe {
+                match op {
+                    Op::Add => avx2::add_mod(a, b, q),
+                    Op::Sub => avx2::sub_mod(a, b, q),
+                    Op::Mul => avx2::mul_mod(a, b, q),
+                    Op::MulShoup => avx2::mul_shoup(a, w, w_shoup, q, false),
+                    Op::MulShoupLazy => avx2::mul_shoup(a, w, w_shoup, q, true),
+                    Op::Reduce4q => avx2::reduce_4q(a, q),
+                }
+            },
+            // SAFETY: the level is one of [`levels`]
+            #[cfg(target_arch = "x86_64")]
+            SimdLevel::Avx512Ifma if q.value() < IFMA_MODULUS_BOUND => unsafe {
+                match op {
+                    Op::Mul => avx512::mul_mod_ifma(a, b, q),
+                    Op::MulShoup => avx512::mul_shoup_ifma(a, w, w_shoup, q),
+                    _ => apply(SimdLevel::Avx512, op, a, b, w, q),
+                }
+            },
+            // SAFETY: the level is one of [`levels`]
+            #[cfg(target_arch = "x86_64")]
+            SimdLevel::Avx512 | SimdLevel::Avx512Ifma => unsafe {
+                match op {
+                    Op::Add => avx512::add_mod(a, b, q),
+                    Op::Sub => avx512::sub_mod(a, b, q),
+                    Op::Mul => avx512::mul_mod(a, b, q),
+                    Op::MulShoup => avx512::mul_shoup(a, w, w_shoup, q, false),
+                    Op::MulShoupLazy => avx512::mul_shoup(a, w, w_shoup, q, true),
+                    Op::Reduce4q => avx512::reduce_4q(a, q),
+                }
+            },
+            _ => match op {
+                Op::Add => scalar::add_mod(a, b, q),
+                Op::Sub => scalar::sub_mod(a, b, q),
+                Op::Mul => scalar::mul_mod(a, b, q),
+                Op::MulShoup => scalar::mul_shoup(a, w, w_shoup, q, false),
+                Op::MulShoupLazy => scalar::mul_shoup(a, w, w_shoup, q, true),
+                Op::Reduce4q => scalar::reduce_4q(a, q),
+            },
+        }
+    }
+
+    /// Apply `op` with the dispatching kernel
+    fn apply_detected(op: Op, a: &mut [u64], b: &[u64], w: u64, q: &Modulus) {
+        match op {
+            Op::Add => add_mod(a, b, q),
+            Op::Sub => sub_mod(a, b, q),
+            Op::Mul => mul_mod(a, b, q),
+            Op::MulShoup => mul_shoup(a, w, q.shoup(w), q, false),
+            Op::MulShoupLazy => mul_shoup(a, w, q.shoup(w), q, true),
+            Op::Reduce4q => reduce_4q(a, q),
+        }
+    }
+
+    /// Operands of `op` of length `len` in its input range
+    fn operand(rng: &mut ChaCha20Rng, op: Op, len: usize, q: &Modulus) -> Vec<u64> {
+        let bound = match op {
+            Op::MulShoupLazy => u64::MAX,
+            Op::Reduce4q => 4 * q.value() - 1,
+            _ => q.value() - 1,
+        };
+        (0..len).map(|_| rng.gen_range(0..=bound)).collect()
+    }
+
+    #[test]
+    fn vector_kernels_match_the_scalar_kernels() {
+        let mut rng = ChaCha20Rng::seed_from_u64(48);
+        for q in moduli() {
+            for op in OPS {
+                // the lengths cover empty slices, tails and several vectors
+                for len in (0..=19).chain([64, 1001]) {
+                    let a = operand(&mut rng, op, len, &q);
+                    let b = operand(&mut rng, Op::Add, len, &q);
+                    let w = rng.gen_range(0..q.value());
+                    let mut expected = a.clone();
+                    apply(SimdLevel::Scalar, op, &mut expected, &b, w, &q);
+                    for level in levels() {
+                        let mut out = a.clone();
+                        apply(level, op, &mut out, &b, w, &q);
+                        assert_eq!(out, expected, "{level:?} {op:?} q = {}", q.value());
+                    }
+                    let mut out = a.clone();
+                    apply_detected(op, &mut out, &b, w, &q);
+                    assert_eq!(out, expected, "{op:?} q = {}", q.value());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn butterflies_match_the_scalar_butterflies() {
+        let mut rng = ChaCha20Rng::seed_from_u64(49);
+        for q in moduli() {
+            let v = q.value();
+            for len in (1..=19).chain([64, 1001]) {
+                let w = rng.gen_range(0..v);
+                let w_shoup = q.shoup(w);
+                let x: Vec<u64> = (0..len).map(|_| rng.gen_range(0..4 * v)).collect();
+                let y: Vec<u64> = (0..len).map(|_| rng.gen_range(0..4 * v)).collect();
+                let (mut ct_x, mut ct_y) = (x.clone(), y.clone());
+                scalar::ct_butterflies(&mut ct_x, &mut ct_y, w, w_shoup, &q);
+                let x2: Vec<u64> = x.iter().map(|&x| x % (2 * v)).collect();
+                let y2: Vec<u64> = y.iter().map(|&y| y % (2 * v)).collect();
+                let (mut gs_x, mut gs_y) = (x2.clone(), y2.clone());
+                scalar::gs_butterflies(&mut gs_x, &mut gs_y, w, w_shoup, &q);
+                let reduced = |a: &[u64]| a.iter().map(|&a| a % v).collect::<Vec<_>>();
+                for level in levels() {
+                    let (mut x, mut y) = (x.clone(), y.clone());
+                    ct_butterflies(level, &mut x, &mut y, w, w_shoup, &q);
+                    assert!(x.iter().chain(&y).all(|&a| a < 4 * v));
+                    assert_eq!(reduced(&x), reduced(&ct_x), "{level:?} q = {v}");
+                    assert_eq!(reduced(&y), reduced(&ct_y), "{level:?} q = {v}");
+
+                    let (mut x, mut y) = (x2.clone(), y2.clone());
+                    gs_butterflies(level, &mut x, &mut y, w, w_shoup, &q);
+                    assert!(x.iter().chain(&y).all(|&a| a < 2 * v));
+                    assert_eq!(reduced(&x), reduced(&gs_x), "{level:?} q = {v}");
+                    assert_eq!(reduced(&y), reduced(&gs_y), "{level:?} q = {v}");
+                }
+            }
+        }
+    }
+
+    /// The tables of `u32` run the scalar butterflies and those of `u64` the
+    /// vector ones, so the transforms of a 28-bit prime must agree
+    #[test]
+    fn vector_transforms_match_the_scalar_transforms() {
+        let mut rng = ChaCha20Rng::seed_from_u64(50);
+        for ring_dim in [1, 2, 8, 64, 1024] {
+            let q = last_prime(28, 2 * ring_dim as u32).unwrap();
+            let scalar = NttTables::new(ring_dim, Modulus::new(q)).unwrap();
+            let vector = NttTables::new(ring_dim, Modulus::new(q as u64)).unwrap();
+            let a: Vec<u32> = (0..ring_dim).map(|_| rng.gen_range(0..q)).collect();
+            let mut b: Vec<u64> = a.iter().map(|&a| a as u64).collect();
+            let mut a_hat = a.clone();
+            scalar.forward(&mut a_hat);
+            vector.forward(&mut b);
+            assert!(a_hat.iter().zip(&b).all(|(&x, &y)| x as u64 == y));
+            vector.inverse(&mut b);
+            assert!(a.iter().zip(&b).all(|(&x, &y)| x as u64 == y));
+        }
+    }
+}
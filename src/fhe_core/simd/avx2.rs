@@ -0,0 +1,206 @@
+//! AVX2 kernels on 4 lanes. AVX2 has neither 64-bit multiplications nor
+//! unsigned 64-bit comparisons, so both are built from 32-bit multiplications
+//! and signed comparisons.
+
+use crate::fhe_core::simd::{barrett_constants, scalar};
+use crate::fhe_core::Modulus;
+use std::arch::x86_64::*;
+
+const LANES: usize = 4;
+
+#[target_feature(enable = "avx2")]
+#[inline]
+fn splat(x: u64) -> __m256i {
+    _mm256_set1_epi64x(x as i64)
+}
+
+#[target_feature(enable = "avx2")]
+#[inline]
+fn shift_count(bits: u32) -> __m128i {
+    _mm_cvtsi64_si128(bits as i64)
+}
+
+/// The unsigned minimum of every lane
+#[target_feature(enable = "avx2")]
+#[inline]
+fn min_u64(a: __m256i, b: __m256i) -> __m256i {
+    let sign = splat(1 << 63);
+    let a_gt_b = _mm256_cmpgt_epi64(_mm256_xor_si256(a, sign), _mm256_xor_si256(b, sign));
+    _mm256_blendv_epi8(a, b, a_gt_b)
+}
+
+/// a mod m for a in [0, 2m): a - m wraps above a when a < m
+#[target_feature(enable = "avx2")]
+#[inline]
+fn reduce(a: __m256i, m: __m256i) -> __m256i {
+    min_u64(a, _mm256_sub_epi64(a, m))
+}
+
+/// The high and low words of the 128-bit products
+#[target_feature(enable = "avx2")]
+#[inline]
+fn mul_wide(a: __m256i, b: __m256i) -> (__m256i, __m256i) {
+    let a_hi = _mm256_srli_epi64::<32>(a);
+    let b_hi = _mm256_srli_epi64::<32>(b);
+    let p00 = _mm256_mul_epu32(a, b);
+    let p01 = _mm256_mul_epu32(a, b_hi);
+    let p10 = _mm256_mul_epu32(a_hi, b);
+    let p11 = _mm256_mul_epu32(a_hi, b_hi);
+    let mask = splat(u32::MAX as u64);
+    let mid = _mm256_add_epi64(
+        _mm256_add_epi64(_mm256_srli_epi64::<32>(p00), _mm256_and_si256(p01, mask)),
+        _mm256_and_si256(p10, mask),
+    );
+    let hi = _mm256_add_epi64(
+        _mm256_add_epi64(p11, _mm256_srli_epi64::<32>(p01)),
+        _mm256_add_epi64(_mm256_srli_epi64::<32>(p10), _mm256_srli_epi64::<32>(mid)),
+    );
+    let lo = _mm256_or_si256(_mm256_slli_epi64::<32>(mid), _mm256_and_si256(p00, mask));
+    (hi, lo)
+}
+
+/// The products modulo 2^64
+#[target_feature(enable = "avx2")]
+#[inline]
+fn mul_lo(a: __m256i, b: __m256i) -> __m256i {
+    let cross = _mm256_add_epi64(
+        _mm256_mul_epu32(a, _mm256_srli_epi64::<32>(b)),
+        _mm256_mul_epu32(_mm256_srli_epi64::<32>(a), b),
+    );
+    _mm256_add_epi64(_mm256_mul_epu32(a, b), _mm256_slli_epi64::<32>(cross))
+}
+
+/// a * w mod q in [0, 2q), see [`Modulus::mul_shoup_lazy`]
+#[target_feature(enable = "avx2")]
+#[inline]
+fn mul_shoup_lazy(a: __m256i, w: __m256i, w_shoup: __m256i, q: __m256i) -> __m256i {
+    let (quotient, _) = mul_wide(a, w_shoup);
+    _mm256_sub_epi64(mul_lo(a, w), mul_lo(quotient, q))
+}
+
+#[target_feature(enable = "avx2")]
+#[inline]
+fn load(x: &[u64]) -> __m256i {
+    debug_assert_eq!(x.len(), LANES);
+    // SAFETY: x holds 4 lanes
+    unsafe { _mm256_loadu_si256(x.as_ptr().cast()) }
+}
+
+#[target_feature(enable = "avx2")]
+#[inline]
+fn store(x: &mut [u64], v: __m256i) {
+    debug_assert_eq!(x.len(), LANES);
+    // SAFETY: x holds 4 lanes
+    unsafe { _mm256_storeu_si256(x.as_mut_ptr().cast(), v) }
+}
+
+#[target_feature(enable = "avx2")]
+pub(super) fn add_mod(a: &mut [u64], b: &[u64], q: &Modulus) {
+    let vq = splat(q.value());
+    let mut a_chunks = a.chunks_exact_mut(LANES);
+    let mut b_chunks = b.chunks_exact(LANES);
+    for (x, y) in a_chunks.by_ref().zip(b_chunks.by_ref()) {
+        store(x, reduce(_mm256_add_epi64(load(x), load(y)), vq));
+    }
+    scalar::add_mod(a_chunks.into_remainder(), b_chunks.remainder(), q);
+}
+
+#[target_feature(enable = "avx2")]
+pub(super) fn sub_mod(a: &mut [u64], b: &[u64], q: &Modulus) {
+    let vq = splat(q.value());
+    let mut a_chunks = a.chunks_exact_mut(LANES);
+    let mut b_chunks = b.chunks_exact(LANES);
+    for (x, y) in a_chunks.by_ref().zip(b_chunks.by_ref()) {
+        let d = _mm256_sub_epi64(load(x), load(y));
+        store(x, min_u64(d, _mm256_add_epi64(d, vq)));
+    }
+    scalar::sub_mod(a_chunks.into_remainder(), b_chunks.remainder(), q);
+}
+
+#[target_feature(enable = "avx2")]
+pub(super) fn mul_mod(a: &mut [u64], b: &[u64], q: &Modulus) {
+    let (bits, mu) = barrett_constants(q);
+    let (vq, vmu) = (splat(q.value()), splat(mu));
+    let (shift_lo, shift_hi) = (shift_count(bits - 1), shift_count(65 - bits));
+    let (shift2_lo, shift2_hi) = (shift_count(bits + 1), shift_count(63 - bits));
+    let mut a_chunks = a.chunks_exact_mut(LANES);
+    let mut b_chunks = b.chunks_exact(LANES);
+    for (x, y) in a_chunks.by_ref().zip(b_chunks.by_ref()) {
+        let (hi, lo) = mul_wide(load(x), load(y));
+        let c1 = _mm256_or_si256(
+            _mm256_srl_epi64(lo, shift_lo),
+            _mm256_sll_epi64(hi, shift_hi),
+        );
+        let (hi2, lo2) = mul_wide(c1, vmu);
+        let c3 = _mm256_or_si256(
+            _mm256_srl_epi64(lo2, shift2_lo),
+            _mm256_sll_epi64(hi2, shift2_hi),
+        );
+        let r = _mm256_sub_epi64(lo, mul_lo(c3, vq));
+        store(x, reduce(reduce(r, vq), vq));
+    }
+    scalar::mul_mod(a_chunks.into_remainder(), b_chunks.remainder(), q);
+}
+
+#[target_feature(enable = "avx2")]
+pub(super) fn mul_shoup(a: &mut [u64], w: u64, w_shoup: u64, q: &Modulus, lazy: bool) {
+    let (vw, vw_shoup, vq) = (splat(w), splat(w_shoup), splat(q.value()));
+    let mut chunks = a.chunks_exact_mut(LANES);
+    for x in chunks.by_ref() {
+        let r = mul_shoup_lazy(load(x), vw, vw_shoup, vq);
+        store(x, if lazy { r } else { reduce(r, vq) });
+    }
+    scalar::mul_shoup(chunks.into_remainder(), w, w_shoup, q, lazy);
+}
+
+#[target_feature(enable = "avx2")]
+pub(super) fn reduce_4q(a: &mut [u64], q: &Modulus) {
+    let (vq, v2q) = (splat(q.value()), splat(2 * q.value()));
+    let mut chunks = a.chunks_exact_mut(LANES);
+    for x in chunks.by_ref() {
+        store(x, reduce(reduce(load(x), v2q), vq));
+    }
+    scalar::reduce_4q(chunks.into_remainder(), q);
+}
+
+#[target_feature(enable = "avx2")]
+pub(super) fn ct_butterflies(x: &mut [u64], y: &mut [u64], w: u64, w_shoup: u64, q: &Modulus) {
+    let (vw, vw_shoup) = (splat(w), splat(w_shoup));
+    let (vq, v2q) = (splat(q.value()), splat(2 * q.value()));
+    let mut x_chunks = x.chunks_exact_mut(LANES);
+    let mut y_chunks = y.chunks_exact_mut(LANES);
+    for (xs, ys) in x_chunks.by_ref().zip(y_chunks.by_ref()) {
+        let u = reduce(load(xs), v2q);
+        let v = mul_shoup_lazy(load(ys), vw, vw_shoup, vq);
+        store(xs, _mm256_add_epi64(u, v));
+        store(ys, _mm256_sub_epi64(_mm256_add_epi64(u, v2q), v));
+    }
+    scalar::ct_butterflies(
+        x_chunks.into_remainder(),
+        y_chunks.into_remainder(),
+        w,
+        w_shoup,
+        q,
+    );
+}
+
+#[target_feature(enable = "avx2")]
+pub(super) fn gs_butterflies(x: &mut [u64], y: &mut [u64], w: u64, w_shoup: u64, q: &Modulus) {
+    let (vw, vw_shoup) = (splat(w), splat(w_shoup));
+    let (vq, v2q) = (splat(q.value()), splat(2 * q.value()));
+    let mut x_chunks = x.chunks_exact_mut(LANES);
+    let mut y_chunks = y.chunks_exact_mut(LANES);
+    for (xs, ys) in x_chunks.by_ref().zip(y_chunks.by_ref()) {
+        let (u, v) = (load(xs), load(ys));
+        store(xs, reduce(_mm256_add_epi64(u, v), v2q));
+        let t = _mm256_sub_epi64(_mm256_add_epi64(u, v2q), v);
+        store(ys, mul_shoup_lazy(t, vw, vw_shoup, vq));
+    }
+    scalar::gs_butterflies(
+        x_chunks.into_remainder(),
+        y_chunks.into_remainder(),
+        w,
+        w_shoup,
+        q,
+    );
+}
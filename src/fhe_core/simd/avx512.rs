@@ -0,0 +1,296 @@
+//! AVX-512 kernels on 8 lanes. AVX-512 F and DQ provide the unsigned minimum
+//! and the low 64-bit products, the high words being built from 32-bit
+//! multiplications. With IFMA, moduli below 2^50 use the 52-bit multiplications
+//! instead, products being split at 52 bits rather than 64.
+
+use crate::fhe_core::simd::{barrett_constants, scalar};
+use crate::fhe_core::Modulus;
+use std::arch::x86_64::*;
+
+const LANES: usize = 8;
+
+const MASK_52: u64 = (1 << 52) - 1;
+
+#[target_feature(enable = "avx512f")]
+#[inline]
+fn splat(x: u64) -> __m512i {
+    _mm512_set1_epi64(x as i64)
+}
+
+#[target_feature(enable = "avx512f")]
+#[inline]
+fn shift_count(bits: u32) -> __m128i {
+    _mm_cvtsi64_si128(bits as i64)
+}
+
+/// a mod m for a in [0, 2m): a - m wraps above a when a < m
+#[target_feature(enable = "avx512f")]
+#[inline]
+fn reduce(a: __m512i, m: __m512i) -> __m512i {
+    _mm512_min_epu64(a, _mm512_sub_epi64(a, m))
+}
+
+/// The high and low words of the 128-bit products
+#[target_feature(enable = "avx512f,avx512dq")]
+#[inline]
+fn mul_wide(a: __m512i, b: __m512i) -> (__m512i, __m512i) {
+    let a_hi = _mm512_srli_epi64::<32>(a);
+    let b_hi = _mm512_srli_epi64::<32>(b);
+    let p00 = _mm512_mul_epu32(a, b);
+    let p01 = _mm512_mul_epu32(a, b_hi);
+    let p10 = _mm512_mul_epu32(a_hi, b);
+    let p11 = _mm512_mul_epu32(a_hi, b_hi);
+    let mask = splat(u32::MAX as u64);
+    let mid = _mm512_add_epi64(
+        _mm512_add_epi64(_mm512_srli_epi64::<32>(p00), _mm512_and_si512(p01, mask)),
+        _mm512_and_si512(p10, mask),
+    );
+    let hi = _mm512_add_epi64(
+        _mm512_add_epi64(p11, _mm512_srli_epi64::<32>(p01)),
+        _mm512_add_epi64(_mm512_srli_epi64::<32>(p10), _mm512_srli_epi64::<32>(mid)),
+    );
+    (hi, _mm512_mullo_epi64(a, b))
+}
+
+/// a * w mod q in [0, 2q), see [`Modulus::mul_shoup_lazy`]
+#[target_feature(enable = "avx512f,avx512dq")]
+#[inline]
+fn mul_shoup_lazy(a: __m512i, w: __m512i, w_shoup: __m512i, q: __m512i) -> __m512i {
+    let (quotient, _) = mul_wide(a, w_shoup);
+    _mm512_sub_epi64(_mm512_mullo_epi64(a, w), _mm512_mullo_epi64(quotient, q))
+}
+
+/// a * w mod q in [0, 2q) for a < 2^52 and q < 2^50, the quotient being
+/// estimated from the top 52 bits of the Shoup quotient
+#[target_feature(enable = "avx512f,avx512ifma")]
+#[inline]
+fn mul_shoup_lazy_ifma(a: __m512i, w: __m512i, w_shoup: __m512i, q: __m512i) -> __m512i {
+    let zero = _mm512_setzero_si512();
+    let quotient = _mm512_madd52hi_epu64(zero, a, _mm512_srli_epi64::<12>(w_shoup));
+    let r = _mm512_sub_epi64(
+        _mm512_madd52lo_epu64(zero, a, w),
+        _mm512_madd52lo_epu64(zero, quotient, q),
+    );
+    _mm512_and_si512(r, splat(MASK_52))
+}
+
+#[target_feature(enable = "avx512f")]
+#[inline]
+fn load(x: &[u64]) -> __m512i {
+    debug_assert_eq!(x.len(), LANES);
+    // SAFETY: x holds 8 lanes
+    unsafe { _mm512_loadu_si512(x.as_ptr().cast()) }
+}
+
+#[target_feature(enable = "avx512f")]
+#[inline]
+fn store(x: &mut [u64], v: __m512i) {
+    debug_assert_eq!(x.len(), LANES);
+    // SAFETY: x holds 8 lanes
+    unsafe { _mm512_storeu_si512(x.as_mut_ptr().cast(), v) }
+}
+
+#[target_feature(enable = "avx512f")]
+pub(super) fn add_mod(a: &mut [u64], b: &[u64], q: &Modulus) {
+    let vq = splat(q.value());
+    let mut a_chunks = a.chunks_exact_mut(LANES);
+    let mut b_chunks = b.chunks_exact(LANES);
+    for (x, y) in a_chunks.by_ref().zip(b_chunks.by_ref()) {
+        store(x, reduce(_mm512_add_epi64(load(x), load(y)), vq));
+    }
+    scalar::add_mod(a_chunks.into_remainder(), b_chunks.remainder(), q);
+}
+
+#[target_feature(enable = "avx512f")]
+pub(super) fn sub_mod(a: &mut [u64], b: &[u64], q: &Modulus) {
+    let vq = splat(q.value());
+    let mut a_chunks = a.chunks_exact_mut(LANES);
+    let mut b_chunks = b.chunks_exact(LANES);
+    for (x, y) in a_chunks.by_ref().zip(b_chunks.by_ref()) {
+        let d = _mm512_sub_epi64(load(x), load(y));
+        store(x, _mm512_min_epu64(d, _mm512_add_epi64(d, vq)));
+    }
+    scalar::sub_mod(a_chunks.into_remainder(), b_chunks.remainder(), q);
+}
+
+#[target_feature(enable = "avx512f,avx512dq")]
+pub(super) fn mul_mod(a: &mut [u64], b: &[u64], q: &Modulus) {
+    let (bits, mu) = barrett_constants(q);
+    let (vq, vmu) = (splat(q.value()), splat(mu));
+    let (shift_lo, shift_hi) = (shift_count(bits - 1), shift_count(65 - bits));
+    let (shift2_lo, shift2_hi) = (shift_count(bits + 1), shift_count(63 - bits));
+    let mut a_chunks = a.chunks_exact_mut(LANES);
+    let mut b_chunks = b.chunks_exact(LANES);
+    for (x, y) in a_chunks.by_ref().zip(b_chunks.by_ref()) {
+        let (hi, lo) = mul_wide(load(x), load(y));
+        let c1 = _mm512_or_si512(
+            _mm512_srl_epi64(lo, shift_lo),
+            _mm512_sll_epi64(hi, shift_hi),
+        );
+        let (hi2, lo2) = mul_wide(c1, vmu);
+        let c3 = _mm512_or_si512(
+            _mm512_srl_epi64(lo2, shift2_lo),
+            _mm512_sll_epi64(hi2, shift2_hi),
+        );
+        let r = _mm512_sub_epi64(lo, _mm512_mullo_epi64(c3, vq));
+        store(x, reduce(reduce(r, vq), vq));
+    }
+    scalar::mul_mod(a_chunks.into_remainder(), b_chunks.remainder(), q);
+}
+
+#[target_feature(enable = "avx512f,avx512dq,avx512ifma")]
+pub(super) fn mul_mod_ifma(a: &mut [u64], b: &[u64], q: &Modulus) {
+    let (bits, mu) = barrett_constants(q);
+    let (vq, vmu, mask) = (splat(q.value()), splat(mu), splat(MASK_52));
+    let (shift_lo, shift_hi) = (shift_count(bits - 1), shift_count(53 - bits));
+    let (shift2_lo, shift2_hi) = (shift_count(bits + 1), shift_count(51 - bits));
+    let zero = _mm512_setzero_si512();
+    let mut a_chunks = a.chunks_exact_mut(LANES);
+    let mut b_chunks = b.chunks_exact(LANES);
+    for (x, y) in a_chunks.by_ref().zip(b_chunks.by_ref()) {
+        let (u, v) = (load(x), load(y));
+        let (hi, lo) = (
+            _mm512_madd52hi_epu64(zero, u, v),
+            _mm512_madd52lo_epu64(zero, u, v),
+        );
+        let c1 = _mm512_and_si512(
+            _mm512_or_si512(
+                _mm512_srl_epi64(lo, shift_lo),
+                _mm512_sll_epi64(hi, shift_hi),
+            ),
+            mask,
+        );
+        let (hi2, lo2) = (
+            _mm512_madd52hi_epu64(zero, c1, vmu),
+            _mm512_madd52lo_epu64(zero, c1, vmu),
+        );
+        let c3 = _mm512_and_si512(
+            _mm512_or_si512(
+                _mm512_srl_epi64(lo2, shift2_lo),
+                _mm512_sll_epi64(hi2, shift2_hi),
+            ),
+            mask,
+        );
+        let r = _mm512_sub_epi64(lo, _mm512_madd52lo_epu64(zero, c3, vq));
+        store(x, reduce(reduce(_mm512_and_si512(r, mask), vq), vq));
+    }
+    scalar::mul_mod(a_chunks.into_remainder(), b_chunks.remainder(), q);
+}
+
+#[target_feature(enable = "avx512f,avx512dq")]
+pub(super) fn mul_shoup(a: &mut [u64], w: u64, w_shoup: u64, q: &Modulus, lazy: bool) {
+    let (vw, vw_shoup, vq) = (splat(w), splat(w_shoup), splat(q.value()));
+    let mut chunks = a.chunks_exact_mut(LANES);
+    for x in chunks.by_ref() {
+        let r = mul_shoup_lazy(load(x), vw, vw_shoup, vq);
+        store(x, if lazy { r } else { reduce(r, vq) });
+    }
+    scalar::mul_shoup(chunks.into_remainder(), w, w_shoup, q, lazy);
+}
+
+#[target_feature(enable = "avx512f,avx512dq,avx512ifma")]
+pub(super) fn mul_shoup_ifma(a: &mut [u64], w: u64, w_shoup: u64, q: &Modulus) {
+    let (vw, vw_shoup, vq) = (splat(w), splat(w_shoup), splat(q.value()));
+    let mut chunks = a.chunks_exact_mut(LANES);
+    for x in chunks.by_ref() {
+        store(
+            x,
+            reduce(mul_shoup_lazy_ifma(load(x), vw, vw_shoup, vq), vq),
+        );
+    }
+    scalar::mul_shoup(chunks.into_remainder(), w, w_shoup, q, false);
+}
+
+#[target_feature(enable = "avx512f")]
+pub(super) fn reduce_4q(a: &mut [u64], q: &Modulus) {
+    let (vq, v2q) = (splat(q.value()), splat(2 * q.value()));
+    let mut chunks = a.chunks_exact_mut(LANES);
+    for x in chunks.by_ref() {
+        store(x, reduce(reduce(load(x), v2q), vq));
+    }
+    scalar::reduce_4q(chunks.into_remainder(), q);
+}
+
+#[target_feature(enable = "avx512f,avx512dq")]
+pub(super) fn ct_butterflies(x: &mut [u64], y: &mut [u64], w: u64, w_shoup: u64, q: &Modulus) {
+    let (vw, vw_shoup) = (splat(w), splat(w_shoup));
+    let (vq, v2q) = (splat(q.value()), splat(2 * q.value()));
+    let mut x_chunks = x.chunks_exact_mut(LANES);
+    let mut y_chunks = y.chunks_exact_mut(LANES);
+    for (xs, ys) in x_chunks.by_ref().zip(y_chunks.by_ref()) {
+        let u = reduce(load(xs), v2q);
+        let v = mul_shoup_lazy(load(ys), vw, vw_shoup, vq);
+        store(xs, _mm512_add_epi64(u, v));
+        store(ys, _mm512_sub_epi64(_mm512_add_epi64(u, v2q), v));
+    }
+    scalar::ct_butterflies(
+        x_chunks.into_remainder(),
+        y_chunks.into_remainder(),
+        w,
+        w_shoup,
+        q,
+    );
+}
+
+#[target_feature(enable = "avx512f,avx512ifma")]
+pub(super) fn ct_butterflies_ifma(x: &mut [u64], y: &mut [u64], w: u64, w_shoup: u64, q: &Modulus) {
+    let (vw, vw_shoup) = (splat(w), splat(w_shoup));
+    let (vq, v2q) = (splat(q.value()), splat(2 * q.value()));
+    let mut x_chunks = x.chunks_exact_mut(LANES);
+    let mut y_chunks = y.chunks_exact_mut(LANES);
+    for (xs, ys) in x_chunks.by_ref().zip(y_chunks.by_ref()) {
+        let u = reduce(load(xs), v2q);
+        let v = mul_shoup_lazy_ifma(load(ys), vw, vw_shoup, vq);
+        store(xs, _mm512_add_epi64(u, v));
+        store(ys, _mm512_sub_epi64(_mm512_add_epi64(u, v2q), v));
+    }
+    scalar::ct_butterflies(
+        x_chunks.into_remainder(),
+        y_chunks.into_remainder(),
+        w,
+        w_shoup,
+        q,
+    );
+}
+
+#[target_feature(enable = "avx512f,avx512dq")]
+pub(super) fn gs_butterflies(x: &mut [u64], y: &mut [u64], w: u64, w_shoup: u64, q: &Modulus) {
+    let (vw, vw_shoup) = (splat(w), splat(w_shoup));
+    let (vq, v2q) = (splat(q.value()), splat(2 * q.value()));
+    let mut x_chunks = x.chunks_exact_mut(LANES);
+    let mut y_chunks = y.chunks_exact_mut(LANES);
+    for (xs, ys) in x_chunks.by_ref().zip(y_chunks.by_ref()) {
+        let (u, v) = (load(xs), load(ys));
+        store(xs, reduce(_mm512_add_epi64(u, v), v2q));
+        let t = _mm512_sub_epi64(_mm512_add_epi64(u, v2q), v);
+        store(ys, mul_shoup_lazy(t, vw, vw_shoup, vq));
+    }
+    scalar::gs_butterflies(
+        x_chunks.into_remainder(),
+        y_chunks.into_remainder(),
+        w,
+        w_shoup,
+        q,
+    );
+}
+
+#[target_feature(enable = "avx512f,avx512ifma")]
+pub(super) fn gs_butterflies_ifma(x: &mut [u64], y: &mut [u64], w: u64, w_shoup: u64, q: &Modulus) {
+    let (vw, vw_shoup) = (splat(w), splat(w_shoup));
+    let (vq, v2q) = (splat(q.value()), splat(2 * q.value()));
+    let mut x_chunks = x.chunks_exact_mut(LANES);
+    let mut y_chunks = y.chunks_exact_mut(LANES);
+    for (xs, ys) in x_chunks.by_ref().zip(y_chunks.by_ref()) {
+        let (u, v) = (load(xs), load(ys));
+        store(xs, reduce(_mm512_add_epi64(u, v), v2q));
+        let t = _mm512_sub_epi64(_mm512_add_epi64(u, v2q), v);
+        store(ys, mul_shoup_lazy_ifma(t, vw, vw_shoup, vq));
+    }
+    scalar::gs_butterflies(
+        x_chunks.into_remainder(),
+        y_chunks.into_remainder(),
+        w,
+        w_shoup,
+        q,
+    );
+}
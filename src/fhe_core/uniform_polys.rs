@@ -0,0 +1,140 @@
+use crate::fhe_core::{
+    sample_seed, DcrtPoly, HashAlgorithm, PolynomialFormat, PrngFromHash, RnsBasis, SEED_LEN,
+};
+use rand_core::CryptoRngCore;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Uniform polynomials over a whole basis, in evaluation format. Those expanded
+/// from a seed serialize as the seed alone and deserialize unexpanded, until
+/// [`Self::expand`] derives them again from the seed.
+#[derive(Debug, Clone)]
+pub struct UniformPolys {
+    seed: Option<[u8; SEED_LEN]>,
+    len: usize,
+    polys: Vec<DcrtPoly>,
+}
+
+#[derive(Serialize)]
+#[serde(rename = "UniformPolys")]
+enum UniformPolysRef<'a> {
+    Seeded {
+        seed: &'a [u8; SEED_LEN],
+        len: usize,
+    },
+    Expanded(&'a [DcrtPoly]),
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "UniformPolys")]
+enum UniformPolysRepr {
+    Seeded { seed: [u8; SEED_LEN], len: usize },
+    Expanded(Vec<DcrtPoly>),
+}
+
+impl UniformPolys {
+    /// `len` polynomials expanded from a seed drawn from `rng`
+    pub fn sample<R: CryptoRngCore + ?Sized>(
+        basis: &RnsBasis,
+        len: usize,
+        hash_algorithm: HashAlgorithm,
+        rng: &mut R,
+    ) -> Self {
+        Self::from_seed(basis, len, hash_algorithm, sample_seed(rng))
+    }
+
+    /// `len` polynomials expanded from `seed`: the i-th tower of the j-th one
+    /// is sampled from the stream of the domain (j, i), as little-endian u64
+    pub fn from_seed(
+        basis: &RnsBasis,
+        len: usize,
+        hash_algorithm: HashAlgorithm,
+        seed: [u8; SEED_LEN],
+    ) -> Self {
+        let mut out = Self {
+            seed: Some(seed),
+            len,
+            polys: Vec::new(),
+        };
+        out.expand(basis, hash_algorithm);
+        out
+    }
+
+    /// Polynomials without a seed, serialized in full
+    pub fn from_polys(polys: Vec<DcrtPoly>) -> Self {
+        Self {
+            seed: None,
+            len: polys.len(),
+            polys,
+        }
+    }
+
+    /// The seed the polynomials are expanded from, if any
+    pub fn seed(&self) -> Option<&[u8; SEED_LEN]> {
+        self.seed.as_ref()
+    }
+
+    /// The number of polynomials
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the polynomials are available, false after deserializing from a
+    /// seed until they are expanded
+    pub fn is_expanded(&self) -> bool {
+        self.polys.len() == self.len
+    }
+
+    /// The polynomials, empty if they are not expanded
+    pub fn polys(&self) -> &[DcrtPoly] {
+        &self.polys
+    }
+
+    /// Derive the polynomials from the seed if they are not expanded
+    pub fn expand(&mut self, basis: &RnsBasis, hash_algorithm: HashAlgorithm) {
+        let Some(seed) = &self.seed else {
+            return;
+        };
+        if self.is_expanded() {
+            return;
+        }
+        self.polys = (0..self.len)
+            .map(|j| {
+                let towers = (0..basis.num_moduli())
+                    .map(|i| {
+                        let mut domain = (j as u64).to_le_bytes().to_vec();
+                        domain.extend_from_slice(&(i as u64).to_le_bytes());
+                        PrngFromHash::new(hash_algorithm, seed, &domain)
+                            .sample_uniform(basis.ring_dim(), basis.modulus(i))
+                    })
+                    .collect();
+                DcrtPoly::from_towers(towers, PolynomialFormat::Evaluation)
+            })
+            .collect();
+    }
+}
+
+impl Serialize for UniformPolys {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match &self.seed {
+            Some(seed) => UniformPolysRef::Seeded {
+                seed,
+                len: self.len,
+            },
+            None => UniformPolysRef::Expanded(&self.polys),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for UniformPolys {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match UniformPolysRepr::deserialize(deserializer)? {
+            UniformPolysRepr::Seeded { seed, len } => Self {
+                seed: Some(seed),
+                len,
+                polys: Vec::new(),
+            },
+            UniformPolysRepr::Expanded(polys) => Self::from_polys(polys),
+        })
+    }
+}
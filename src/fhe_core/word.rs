@@ -0,0 +1,321 @@
+use crate::fhe_core::{simd, Modulus, SimdLevel};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt::Debug;
+use std::ops::{Add, BitAnd, BitOr, Div, Mul, Rem, Shl, Shr, Sub};
+use zeroize::Zeroize;
+
+/// An unsigned machine word holding residues: `u32` for moduli of up to 28
+/// bits, `u64` for up to 60 bits and `u128`, whose double word products are
+/// emulated with 64-bit limbs, for up to 121 bits, matching
+/// [`MAX_MODULUS_SIZE`](crate::fhe_core::MAX_MODULUS_SIZE) for the native word
+/// of each pointer width. The moduli leave two bits of the word free for the
+/// lazily reduced values in [0, 4q). Double words are (high, low) pairs.
+///
+/// The slice kernels are those of the NTT and of the RNS polynomials, scalar
+/// by default and vectorized by `u64`.
+pub trait Word:
+    Copy
+    + Default
+    + Debug
+    + Eq
+    + Ord
+    + Send
+    + Sync
+    + 'static
+    + Serialize
+    + DeserializeOwned
+    + Zeroize
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Rem<Output = Self>
+    + BitAnd<Output = Self>
+    + BitOr<Output = Self>
+    + Shl<u32, Output = Self>
+    + Shr<u32, Output = Self>
+{
+    /// 0
+    const ZERO: Self;
+    /// 1
+    const ONE: Self;
+    /// The number of bits of the word
+    const BITS: u32;
+    /// The largest bit length of a modulus
+    const MAX_MODULUS_BITS: u32;
+
+    /// The word of value `x`, truncated to the low bits for narrower words
+    fn from_u64(x: u64) -> Self;
+
+    /// The number of leading zero bits
+    fn leading_zeros(self) -> u32;
+
+    /// self + rhs mod 2^BITS
+    fn wrapping_add(self, rhs: Self) -> Self;
+
+    /// self - rhs mod 2^BITS
+    fn wrapping_sub(self, rhs: Self) -> Self;
+
+    /// self * rhs mod 2^BITS
+    fn wrapping_mul(self, rhs: Self) -> Self;
+
+    /// self + rhs mod 2^BITS and whether it overflowed
+    fn overflowing_add(self, rhs: Self) -> (Self, bool);
+
+    /// The double word product (high, low) of self and rhs
+    fn mul_wide(self, rhs: Self) -> (Self, Self);
+
+    /// The quotient and remainder of the double word (hi, lo) by `d`, for
+    /// `hi < d` so that the quotient fits a word
+    fn div_rem_wide(hi: Self, lo: Self, d: Self) -> (Self, Self);
+
+    /// a <- a + b mod q
+    fn add_mod_slice(a: &mut [Self], b: &[Self], q: &Modulus<Self>) {
+        simd::scalar::add_mod(a, b, q);
+    }
+
+    /// a <- a - b mod q
+    fn sub_mod_slice(a: &mut [Self], b: &[Self], q: &Modulus<Self>) {
+        simd::scalar::sub_mod(a, b, q);
+    }
+
+    /// a <- a * b mod q
+    fn mul_mod_slice(a: &mut [Self], b: &[Self], q: &Modulus<Self>) {
+        simd::scalar::mul_mod(a, b, q);
+    }
+
+    /// a <- a * w mod q for a constant `w` with Shoup quotient `w_shoup`, see
+    /// [`simd::mul_shoup`]
+    fn mul_shoup_slice(a: &mut [Self], w: Self, w_shoup: Self, q: &Modulus<Self>, lazy: bool) {
+        simd::scalar::mul_shoup(a, w, w_shoup, q, lazy);
+    }
+
+    /// a <- a mod q for `a` in [0, 4q)
+    fn reduce_4q_slice(a: &mut [Self], q: &Modulus<Self>) {
+        simd::scalar::reduce_4q(a, q);
+    }
+
+    /// Harvey's Cooley-Tukey butterflies on values in [0, 4q)
+    fn ct_butterflies(x: &mut [Self], y: &mut [Self], w: Self, w_shoup: Self, q: &Modulus<Self>) {
+        simd::scalar::ct_butterflies(x, y, w, w_shoup, q);
+    }
+
+    /// Harvey's Gentleman-Sande butterflies on values in [0, 2q)
+    fn gs_butterflies(x: &mut [Self], y: &mut [Self], w: Self, w_shoup: Self, q: &Modulus<Self>) {
+        simd::scalar::gs_butterflies(x, y, w, w_shoup, q);
+    }
+}
+
+/// The primitive operations of a word with a native double word
+macro_rules! native_word_impl {
+    ($word:ty, $double:ty) => {
+        const ZERO: Self = 0;
+        const ONE: Self = 1;
+        const BITS: u32 = <$word>::BITS;
+
+        #[inline]
+        fn leading_zeros(self) -> u32 {
+            <$word>::leading_zeros(self)
+        }
+
+        #[inline]
+        fn wrapping_add(self, rhs: Self) -> Self {
+            <$word>::wrapping_add(self, rhs)
+        }
+
+        #[inline]
+        fn wrapping_sub(self, rhs: Self) -> Self {
+            <$word>::wrapping_sub(self, rhs)
+        }
+
+        #[inline]
+        fn wrapping_mul(self, rhs: Self) -> Self {
+            <$word>::wrapping_mul(self, rhs)
+        }
+
+        #[inline]
+        fn overflowing_add(self, rhs: Self) -> (Self, bool) {
+            <$word>::overflowing_add(self, rhs)
+        }
+
+        #[inline]
+        fn mul_wide(self, rhs: Self) -> (Self, Self) {
+            let product = self as $double * rhs as $double;
+            ((product >> <$word>::BITS) as $word, product as $word)
+        }
+
+        #[inline]
+        fn div_rem_wide(hi: Self, lo: Self, d: Self) -> (Self, Self) {
+            debug_assert!(hi < d);
+            let x = (hi as $double) << <$word>::BITS | lo as $double;
+            ((x / d as $double) as $word, (x % d as $double) as $word)
+        }
+    };
+}
+
+impl Word for u32 {
+    const MAX_MODULUS_BITS: u32 = 28;
+
+    native_word_impl!(u32, u64);
+
+    #[inline]
+    fn from_u64(x: u64) -> Self {
+        x as u32
+    }
+}
+
+impl Word for u64 {
+    const MAX_MODULUS_BITS: u32 = 60;
+
+    native_word_impl!(u64, u128);
+
+    #[inline]
+    fn from_u64(x: u64) -> Self {
+        x
+    }
+
+    fn add_mod_slice(a: &mut [Self], b: &[Self], q: &Modulus<Self>) {
+        simd::add_mod(a, b, q);
+    }
+
+    fn sub_mod_slice(a: &mut [Self], b: &[Self], q: &Modulus<Self>) {
+        simd::sub_mod(a, b, q);
+    }
+
+    fn mul_mod_slice(a: &mut [Self], b: &[Self], q: &Modulus<Self>) {
+        simd::mul_mod(a, b, q);
+    }
+
+    fn mul_shoup_slice(a: &mut [Self], w: Self, w_shoup: Self, q: &Modulus<Self>, lazy: bool) {
+        simd::mul_shoup(a, w, w_shoup, q, lazy);
+    }
+
+    fn reduce_4q_slice(a: &mut [Self], q: &Modulus<Self>) {
+        simd::reduce_4q(a, q);
+    }
+
+    fn ct_butterflies(x: &mut [Self], y: &mut [Self], w: Self, w_shoup: Self, q: &Modulus<Self>) {
+        simd::ct_butterflies(SimdLevel::detect(), x, y, w, w_shoup, q);
+    }
+
+    fn gs_butterflies(x: &mut [Self], y: &mut [Self], w: Self, w_shoup: Self, q: &Modulus<Self>) {
+        simd::gs_butterflies(SimdLevel::detect(), x, y, w, w_shoup, q);
+    }
+}
+
+impl Word for u128 {
+    const ZERO: Self = 0;
+    const ONE: Self = 1;
+    const BITS: u32 = u128::BITS;
+    const MAX_MODULUS_BITS: u32 = 121;
+
+    #[inline]
+    fn from_u64(x: u64) -> Self {
+        x as u128
+    }
+
+    #[inline]
+    fn leading_zeros(self) -> u32 {
+        u128::leading_zeros(self)
+    }
+
+    #[inline]
+    fn wrapping_add(self, rhs: Self) -> Self {
+        u128::wrapping_add(self, rhs)
+    }
+
+    #[inline]
+    fn wrapping_sub(self, rhs: Self) -> Self {
+        u128::wrapping_sub(self, rhs)
+    }
+
+    #[inline]
+    fn wrapping_mul(self, rhs: Self) -> Self {
+        u128::wrapping_mul(self, rhs)
+    }
+
+    #[inline]
+    fn overflowing_add(self, rhs: Self) -> (Self, bool) {
+        u128::overflowing_add(self, rhs)
+    }
+
+    /// Schoolbook multiplication of the 64-bit halves
+    #[inline]
+    fn mul_wide(self, rhs: Self) -> (Self, Self) {
+        const MASK: u128 = u64::MAX as u128;
+        let (a_hi, a_lo) = (self >> 64, self & MASK);
+        let (b_hi, b_lo) = (rhs >> 64, rhs & MASK);
+        let p00 = a_lo * b_lo;
+        let p01 = a_lo * b_hi;
+        let p10 = a_hi * b_lo;
+        let p11 = a_hi * b_hi;
+        let middle = (p00 >> 64) + (p01 & MASK) + (p10 & MASK);
+        let hi = p11 + (p01 >> 64) + (p10 >> 64) + (middle >> 64);
+        (hi, middle << 64 | p00 & MASK)
+    }
+
+    /// Restoring binary long division, meant for precomputations
+    fn div_rem_wide(hi: Self, lo: Self, d: Self) -> (Self, Self) {
+        debug_assert!(hi < d);
+        let (mut remainder, mut quotient) = (hi, lo);
+        for _ in 0..u128::BITS {
+            let carry = remainder >> 127;
+            remainder = remainder << 1 | quotient >> 127;
+            quotient <<= 1;
+            if carry == 1 || remainder >= d {
+                remainder = remainder.wrapping_sub(d);
+                quotient |= 1;
+            }
+        }
+        (quotient, remainder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+
+    /// Check the double word product of `a` and `b` and its division by `b`
+    /// with the remainder `r < b`
+    fn check<W: Word>(a: W, b: W, r: W) {
+        let (hi, lo) = a.mul_wide(b);
+        assert_eq!(lo, a.wrapping_mul(b));
+        assert_eq!(b.mul_wide(a), (hi, lo));
+        let (lo, carry) = lo.overflowing_add(r);
+        let hi = if carry { hi + W::ONE } else { hi };
+        if a < b {
+            assert_eq!(W::div_rem_wide(hi, lo, b), (a, r));
+        }
+    }
+
+    #[test]
+    fn double_words_of_every_word() {
+        let mut rng = ChaCha20Rng::from_seed([5; 32]);
+        for _ in 0..100_000 {
+            let (a, b) = (rng.gen::<u32>(), rng.gen::<u32>().max(1));
+            check(a, b, rng.gen_range(0..b));
+            let (a, b) = (rng.gen::<u64>(), rng.gen::<u64>().max(1));
+            check(a, b, rng.gen_range(0..b));
+            let (a, b) = (rng.gen::<u128>(), rng.gen::<u128>().max(1));
+            check(a, b, rng.gen_range(0..b));
+            // the products of 64-bit halves are native
+            let (a, b) = (rng.gen::<u64>(), rng.gen::<u64>());
+            let product = a as u128 * b as u128;
+            assert_eq!((a as u128).mul_wide(b as u128), (0, product));
+            assert_eq!(
+                (product >> 32).mul_wide(1 << 96),
+                (product >> 64, (product >> 32) << 96)
+            );
+        }
+        let max = u128::MAX;
+        assert_eq!(max.mul_wide(max), (max - 1, 1));
+        assert_eq!((1u128 << 64).mul_wide(1 << 64), (1, 0));
+        assert_eq!(u128::div_rem_wide(max - 1, 1, max), (max, 0));
+        assert_eq!(u128::div_rem_wide(0, max, 1 << 127), (1, max >> 1));
+        assert_eq!(u32::MAX.mul_wide(u32::MAX), (u32::MAX - 1, 1));
+    }
+}
@@ -21,7 +21,9 @@
 #[macro_use]
 mod macros;
 
+pub mod binfhe;
 mod error;
 mod fhe_core;
+pub mod pke;
 
 pub use error::*;
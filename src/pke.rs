@@ -0,0 +1,20 @@
+//! BGV public key encryption over RNS polynomials, with threshold key
+//! generation, decryption and key sharing for several parties
+
+mod ciphertext;
+mod context;
+mod keys;
+mod noise;
+mod params;
+mod plaintext;
+mod scheme;
+
+pub use crate::fhe_core::{
+    DecryptionNoiseMode, HashAlgorithm, KeySwitchTechnique, MultipartyMode, PkeSchemeFeature,
+    PlaintextEncodings, PrngFromHash, ProxyReEncryptionMode, Scheme, SecretShareType, SEED_LEN,
+};
+pub use ciphertext::*;
+pub use context::*;
+pub use keys::*;
+pub use params::*;
+pub use plaintext::Plaintext;
@@ -0,0 +1,255 @@
+use crate::fhe_core::{DcrtPoly, HashAlgorithm, PlaintextEncodings, UniformPolys, SEED_LEN};
+use crate::pke::keys::hash_polys;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A BGV ciphertext (c_0, ..., c_k) whose phase c_0 + c_1 * s + ... + c_k * s^k
+/// is m + t * e, with every element in evaluation format over the same number
+/// of primes, and a heuristic estimate of log2 of the norm of the phase. The
+/// uniform c_1 of a fresh secret key encryption serializes as its seed.
+#[derive(Debug, Clone)]
+pub struct Ciphertext {
+    elements: Vec<DcrtPoly>,
+    seed: Option<[u8; SEED_LEN]>,
+    encoding: PlaintextEncodings,
+    hops: usize,
+    key_tag: String,
+    noise: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename = "Ciphertext")]
+struct CiphertextRef<'a> {
+    elements: &'a [DcrtPoly],
+    seed: Option<&'a [u8; SEED_LEN]>,
+    encoding: PlaintextEncodings,
+    hops: usize,
+    key_tag: &'a str,
+    noise: f64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "Ciphertext")]
+struct CiphertextRepr {
+    elements: Vec<DcrtPoly>,
+    seed: Option<[u8; SEED_LEN]>,
+    encoding: PlaintextEncodings,
+    hops: usize,
+    key_tag: String,
+    noise: f64,
+}
+
+impl Serialize for Ciphertext {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // c_1 is left out when it expands from the seed
+        let elements = match self.seed {
+            Some(_) => &self.elements[..1],
+            None => &self.elements[..],
+        };
+        CiphertextRef {
+            elements,
+            seed: self.seed.as_ref(),
+            encoding: self.encoding,
+            hops: self.hops,
+            key_tag: &self.key_tag,
+            noise: self.noise,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Ciphertext {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = CiphertextRepr::deserialize(deserializer)?;
+        Ok(Self {
+            elements: repr.elements,
+            seed: repr.seed,
+            encoding: repr.encoding,
+            hops: repr.hops,
+            key_tag: repr.key_tag,
+            noise: repr.noise,
+        })
+    }
+}
+
+impl Ciphertext {
+    pub(crate) fn new(elements: Vec<DcrtPoly>, encoding: PlaintextEncodings) -> Self {
+        Self {
+            elements,
+            seed: None,
+            encoding,
+            hops: 0,
+            key_tag: String::new(),
+            noise: f64::NAN,
+        }
+    }
+
+    /// The same ciphertext, whose c_1 is the uniform polynomial expanded from
+    /// `seed` over the whole chain
+    pub(crate) fn with_seed(mut self, seed: [u8; SEED_LEN]) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// The same ciphertext with the estimate `noise` of log2 of its phase
+    pub(crate) fn with_noise(mut self, noise: f64) -> Self {
+        self.noise = noise;
+        self
+    }
+
+    /// The same ciphertext under the key tagged `key_tag`
+    pub(crate) fn with_key_tag(mut self, key_tag: &str) -> Self {
+        self.key_tag = key_tag.to_string();
+        self
+    }
+
+    /// The same ciphertext after `hops` proxy re-encryptions
+    pub(crate) fn with_hops(mut self, hops: usize) -> Self {
+        self.hops = hops;
+        self
+    }
+
+    /// The number of polynomials of the ciphertext, 2 for a fresh one
+    pub fn num_elements(&self) -> usize {
+        self.elements.len()
+    }
+
+    /// The number of primes the ciphertext is defined over
+    pub fn num_towers(&self) -> usize {
+        self.elements[0].num_towers()
+    }
+
+    /// The encoding of the encrypted plaintext
+    pub fn encoding(&self) -> PlaintextEncodings {
+        self.encoding
+    }
+
+    /// The number of proxy re-encryptions the ciphertext, or any ciphertext it
+    /// was computed from, went through
+    pub fn hops(&self) -> usize {
+        self.hops
+    }
+
+    /// The tag of the public key the ciphertext is encrypted under, the sum of
+    /// the tags of the keys of the parties for a joint public key
+    pub fn key_tag(&self) -> &str {
+        &self.key_tag
+    }
+
+    pub(crate) fn noise(&self) -> f64 {
+        self.noise
+    }
+
+    pub(crate) fn set_noise(&mut self, noise: f64) {
+        self.noise = noise;
+    }
+
+    pub(crate) fn elements(&self) -> &[DcrtPoly] {
+        &self.elements
+    }
+
+    /// The elements, which no longer expand from the seed once modified
+    pub(crate) fn elements_mut(&mut self) -> &mut Vec<DcrtPoly> {
+        self.seed = None;
+        &mut self.elements
+    }
+
+    pub(crate) fn seed(&self) -> Option<&[u8; SEED_LEN]> {
+        self.seed.as_ref()
+    }
+
+    /// Restore the c_1 of a ciphertext deserialized without it
+    pub(crate) fn restore_uniform(&mut self, c1: DcrtPoly) {
+        self.elements.push(c1);
+    }
+}
+
+/// The share of a party in a threshold decryption: c_0 + c_1 * s_1 + t * e for
+/// the lead party and c_1 * s_j + t * e for the others, so that the shares add up
+/// to the phase of the ciphertext. It carries the key tag of the ciphertext and
+/// the hex hash of the little-endian residues of its elements, so that only the
+/// shares of a single ciphertext are combined.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecryptionShare {
+    share: DcrtPoly,
+    encoding: PlaintextEncodings,
+    key_tag: String,
+    ciphertext_digest: String,
+    is_lead: bool,
+}
+
+impl DecryptionShare {
+    pub(crate) fn new(
+        share: DcrtPoly,
+        ct: &Ciphertext,
+        is_lead: bool,
+        hash_algorithm: HashAlgorithm,
+    ) -> Self {
+        Self {
+            share,
+            encoding: ct.encoding(),
+            key_tag: ct.key_tag().to_string(),
+            ciphertext_digest: hash_polys(ct.elements(), hash_algorithm),
+            is_lead,
+        }
+    }
+
+    /// The number of primes the share is defined over
+    pub fn num_towers(&self) -> usize {
+        self.share.num_towers()
+    }
+
+    /// The encoding of the encrypted plaintext
+    pub fn encoding(&self) -> PlaintextEncodings {
+        self.encoding
+    }
+
+    /// The key tag of the ciphertext
+    pub fn key_tag(&self) -> &str {
+        &self.key_tag
+    }
+
+    /// The hash of the elements of the ciphertext
+    pub fn ciphertext_digest(&self) -> &str {
+        &self.ciphertext_digest
+    }
+
+    /// Whether the share is that of the lead party, which holds c_0
+    pub fn is_lead(&self) -> bool {
+        self.is_lead
+    }
+
+    pub(crate) fn share(&self) -> &DcrtPoly {
+        &self.share
+    }
+}
+
+/// A uniform polynomial over the whole chain shared by all the parties, the
+/// common reference string of a threshold key generation, serialized as its
+/// seed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommonRandomPoly {
+    a: UniformPolys,
+}
+
+impl CommonRandomPoly {
+    pub(crate) fn new(a: UniformPolys) -> Self {
+        Self { a }
+    }
+
+    /// The seed the polynomial is expanded from
+    pub fn seed(&self) -> Option<&[u8; SEED_LEN]> {
+        self.a.seed()
+    }
+
+    pub(crate) fn poly(&self) -> &DcrtPoly {
+        &self.a.polys()[0]
+    }
+
+    pub(crate) fn uniform(&self) -> &UniformPolys {
+        &self.a
+    }
+
+    pub(crate) fn uniform_mut(&mut self) -> &mut UniformPolys {
+        &mut self.a
+    }
+}
@@ -0,0 +1,905 @@
+mod key_sharing;
+mod multiparty;
+mod pre;
+mod serial;
+
+use crate::fhe_core::parallel::{self, ThreadPool};
+use crate::fhe_core::{
+    ContextRng, DcrtPoly, PkeSchemeFeature, PlaintextEncodings, PolynomialFormat, UniformPolys,
+    SEED_LEN,
+};
+use crate::pke::{
+    noise, scheme, Ciphertext, CryptoParams, EvalKey, KeyPair, Plaintext, PrivateKey, PublicKey,
+};
+use crate::{Error, FheResult};
+use rand_chacha::ChaCha20Rng;
+use rand_core::CryptoRngCore;
+use std::collections::BTreeMap;
+
+pub use serial::{SerialObject, FORMAT_VERSION};
+
+/// Entry point of the BGV scheme. Every operation belongs to a
+/// [`PkeSchemeFeature`] that must be enabled first, and the evaluation keys
+/// needed by multiplications and rotations are stored in the context. The
+/// randomness of every sampling is drawn from the generator of the context,
+/// shared by its clones. With the `parallel` feature the towers, key switching
+/// digits, keys and ciphertexts of an operation are processed on the thread
+/// pool of the context.
+#[derive(Debug, Clone)]
+pub struct CryptoContext {
+    params: CryptoParams,
+    features: u8,
+    eval_mult_key: Option<EvalKey>,
+    eval_automorphism_keys: BTreeMap<usize, EvalKey>,
+    rng: ContextRng,
+    rng_seed: Option<[u8; SEED_LEN]>,
+    pool: ThreadPool,
+}
+
+impl CryptoContext {
+    /// Create a context with no feature enabled
+    pub fn new(params: CryptoParams) -> Self {
+        Self {
+            params,
+            features: 0,
+            eval_mult_key: None,
+            eval_automorphism_keys: BTreeMap::new(),
+            rng: ContextRng::default(),
+            rng_seed: None,
+            pool: ThreadPool::default(),
+        }
+    }
+
+    /// The parameters of the context
+    pub fn params(&self) -> &CryptoParams {
+        &self.params
+    }
+
+    /// Draw the randomness from `rng` rather than from ChaCha20 keyed by the
+    /// operating system
+    pub fn set_rng<R: CryptoRngCore + Send + 'static>(&mut self, rng: R) {
+        self.rng = ContextRng::new(rng);
+        self.rng_seed = None;
+    }
+
+    /// Draw the randomness from ChaCha20 keyed with `seed`, so the same
+    /// sequence of operations gives the same keys and ciphertexts. Meant for
+    /// tests and known-answer vectors, anyone holding the seed can recompute
+    /// the secrets. The seed is not serialized with the context.
+    pub fn set_rng_seed(&mut self, seed: [u8; SEED_LEN]) {
+        self.rng = ContextRng::from_seed(seed);
+        self.rng_seed = Some(seed);
+    }
+
+    /// The seed of the generator, if set by [`Self::set_rng_seed`]
+    pub fn rng_seed(&self) -> Option<&[u8; SEED_LEN]> {
+        self.rng_seed.as_ref()
+    }
+
+    /// Run the parallel operations on a pool of `num_threads` threads of the
+    /// context rather than on the global rayon pool, as many as the CPU has
+    /// logical cores if 0. The pool is shared by the clones of the context and
+    /// is not serialized.
+    #[cfg(feature = "parallel")]
+    pub fn set_num_threads(&mut self, num_threads: usize) -> FheResult<()> {
+        self.pool = ThreadPool::new(num_threads)
+            .map_err(|e| Error::InvalidParameters(format!("cannot build thread pool: {e}")))?;
+        Ok(())
+    }
+
+    /// The number of threads running the parallel operations
+    #[cfg(feature = "parallel")]
+    pub fn num_threads(&self) -> usize {
+        self.pool.num_threads()
+    }
+
+    /// Enable `feature`, bootstrapping and scheme switching are not available
+    pub fn enable(&mut self, feature: PkeSchemeFeature) -> FheResult<()> {
+        if matches!(
+            feature,
+            PkeSchemeFeature::Fhe | PkeSchemeFeature::SchemeSwitch
+        ) {
+            return Err(Error::InvalidParameters(format!(
+                "feature {feature} is not supported by BGV"
+            )));
+        }
+        self.features |= u8::from(feature);
+        Ok(())
+    }
+
+    /// Whether `feature` is enabled
+    pub fn is_enabled(&self, feature: PkeSchemeFeature) -> bool {
+        self.features & u8::from(feature) != 0
+    }
+
+    /// Generate a fresh key pair
+    pub fn key_gen(&self) -> FheResult<KeyPair> {
+        self.require(PkeSchemeFeature::Pke)?;
+        Ok(scheme::key_gen(&self.params, None, &mut self.rng()))
+    }
+
+    /// Encode `values` in the slots of a plaintext, which needs a prime
+    /// plaintext modulus `t = 1 mod 2N`
+    pub fn make_packed_plaintext(&self, values: &[i64]) -> FheResult<Plaintext> {
+        if self.params.packing().is_none() {
+            return Err(Error::InvalidParameters(format!(
+                "plaintext modulus {} does not support packed encoding",
+                self.params.plaintext_modulus()
+            )));
+        }
+        self.check_length(values)?;
+        Ok(Plaintext::new(values.to_vec(), PlaintextEncodings::Packed))
+    }
+
+    /// Encode `values` as the coefficients of a plaintext
+    pub fn make_coef_packed_plaintext(&self, values: &[i64]) -> FheResult<Plaintext> {
+        self.check_length(values)?;
+        Ok(Plaintext::new(
+            values.to_vec(),
+            PlaintextEncodings::CoefPacked,
+        ))
+    }
+
+    /// Encrypt `pt` under `pk`
+    pub fn encrypt(&self, pk: &PublicKey, pt: &Plaintext) -> FheResult<Ciphertext> {
+        self.require(PkeSchemeFeature::Pke)?;
+        self.check_public_key(pk)?;
+        let coefficients = self.encode(pt)?;
+        let mut rng = self.rng();
+        Ok(self
+            .install(|| scheme::encrypt(&self.params, pk, &coefficients, pt.encoding(), &mut rng)))
+    }
+
+    /// Encrypt every plaintext of `pts` under `pk`, in parallel with the
+    /// `parallel` feature. Every encryption draws its randomness from its own
+    /// generator keyed from that of the context, so the ciphertexts do not
+    /// depend on the number of threads.
+    pub fn encrypt_batch(&self, pk: &PublicKey, pts: &[Plaintext]) -> FheResult<Vec<Ciphertext>> {
+        self.require(PkeSchemeFeature::Pke)?;
+        self.check_public_key(pk)?;
+        let coefficients = pts
+            .iter()
+            .map(|pt| self.encode(pt))
+            .collect::<FheResult<Vec<_>>>()?;
+        let rngs = self.rng.fork(pts.len());
+        Ok(self.install(|| {
+            parallel::map(pts, |i, pt| {
+                scheme::encrypt(
+                    &self.params,
+                    pk,
+                    &coefficients[i],
+                    pt.encoding(),
+                    &mut rngs[i].clone(),
+                )
+            })
+        }))
+    }
+
+    /// Encrypt `pt` under `sk`, with the noise of a single error rather than
+    /// that of a public key encryption and a c_1 serialized as its seed
+    pub fn encrypt_with_secret_key(
+        &self,
+        sk: &PrivateKey,
+        pt: &Plaintext,
+    ) -> FheResult<Ciphertext> {
+        self.require(PkeSchemeFeature::Pke)?;
+        self.check_private_key(sk)?;
+        let coefficients = self.encode(pt)?;
+        let mut rng = self.rng();
+        let ct = self.install(|| {
+            scheme::encrypt_with_secret_key(
+                &self.params,
+                sk.poly(),
+                &coefficients,
+                pt.encoding(),
+                &mut rng,
+            )
+        });
+        Ok(ct.with_key_tag(sk.key_tag()))
+    }
+
+    /// Decrypt `ct` into all N of its slots or coefficients
+    pub fn decrypt(&self, sk: &PrivateKey, ct: &Ciphertext) -> FheResult<Plaintext> {
+        self.require(PkeSchemeFeature::Pke)?;
+        self.check_private_key(sk)?;
+        self.check_ciphertext(ct)?;
+        check_key_tags(sk.key_tag(), ct.key_tag())?;
+        Ok(self.install(|| self.decrypt_unchecked(sk, ct)))
+    }
+
+    /// Decrypt every ciphertext of `cts`, in parallel with the `parallel`
+    /// feature
+    pub fn decrypt_batch(&self, sk: &PrivateKey, cts: &[Ciphertext]) -> FheResult<Vec<Plaintext>> {
+        self.require(PkeSchemeFeature::Pke)?;
+        self.check_private_key(sk)?;
+        for ct in cts {
+            self.check_ciphertext(ct)?;
+            check_key_tags(sk.key_tag(), ct.key_tag())?;
+        }
+        Ok(self.install(|| parallel::map(cts, |_, ct| self.decrypt_unchecked(sk, ct))))
+    }
+
+    /// The number of bits the noise of `ct` can still grow by before its
+    /// decryption fails: log2 of half the modulus of the ciphertext minus log2
+    /// of the largest coefficient of its phase m + t * e under `sk`
+    pub fn noise_budget(&self, sk: &PrivateKey, ct: &Ciphertext) -> FheResult<f64> {
+        self.require(PkeSchemeFeature::Pke)?;
+        self.check_private_key(sk)?;
+        self.check_ciphertext(ct)?;
+        check_key_tags(sk.key_tag(), ct.key_tag())?;
+        let phase = scheme::phase(&self.params, sk.poly(), ct);
+        let log_q: f64 = self.params.moduli()[..ct.num_towers()]
+            .iter()
+            .map(|&q| (q as f64).log2())
+            .sum();
+        Ok((log_q - 1.0 - phase.log2_norm(self.params.basis())).max(0.0))
+    }
+
+    /// A heuristic estimate of log2 of the largest coefficient of the phase
+    /// m + t * e of `ct`, tracked through every operation without the secret
+    /// key: the noise budget is about log2 of half the modulus of the
+    /// ciphertext minus the estimate.
+    pub fn estimated_noise(&self, ct: &Ciphertext) -> FheResult<f64> {
+        self.check_ciphertext(ct)?;
+        Ok(ct.noise())
+    }
+
+    /// ct1 + ct2
+    pub fn eval_add(&self, ct1: &Ciphertext, ct2: &Ciphertext) -> FheResult<Ciphertext> {
+        self.require(PkeSchemeFeature::LeveledShe)?;
+        let (mut out, other) = self.align(ct1, ct2)?;
+        let basis = self.params.basis();
+        let elements = out.elements_mut();
+        for (i, c) in other.elements().iter().enumerate() {
+            match elements.get_mut(i) {
+                Some(e) => e.add_assign(basis, c),
+                None => elements.push(c.clone()),
+            }
+        }
+        out.set_noise(noise::add(out.noise(), other.noise()));
+        Ok(out)
+    }
+
+    /// ct1 - ct2
+    pub fn eval_sub(&self, ct1: &Ciphertext, ct2: &Ciphertext) -> FheResult<Ciphertext> {
+        self.eval_add(ct1, &self.eval_negate(ct2)?)
+    }
+
+    /// -ct
+    pub fn eval_negate(&self, ct: &Ciphertext) -> FheResult<Ciphertext> {
+        self.require(PkeSchemeFeature::LeveledShe)?;
+        self.check_ciphertext(ct)?;
+        let mut out = ct.clone();
+        for c in out.elements_mut() {
+            c.neg_assign(self.params.basis());
+        }
+        Ok(out)
+    }
+
+    /// ct1 * ct2, relinearized with the evaluation multiplication key
+    pub fn eval_mult(&self, ct1: &Ciphertext, ct2: &Ciphertext) -> FheResult<Ciphertext> {
+        self.require(PkeSchemeFeature::LeveledShe)?;
+        let ek = self.eval_mult_key()?;
+        let (ct1, ct2) = self.align(ct1, ct2)?;
+        if ct1.num_elements() != 2 || ct2.num_elements() != 2 {
+            return Err(Error::InvalidCiphertext(
+                "only ciphertexts of two elements can be multiplied".to_string(),
+            ));
+        }
+        check_key_tags(ek.source_key_tag(), ct1.key_tag())?;
+        Ok(self.install(|| {
+            let mut out = scheme::eval_mult(&self.params, &ct1, &ct2);
+            scheme::relinearize(&self.params, ek, &mut out);
+            out
+        }))
+    }
+
+    /// Drop the last prime of the ciphertext, dividing its noise by it
+    pub fn mod_reduce(&self, ct: &Ciphertext) -> FheResult<Ciphertext> {
+        self.require(PkeSchemeFeature::LeveledShe)?;
+        self.check_ciphertext(ct)?;
+        if ct.num_towers() <= self.params.min_towers() {
+            return Err(Error::InvalidCiphertext(
+                "the ciphertext is at the last level".to_string(),
+            ));
+        }
+        let mut out = ct.clone();
+        scheme::mod_reduce(&self.params, &mut out);
+        Ok(out)
+    }
+
+    /// Generate the relinearization key from s^2 to s and store it
+    pub fn eval_mult_key_gen(&mut self, sk: &PrivateKey) -> FheResult<()> {
+        self.require(PkeSchemeFeature::LeveledShe)?;
+        self.check_private_key(sk)?;
+        let basis = self.params.basis();
+        let s2 = sk.poly().mul(basis, sk.poly());
+        let mut rng = self.rng();
+        let ek =
+            self.install(|| scheme::key_switch_gen(&self.params, &s2, sk.poly(), None, &mut rng));
+        self.eval_mult_key = Some(ek.with_key_tags(sk.key_tag(), sk.key_tag()));
+        Ok(())
+    }
+
+    /// The relinearization key, if one was generated or loaded
+    pub fn get_eval_mult_key(&self) -> Option<&EvalKey> {
+        self.eval_mult_key.as_ref()
+    }
+
+    /// Load a relinearization key, e.g. one generated jointly
+    pub fn insert_eval_mult_key(&mut self, ek: EvalKey) -> FheResult<()> {
+        self.check_eval_key(&ek)?;
+        self.eval_mult_key = Some(ek);
+        Ok(())
+    }
+
+    /// Generate and store the keys rotating the slots by every index of
+    /// `indices`, positive indices rotating left
+    pub fn eval_rotate_key_gen(&mut self, sk: &PrivateKey, indices: &[i32]) -> FheResult<()> {
+        self.require(PkeSchemeFeature::LeveledShe)?;
+        let keys = self.eval_automorphism_key_gen(sk, &self.rotation_indices(indices))?;
+        self.eval_automorphism_keys.extend(keys);
+        Ok(())
+    }
+
+    /// Rotate the slot rows of `ct` left by `index`
+    pub fn eval_rotate(&self, ct: &Ciphertext, index: i32) -> FheResult<Ciphertext> {
+        self.require(PkeSchemeFeature::LeveledShe)?;
+        self.eval_automorphism(ct, scheme::rotation_index(&self.params, index))
+    }
+
+    /// Generate and store the keys used by [`Self::eval_sum`]
+    pub fn eval_sum_key_gen(&mut self, sk: &PrivateKey) -> FheResult<()> {
+        self.require(PkeSchemeFeature::AdvancedShe)?;
+        let keys = self.eval_automorphism_key_gen(sk, &self.sum_indices())?;
+        self.eval_automorphism_keys.extend(keys);
+        Ok(())
+    }
+
+    /// Add up the first `batch_size` slots, a power of two, of every cyclic
+    /// window: with `batch_size` = N every slot holds the sum of all of them
+    pub fn eval_sum(&self, ct: &Ciphertext, batch_size: usize) -> FheResult<Ciphertext> {
+        self.require(PkeSchemeFeature::AdvancedShe)?;
+        self.require(PkeSchemeFeature::LeveledShe)?;
+        let ring_dim = self.params.ring_dim();
+        if !batch_size.is_power_of_two() || batch_size > ring_dim {
+            return Err(Error::InvalidParameters(format!(
+                "batch size {batch_size} is not a power of two up to {ring_dim}"
+            )));
+        }
+        let mut out = ct.clone();
+        let mut step = 1;
+        while step < batch_size.min(ring_dim / 2) {
+            let rotated =
+                self.eval_automorphism(&out, scheme::rotation_index(&self.params, step as i32))?;
+            out = self.eval_add(&out, &rotated)?;
+            step <<= 1;
+        }
+        if batch_size == ring_dim {
+            let swapped = self.eval_automorphism(&out, scheme::row_swap_index(&self.params))?;
+            out = self.eval_add(&out, &swapped)?;
+        }
+        Ok(out)
+    }
+
+    /// The stored automorphism keys, by automorphism index
+    pub fn get_eval_automorphism_keys(&self) -> &BTreeMap<usize, EvalKey> {
+        &self.eval_automorphism_keys
+    }
+
+    /// Load automorphism keys, e.g. ones generated jointly
+    pub fn insert_eval_automorphism_keys(
+        &mut self,
+        keys: BTreeMap<usize, EvalKey>,
+    ) -> FheResult<()> {
+        for ek in keys.values() {
+            self.check_eval_key(ek)?;
+        }
+        self.eval_automorphism_keys.extend(keys);
+        Ok(())
+    }
+
+    /// Generate the key switching key from `sk_from` to `sk_to`
+    pub fn key_switch_gen(&self, sk_from: &PrivateKey, sk_to: &PrivateKey) -> FheResult<EvalKey> {
+        self.require(PkeSchemeFeature::KeySwitch)?;
+        self.check_private_key(sk_from)?;
+        self.check_private_key(sk_to)?;
+        let mut rng = self.rng();
+        let ek = self.install(|| {
+            scheme::key_switch_gen(&self.params, sk_from.poly(), sk_to.poly(), None, &mut rng)
+        });
+        Ok(ek.with_key_tags(sk_from.key_tag(), sk_to.key_tag()))
+    }
+
+    /// Switch a ciphertext of two elements to the target key of `ek`
+    pub fn key_switch(&self, ct: &Ciphertext, ek: &EvalKey) -> FheResult<Ciphertext> {
+        self.require(PkeSchemeFeature::KeySwitch)?;
+        self.check_ciphertext(ct)?;
+        self.check_eval_key(ek)?;
+        check_linear(ct)?;
+        check_key_tags(ek.source_key_tag(), ct.key_tag())?;
+        let [mut k0, k1] = self.install(|| scheme::key_switch(&self.params, ek, &ct.elements()[1]));
+        k0.add_assign(self.params.basis(), &ct.elements()[0]);
+        let key_switch = noise::key_switch(&self.params, ct.num_towers(), self.params.std_dev());
+        Ok(Ciphertext::new(vec![k0, k1], ct.encoding())
+            .with_hops(ct.hops())
+            .with_key_tag(ek.target_key_tag())
+            .with_noise(noise::add(ct.noise(), key_switch)))
+    }
+
+    /// The automorphism indices of the rotations by `indices`
+    fn rotation_indices(&self, indices: &[i32]) -> Vec<usize> {
+        indices
+            .iter()
+            .map(|&r| scheme::rotation_index(&self.params, r))
+            .collect()
+    }
+
+    /// The automorphism indices used by [`Self::eval_sum`]
+    fn sum_indices(&self) -> Vec<usize> {
+        let half = self.params.ring_dim() / 2;
+        let mut indices: Vec<usize> = (0..half.trailing_zeros())
+            .map(|i| scheme::rotation_index(&self.params, 1 << i))
+            .collect();
+        indices.push(scheme::row_swap_index(&self.params));
+        indices
+    }
+
+    fn eval_automorphism_key_gen(
+        &self,
+        sk: &PrivateKey,
+        indices: &[usize],
+    ) -> FheResult<BTreeMap<usize, EvalKey>> {
+        self.check_private_key(sk)?;
+        // one generator per key, so the keys do not depend on the number of
+        // threads
+        let rngs = self.rng.fork(indices.len());
+        let keys = self.install(|| {
+            parallel::map(indices, |i, &k| {
+                let s_k = scheme::automorphism_poly(&self.params, sk.poly(), k);
+                let ek = scheme::key_switch_gen(
+                    &self.params,
+                    &s_k,
+                    sk.poly(),
+                    None,
+                    &mut rngs[i].clone(),
+                );
+                (k, ek.with_key_tags(sk.key_tag(), sk.key_tag()))
+            })
+        });
+        Ok(keys.into_iter().collect())
+    }
+
+    fn eval_automorphism(&self, ct: &Ciphertext, k: usize) -> FheResult<Ciphertext> {
+        self.check_ciphertext(ct)?;
+        check_linear(ct)?;
+        if k == 1 {
+            return Ok(ct.clone());
+        }
+        let ek = self
+            .eval_automorphism_keys
+            .get(&k)
+            .ok_or_else(|| Error::MissingEvalKey(format!("automorphism {k}")))?;
+        check_key_tags(ek.source_key_tag(), ct.key_tag())?;
+        Ok(self.install(|| scheme::automorphism(&self.params, ek, ct, k)))
+    }
+
+    fn eval_mult_key(&self) -> FheResult<&EvalKey> {
+        self.eval_mult_key
+            .as_ref()
+            .ok_or_else(|| Error::MissingEvalKey("relinearization".to_string()))
+    }
+
+    /// Bring two ciphertexts to the same number of primes
+    fn align(&self, ct1: &Ciphertext, ct2: &Ciphertext) -> FheResult<(Ciphertext, Ciphertext)> {
+        self.check_ciphertext(ct1)?;
+        self.check_ciphertext(ct2)?;
+        if u8::from(ct1.encoding()) != u8::from(ct2.encoding()) {
+            return Err(Error::InvalidCiphertext(format!(
+                "cannot combine {} and {} ciphertexts",
+                ct1.encoding(),
+                ct2.encoding()
+            )));
+        }
+        check_key_tags(ct1.key_tag(), ct2.key_tag())?;
+        let num_towers = ct1.num_towers().min(ct2.num_towers());
+        let hops = ct1.hops().max(ct2.hops());
+        let (mut ct1, mut ct2) = (ct1.clone().with_hops(hops), ct2.clone().with_hops(hops));
+        for c in ct1.elements_mut().iter_mut().chain(ct2.elements_mut()) {
+            c.truncate(num_towers);
+        }
+        Ok((ct1, ct2))
+    }
+
+    /// The plaintext polynomial of `pt`, with coefficients in (-t/2, t/2]
+    fn encode(&self, pt: &Plaintext) -> FheResult<Vec<i64>> {
+        let t = self.params.plaintext_modulus_ref();
+        match (pt.encoding(), self.params.packing()) {
+            (PlaintextEncodings::Packed, Some(packing)) => Ok(packing.encode(pt.values())),
+            (PlaintextEncodings::CoefPacked, _) => Ok(pt
+                .values()
+                .iter()
+                .map(|&v| t.center(t.reduce_i64(v)))
+                .collect()),
+            (encoding, _) => Err(Error::InvalidParameters(format!(
+                "unsupported plaintext encoding {encoding}"
+            ))),
+        }
+    }
+
+    fn decode(&self, coefficients: &[u64], encoding: PlaintextEncodings) -> Plaintext {
+        let t = self.params.plaintext_modulus_ref();
+        let values = match (encoding, self.params.packing()) {
+            (PlaintextEncodings::Packed, Some(packing)) => packing.decode(coefficients),
+            _ => coefficients.iter().map(|&c| t.center(c)).collect(),
+        };
+        Plaintext::new(values, encoding)
+    }
+
+    /// The plaintext behind `ct`, checked against `sk` by the caller
+    fn decrypt_unchecked(&self, sk: &PrivateKey, ct: &Ciphertext) -> Plaintext {
+        let phase = scheme::phase(&self.params, sk.poly(), ct);
+        self.decode(&scheme::decode_phase(&self.params, phase), ct.encoding())
+    }
+
+    /// Run `op` on the thread pool of the context
+    fn install<R: Send>(&self, op: impl FnOnce() -> R + Send) -> R {
+        self.pool.install(op)
+    }
+
+    /// A generator of its own for an operation, keyed from that of the
+    /// context before the operation runs on the thread pool so that the lock of
+    /// the latter is never held across parallel loops
+    fn rng(&self) -> ChaCha20Rng {
+        self.rng.fork_one()
+    }
+
+    fn require(&self, feature: PkeSchemeFeature) -> FheResult<()> {
+        if !self.is_enabled(feature) {
+            return Err(Error::FeatureNotEnabled(feature));
+        }
+        Ok(())
+    }
+
+    fn check_length(&self, values: &[i64]) -> FheResult<()> {
+        if values.len() > self.params.ring_dim() {
+            return Err(Error::InvalidParameters(format!(
+                "{} values do not fit {} slots",
+                values.len(),
+                self.params.ring_dim()
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_private_key(&self, sk: &PrivateKey) -> FheResult<()> {
+        if !self.is_key_poly(sk.poly()) {
+            return Err(Error::InvalidKey(
+                "private key for other parameters".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_public_key(&self, pk: &PublicKey) -> FheResult<()> {
+        if !self.is_uniform(pk.uniform(), 1) || !self.is_key_poly(pk.b()) {
+            return Err(Error::InvalidKey(
+                "public key for other parameters".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_eval_key(&self, ek: &EvalKey) -> FheResult<()> {
+        let digits = self.num_digits();
+        if ek.num_digits() != digits {
+            return Err(Error::InvalidKey(format!(
+                "expected an evaluation key of {} digits, got {}",
+                digits,
+                ek.num_digits()
+            )));
+        }
+        if !self.is_uniform(ek.uniform(), digits) || !ek.b().iter().all(|p| self.is_key_poly(p)) {
+            return Err(Error::InvalidKey(
+                "evaluation key for other parameters".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_ciphertext(&self, ct: &Ciphertext) -> FheResult<()> {
+        let num_towers = ct.elements().first().map_or(0, DcrtPoly::num_towers);
+        if ct.num_elements() < 2
+            || !(self.params.min_towers()..=self.params.moduli().len()).contains(&num_towers)
+            || !ct
+                .elements()
+                .iter()
+                .all(|c| c.num_towers() == num_towers && self.is_well_formed(c))
+        {
+            return Err(Error::InvalidCiphertext(
+                "ciphertext for other parameters".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether a key polynomial is well formed over the whole chain
+    fn is_key_poly(&self, p: &DcrtPoly) -> bool {
+        p.num_towers() == self.params.moduli().len() && self.is_well_formed(p)
+    }
+
+    /// The number of digits of the evaluation keys
+    fn num_digits(&self) -> usize {
+        (0..self.params.moduli().len())
+            .map(|i| self.params.digits(i))
+            .sum()
+    }
+
+    /// Whether `a` holds `len` expanded key polynomials
+    fn is_uniform(&self, a: &UniformPolys, len: usize) -> bool {
+        a.len() == len && a.is_expanded() && a.polys().iter().all(|p| self.is_key_poly(p))
+    }
+
+    /// Whether every tower of an evaluation format polynomial has N coefficients
+    fn is_well_formed(&self, p: &DcrtPoly) -> bool {
+        matches!(p.format(), PolynomialFormat::Evaluation)
+            && (0..p.num_towers()).all(|i| p.tower(i).len() == self.params.ring_dim())
+    }
+}
+
+/// Fail for objects of two different keys, or of no known key
+pub(crate) fn check_key_tags(tag1: &str, tag2: &str) -> FheResult<()> {
+    if tag1.is_empty() || tag1 != tag2 {
+        return Err(Error::KeyTagMismatch(tag1.to_string(), tag2.to_string()));
+    }
+    Ok(())
+}
+
+/// Fail for ciphertexts that still need a relinearization
+pub(crate) fn check_linear(ct: &Ciphertext) -> FheResult<()> {
+    if ct.num_elements() != 2 {
+        return Err(Error::InvalidCiphertext(format!(
+            "expected a ciphertext of two elements, got {}",
+            ct.num_elements()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::pke::CryptoParamsBuilder;
+
+    /// A context of the parameters of `builder` with `features` enabled
+    pub(crate) fn context_with(
+        builder: CryptoParamsBuilder,
+        features: &[PkeSchemeFeature],
+    ) -> CryptoContext {
+        let mut cc = CryptoContext::new(builder.build().unwrap());
+        for &feature in features {
+            cc.enable(feature).unwrap();
+        }
+        cc
+    }
+
+    /// The first `len` values of `pt`
+    pub(crate) fn values(mut pt: Plaintext, len: usize) -> Vec<i64> {
+        pt.set_length(len);
+        pt.values().to_vec()
+    }
+
+    /// 50-bit primes keep the ring dimension at 4096
+    fn context() -> CryptoContext {
+        context_with(
+            CryptoParams::builder().mod_size(50),
+            &[PkeSchemeFeature::Pke, PkeSchemeFeature::LeveledShe],
+        )
+    }
+
+    #[test]
+    fn seeded_operations_repeat() {
+        let run = || {
+            let mut cc = context();
+            cc.set_rng_seed([7; SEED_LEN]);
+            let keys = cc.key_gen().unwrap();
+            let pt = cc.make_packed_plaintext(&[5, -6]).unwrap();
+            let ct1 = cc.encrypt(&keys.public_key, &pt).unwrap();
+            let ct2 = cc.encrypt(&keys.public_key, &pt).unwrap();
+            (ct1, ct2)
+        };
+        let (a1, a2) = run();
+        let (b1, b2) = run();
+        assert_eq!(a1.elements(), b1.elements());
+        assert_eq!(a2.elements(), b2.elements());
+        assert_ne!(a1.elements(), a2.elements());
+    }
+
+    #[test]
+    fn eval_sum_needs_leveled_she() {
+        let mut cc = context_with(
+            CryptoParams::builder().mod_size(50),
+            &[PkeSchemeFeature::Pke, PkeSchemeFeature::AdvancedShe],
+        );
+        let keys = cc.key_gen().unwrap();
+        cc.eval_sum_key_gen(&keys.secret_key).unwrap();
+        let pt = cc.make_packed_plaintext(&[1, 2, 3, 4]).unwrap();
+        let ct = cc.encrypt(&keys.public_key, &pt).unwrap();
+        assert!(matches!(
+            cc.eval_sum(&ct, 4),
+            Err(Error::FeatureNotEnabled(PkeSchemeFeature::LeveledShe))
+        ));
+        cc.enable(PkeSchemeFeature::LeveledShe).unwrap();
+        let sum = cc.eval_sum(&ct, 4).unwrap();
+        let pt = cc.decrypt(&keys.secret_key, &sum).unwrap();
+        assert_eq!(values(pt, 1), &[10]);
+    }
+
+    #[test]
+    fn secret_key_encryption() {
+        let cc = context();
+        let keys = cc.key_gen().unwrap();
+        let pt = cc.make_packed_plaintext(&[1, -2, 3]).unwrap();
+        let ct = cc.encrypt_with_secret_key(&keys.secret_key, &pt).unwrap();
+        assert!(ct.seed().is_some());
+        assert_eq!(ct.key_tag(), keys.public_key.key_tag());
+        assert_eq!(
+            values(cc.decrypt(&keys.secret_key, &ct).unwrap(), 3),
+            &[1, -2, 3]
+        );
+        // the seed no longer describes c_1 once the ciphertext is modified
+        let sum = cc.eval_add(&ct, &ct).unwrap();
+        assert!(sum.seed().is_none());
+        assert_eq!(
+            values(cc.decrypt(&keys.secret_key, &sum).unwrap(), 3),
+            &[2, -4, 6]
+        );
+    }
+
+    /// log2 of the largest coefficient of the phase of `ct`, measured from
+    /// its noise budget
+    fn measured_noise(cc: &CryptoContext, sk: &PrivateKey, ct: &Ciphertext) -> f64 {
+        let log_q: f64 = cc.params.moduli()[..ct.num_towers()]
+            .iter()
+            .map(|&q| (q as f64).log2())
+            .sum();
+        log_q - 1.0 - cc.noise_budget(sk, ct).unwrap()
+    }
+
+    #[test]
+    fn noise_estimates_bound_the_noise() {
+        let mut cc = context_with(
+            CryptoParams::builder().mod_size(50).multiplicative_depth(2),
+            &[PkeSchemeFeature::Pke, PkeSchemeFeature::LeveledShe],
+        );
+        let keys = cc.key_gen().unwrap();
+        cc.eval_mult_key_gen(&keys.secret_key).unwrap();
+        cc.eval_rotate_key_gen(&keys.secret_key, &[1]).unwrap();
+        let pt = cc.make_packed_plaintext(&[3, -4, 5]).unwrap();
+        let fresh = cc.encrypt(&keys.public_key, &pt).unwrap();
+        let sum = cc.eval_add(&fresh, &fresh).unwrap();
+        let product = cc.eval_mult(&fresh, &fresh).unwrap();
+        let rotated = cc.eval_rotate(&product, 1).unwrap();
+        let reduced = cc.mod_reduce(&rotated).unwrap();
+        let squared = cc.eval_mult(&reduced, &reduced).unwrap();
+        for ct in [&fresh, &sum, &product, &rotated, &reduced, &squared] {
+            let estimate = cc.estimated_noise(ct).unwrap();
+            assert!(estimate + 1.0 >= measured_noise(&cc, &keys.secret_key, ct));
+        }
+        let budget = |ct| cc.noise_budget(&keys.secret_key, ct).unwrap();
+        assert!(budget(&fresh) > 0.0);
+        assert!(budget(&product) < budget(&fresh));
+        assert!(budget(&squared) < budget(&reduced));
+        assert!(budget(&squared) > 0.0);
+        assert_eq!(
+            values(cc.decrypt(&keys.secret_key, &squared).unwrap(), 3),
+            &[256, 625, 0]
+        );
+
+        let other = cc.key_gen().unwrap();
+        assert!(matches!(
+            cc.noise_budget(&other.secret_key, &fresh),
+            Err(Error::KeyTagMismatch(..))
+        ));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn secret_key_encryption_on_context_pool() {
+        let mut cc = context();
+        cc.set_rng_seed([3; SEED_LEN]);
+        let keys = cc.key_gen().unwrap();
+        let pt = cc.make_coef_packed_plaintext(&[4, 5]).unwrap();
+        let ct1 = cc.encrypt_with_secret_key(&keys.secret_key, &pt).unwrap();
+        let mut cc = context();
+        cc.set_rng_seed([3; SEED_LEN]);
+        cc.set_num_threads(3).unwrap();
+        let keys = cc.key_gen().unwrap();
+        let ct2 = cc.encrypt_with_secret_key(&keys.secret_key, &pt).unwrap();
+        assert_eq!(ct1.elements(), ct2.elements());
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn seeded_results_do_not_depend_on_the_threads() {
+        let run = |num_threads| {
+            let mut cc = context();
+            cc.set_rng_seed([9; SEED_LEN]);
+            cc.set_num_threads(num_threads).unwrap();
+            assert_eq!(cc.num_threads(), num_threads);
+            let keys = cc.key_gen().unwrap();
+            cc.eval_mult_key_gen(&keys.secret_key).unwrap();
+            cc.eval_rotate_key_gen(&keys.secret_key, &[1, -2]).unwrap();
+            let pts: Vec<_> = (0..5)
+                .map(|i| cc.make_packed_plaintext(&[i, -i, 2 * i]).unwrap())
+                .collect();
+            let cts = cc.encrypt_batch(&keys.public_key, &pts).unwrap();
+            let product = cc.eval_mult(&cts[1], &cts[2]).unwrap();
+            let rotated = cc.eval_rotate(&product, -2).unwrap();
+            let sk_ct = cc
+                .encrypt_with_secret_key(&keys.secret_key, &pts[3])
+                .unwrap();
+            let mut out: Vec<_> = cts.iter().map(|ct| ct.elements().to_vec()).collect();
+            out.extend([product, rotated, sk_ct].map(|ct| ct.elements().to_vec()));
+            out
+        };
+        let expected = run(1);
+        for num_threads in [2, 3, 8] {
+            assert_eq!(run(num_threads), expected);
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn batches_match_single_operations() {
+        let mut cc = context();
+        cc.set_num_threads(4).unwrap();
+        let keys = cc.key_gen().unwrap();
+        let pts: Vec<_> = (0..9)
+            .map(|i| cc.make_packed_plaintext(&[i, 1 - i]).unwrap())
+            .collect();
+        let cts = cc.encrypt_batch(&keys.public_key, &pts).unwrap();
+        let decrypted = cc.decrypt_batch(&keys.secret_key, &cts).unwrap();
+        for (i, (ct, pt)) in cts.iter().zip(decrypted).enumerate() {
+            let single = cc.decrypt(&keys.secret_key, ct).unwrap();
+            assert_eq!(values(pt.clone(), 2), values(single, 2));
+            assert_eq!(values(pt, 2), &[i as i64, 1 - i as i64]);
+        }
+        // every ciphertext of a batch has randomness of its own
+        assert_ne!(cts[0].elements(), cts[1].elements());
+        assert!(cc.encrypt_batch(&keys.public_key, &[]).unwrap().is_empty());
+        let other = cc.key_gen().unwrap();
+        assert!(matches!(
+            cc.decrypt_batch(&other.secret_key, &cts),
+            Err(Error::KeyTagMismatch(..))
+        ));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn encrypt_inside_parallel_iterator() {
+        use rayon::prelude::*;
+
+        let cc = context();
+        let keys = cc.key_gen().unwrap();
+        let pt = cc.make_packed_plaintext(&[1, 2, 3]).unwrap();
+        // the workers of the outer iterator run the parallel loops of the
+        // encryptions, and steal other encryptions while waiting on them
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(8)
+            .build()
+            .unwrap();
+        let cts: Vec<_> = pool.install(|| {
+            (0..64)
+                .into_par_iter()
+                .map(|_| cc.encrypt(&keys.public_key, &pt).unwrap())
+                .collect()
+        });
+        for ct in &cts {
+            assert_eq!(
+                values(cc.decrypt(&keys.secret_key, ct).unwrap(), 3),
+                &[1, 2, 3]
+            );
+        }
+    }
+}
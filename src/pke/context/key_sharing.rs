@@ -0,0 +1,555 @@
+//! Threshold sharing of secret keys: a key is split additively between n
+//! parties, or with Shamir's scheme over Z_Q so that any t of them can recover
+//! it, recreate the share of an absent party or decrypt with Lagrange
+//! coefficients computed modulo every prime of the chain.
+//!
+//! A sharing is handed over to a new committee without reconstructing the key:
+//! each party of a qualified subset shares lambda_i * s_i, and every new party
+//! adds up the sub-shares it receives. The sub-shares have uniform coefficients
+//! over Z_Q and are perfectly hiding, so unlike decryption shares they are not
+//! flooded with noise of width `MP_SD`: the noise would not hide anything more
+//! and would change the key itself to s + e, whose decryptions carry c_1 * e
+//! for a uniform c_1. The decryption shares computed from reshared keys are
+//! flooded as any others.
+
+use crate::fhe_core::{DcrtPoly, PolynomialFormat, SecretShareType};
+use crate::pke::context::check_key_tags;
+use crate::pke::keys::add_key_tags;
+use crate::pke::{Ciphertext, CryptoContext, DecryptionShare, KeyShare, PrivateKey};
+use crate::{Error, FheResult};
+
+impl CryptoContext {
+    /// Split `sk` into the shares of the parties 1 to `num_parties`, any
+    /// `threshold` of which recover it; additive sharing needs all of them
+    pub fn share_keys(
+        &self,
+        sk: &PrivateKey,
+        num_parties: usize,
+        threshold: usize,
+        share_type: SecretShareType,
+    ) -> FheResult<Vec<KeyShare>> {
+        self.require_multiparty()?;
+        self.check_private_key(sk)?;
+        check_sharing(num_parties, threshold, share_type)?;
+        let basis = self.params.basis();
+        let num_towers = basis.num_moduli();
+        let share = |index, poly| {
+            KeyShare::new(
+                index,
+                num_parties,
+                threshold,
+                share_type,
+                poly,
+                sk.key_tag(),
+            )
+        };
+        let mut rng = self.rng();
+
+        match share_type {
+            SecretShareType::Additive => {
+                let mut last = sk.poly().clone();
+                let mut shares: Vec<KeyShare> = (1..num_parties)
+                    .map(|index| {
+                        let poly = DcrtPoly::uniform(basis, num_towers, &mut rng);
+                        last.sub_assign(basis, &poly);
+                        share(index, poly)
+                    })
+                    .collect();
+                shares.push(share(num_parties, last));
+                Ok(shares)
+            }
+            SecretShareType::Shamir => {
+                // f(x) = s + r_1 x + ... + r_{t-1} x^(t-1) with uniform r_k
+                let coefficients: Vec<DcrtPoly> = (1..threshold)
+                    .map(|_| DcrtPoly::uniform(basis, num_towers, &mut rng))
+                    .collect();
+                Ok((1..=num_parties)
+                    .map(|index| {
+                        let mut poly =
+                            DcrtPoly::zero(basis, num_towers, PolynomialFormat::Evaluation);
+                        for r in coefficients.iter().rev() {
+                            poly.add_assign(basis, r);
+                            poly.mul_scalar_assign(basis, index as u64);
+                        }
+                        poly.add_assign(basis, sk.poly());
+                        share(index, poly)
+                    })
+                    .collect())
+            }
+        }
+    }
+
+    /// Add the shares held by one party of several keys, e.g. of the secret
+    /// keys of all the parties, into its share of their sum, tagged as the
+    /// joint public key of the keys
+    pub fn add_key_shares(&self, shares: &[KeyShare]) -> FheResult<KeyShare> {
+        let first = self.check_party_shares(shares)?;
+        let mut key_tag = first.key_tag().to_string();
+        for share in &shares[1..] {
+            key_tag = add_key_tags(&key_tag, share.key_tag())?;
+        }
+        Ok(self.sum_key_shares(shares, &key_tag))
+    }
+
+    /// Add the sub-shares of one key received by one party of a new committee
+    /// from [`Self::reshare_key`] into its share of the key, which keeps its
+    /// tag
+    pub fn add_reshared_key_shares(&self, sub_shares: &[KeyShare]) -> FheResult<KeyShare> {
+        let first = self.check_party_shares(sub_shares)?;
+        for share in sub_shares {
+            check_key_tags(first.key_tag(), share.key_tag())?;
+        }
+        Ok(self.sum_key_shares(sub_shares, first.key_tag()))
+    }
+
+    /// Recover the shared key from at least threshold shares of distinct
+    /// parties
+    pub fn recover_shared_key(&self, shares: &[KeyShare]) -> FheResult<PrivateKey> {
+        self.require_multiparty()?;
+        let poly = self.interpolate(shares, 0)?;
+        Ok(PrivateKey::new(poly).with_key_tag(shares[0].key_tag()))
+    }
+
+    /// The sub-shares for the parties 1 to `num_parties` of a new committee
+    /// with `threshold` and `share_type`, produced by the holder of `share` in
+    /// the qualified subset `indices` of the current committee. Each new party
+    /// then combines the sub-shares of every party of `indices` with
+    /// [`Self::add_reshared_key_shares`]; resharing to the same parties refreshes the
+    /// shares proactively. The sub-shares carry no flooding noise, see the
+    /// module documentation.
+    pub fn reshare_key(
+        &self,
+        share: &KeyShare,
+        indices: &[usize],
+        num_parties: usize,
+        threshold: usize,
+        share_type: SecretShareType,
+    ) -> FheResult<Vec<KeyShare>> {
+        let weighted = self.scaled_share(share, indices)?;
+        self.share_keys(&weighted, num_parties, threshold, share_type)
+    }
+
+    /// Recreate the share of the absent party `index` from at least threshold
+    /// Shamir shares of the others
+    pub fn recover_key_share(&self, shares: &[KeyShare], index: usize) -> FheResult<KeyShare> {
+        self.require_multiparty()?;
+        let first = self.check_key_shares(shares)?;
+        if !matches!(first.share_type(), SecretShareType::Shamir)
+            || index == 0
+            || index > first.num_parties()
+        {
+            return Err(Error::InvalidParameters(format!(
+                "cannot recreate the share of party {index} from {} shares",
+                first.share_type()
+            )));
+        }
+        Ok(KeyShare::new(
+            index,
+            first.num_parties(),
+            first.threshold(),
+            first.share_type(),
+            self.interpolate(shares, index)?,
+            first.key_tag(),
+        ))
+    }
+
+    /// The decryption share of the lead party of the subset `indices` of at
+    /// least threshold parties, from its share of the joint secret key
+    pub fn threshold_decrypt_lead(
+        &self,
+        ct: &Ciphertext,
+        share: &KeyShare,
+        indices: &[usize],
+    ) -> FheResult<DecryptionShare> {
+        let mut out = self.decryption_share(ct, &self.scaled_share(share, indices)?)?;
+        out.add_assign(self.params.basis(), &ct.elements()[0]);
+        Ok(DecryptionShare::new(
+            out,
+            ct,
+            true,
+            self.params.hash_algorithm(),
+        ))
+    }
+
+    /// The decryption share of any other party of the subset `indices`, to be
+    /// combined by [`Self::multiparty_decrypt_fusion`]
+    pub fn threshold_decrypt_main(
+        &self,
+        ct: &Ciphertext,
+        share: &KeyShare,
+        indices: &[usize],
+    ) -> FheResult<DecryptionShare> {
+        let out = self.decryption_share(ct, &self.scaled_share(share, indices)?)?;
+        Ok(DecryptionShare::new(
+            out,
+            ct,
+            false,
+            self.params.hash_algorithm(),
+        ))
+    }
+
+    /// lambda_i * s_i for the Lagrange coefficient of the party i in `indices`,
+    /// whose weighted shares add up to the key
+    fn scaled_share(&self, share: &KeyShare, indices: &[usize]) -> FheResult<PrivateKey> {
+        self.require_multiparty()?;
+        self.check_key_shares(std::slice::from_ref(share))?;
+        check_indices(share, indices)?;
+        if !indices.contains(&share.index()) {
+            return Err(Error::InvalidParameters(format!(
+                "party {} is not among the decrypting parties",
+                share.index()
+            )));
+        }
+        let mut poly = share.poly().clone();
+        if matches!(share.share_type(), SecretShareType::Shamir) {
+            let lambda = self.lagrange_coefficients(indices, share.index(), 0)?;
+            poly.mul_rns_scalar_assign(self.params.basis(), &lambda);
+        }
+        Ok(PrivateKey::new(poly).with_key_tag(share.key_tag()))
+    }
+
+    /// The value at `x` of the sharing polynomial of `shares`, the key at 0
+    fn interpolate(&self, shares: &[KeyShare], x: usize) -> FheResult<DcrtPoly> {
+        let first = self.check_key_shares(shares)?;
+        for share in shares {
+            check_key_tags(first.key_tag(), share.key_tag())?;
+        }
+        let indices: Vec<usize> = shares.iter().map(KeyShare::index).collect();
+        check_indices(first, &indices)?;
+        let basis = self.params.basis();
+        let mut out = DcrtPoly::zero(basis, basis.num_moduli(), PolynomialFormat::Evaluation);
+        for share in shares {
+            let mut poly = share.poly().clone();
+            if matches!(first.share_type(), SecretShareType::Shamir) {
+                let lambda = self.lagrange_coefficients(&indices, share.index(), x)?;
+                poly.mul_rns_scalar_assign(basis, &lambda);
+            }
+            out.add_assign(basis, &poly);
+        }
+        Ok(out)
+    }
+
+    /// prod_{j != i} (x - j) / (i - j) modulo every prime of the chain
+    fn lagrange_coefficients(&self, indices: &[usize], i: usize, x: usize) -> FheResult<Vec<u64>> {
+        let basis = self.params.basis();
+        (0..basis.num_moduli())
+            .map(|k| {
+                let q = basis.modulus(k);
+                let (mut num, mut den) = (1, 1);
+                for &j in indices.iter().filter(|&&j| j != i) {
+                    num = q.mul(num, q.reduce_i64(x as i64 - j as i64));
+                    den = q.mul(den, q.reduce_i64(i as i64 - j as i64));
+                }
+                let inv = q.inv(den).ok_or_else(|| {
+                    Error::InvalidParameters("indices are not distinct modulo q".to_string())
+                })?;
+                Ok(q.mul(num, inv))
+            })
+            .collect()
+    }
+
+    /// Check that `shares` are shares of the same party, and return the first
+    /// one
+    fn check_party_shares<'a>(&self, shares: &'a [KeyShare]) -> FheResult<&'a KeyShare> {
+        self.require_multiparty()?;
+        let first = self.check_key_shares(shares)?;
+        if shares.iter().any(|share| share.index() != first.index()) {
+            return Err(Error::InvalidKey(
+                "cannot add the shares of different parties".to_string(),
+            ));
+        }
+        Ok(first)
+    }
+
+    /// The share of the sum of the keys of `shares`, tagged `key_tag`
+    fn sum_key_shares(&self, shares: &[KeyShare], key_tag: &str) -> KeyShare {
+        let basis = self.params.basis();
+        let first = &shares[0];
+        let mut poly = first.poly().clone();
+        for share in &shares[1..] {
+            poly.add_assign(basis, share.poly());
+        }
+        KeyShare::new(
+            first.index(),
+            first.num_parties(),
+            first.threshold(),
+            first.share_type(),
+            poly,
+            key_tag,
+        )
+    }
+
+    /// Check that the shares are well formed and of the same sharing, and
+    /// return the first one
+    fn check_key_shares<'a>(&self, shares: &'a [KeyShare]) -> FheResult<&'a KeyShare> {
+        let first = shares
+            .first()
+            .ok_or_else(|| Error::InvalidKey("no key share".to_string()))?;
+        for share in shares {
+            if share.num_parties() != first.num_parties()
+                || share.threshold() != first.threshold()
+                || u8::from(share.share_type()) != u8::from(first.share_type())
+                || share.index() == 0
+                || share.index() > share.num_parties()
+                || !self.is_key_poly(share.poly())
+            {
+                return Err(Error::InvalidKey(
+                    "key shares of different sharings or parameters".to_string(),
+                ));
+            }
+        }
+        Ok(first)
+    }
+}
+
+fn check_sharing(
+    num_parties: usize,
+    threshold: usize,
+    share_type: SecretShareType,
+) -> FheResult<()> {
+    let valid = match share_type {
+        SecretShareType::Additive => num_parties >= 2 && threshold == num_parties,
+        SecretShareType::Shamir => threshold >= 1 && threshold <= num_parties,
+    };
+    if !valid {
+        return Err(Error::InvalidParameters(format!(
+            "invalid {share_type} sharing with threshold {threshold} of {num_parties}"
+        )));
+    }
+    Ok(())
+}
+
+/// Check that `indices` names at least threshold distinct parties of the
+/// sharing of `share`
+fn check_indices(share: &KeyShare, indices: &[usize]) -> FheResult<()> {
+    let mut sorted = indices.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    if sorted.len() != indices.len()
+        || sorted.len() < share.threshold()
+        || sorted.iter().any(|&i| i == 0 || i > share.num_parties())
+    {
+        return Err(Error::InvalidParameters(format!(
+            "expected at least {} distinct parties among 1 to {}",
+            share.threshold(),
+            share.num_parties()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fhe_core::{MultipartyMode, PkeSchemeFeature};
+    use crate::pke::context::tests::{context_with, values};
+    use crate::pke::CryptoParams;
+
+    fn context() -> CryptoContext {
+        context_with(
+            CryptoParams::builder()
+                .mod_size(50)
+                .multiparty_mode(MultipartyMode::FixedNoiseMultiparty),
+            &[PkeSchemeFeature::Pke, PkeSchemeFeature::MultiParty],
+        )
+    }
+
+    /// The shares of the new committee of `num_parties` from the resharing by
+    /// the parties `indices` of `shares`
+    fn reshare(
+        cc: &CryptoContext,
+        shares: &[KeyShare],
+        indices: &[usize],
+        num_parties: usize,
+        threshold: usize,
+        share_type: SecretShareType,
+    ) -> Vec<KeyShare> {
+        let sub_shares: Vec<Vec<KeyShare>> = indices
+            .iter()
+            .map(|&i| {
+                cc.reshare_key(&shares[i - 1], indices, num_parties, threshold, share_type)
+                    .unwrap()
+            })
+            .collect();
+        (0..num_parties)
+            .map(|j| {
+                let received: Vec<KeyShare> = sub_shares.iter().map(|s| s[j].clone()).collect();
+                cc.add_reshared_key_shares(&received).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn shares_recover_the_key() {
+        let cc = context();
+        let keys = cc.key_gen().unwrap();
+        let sk = &keys.secret_key;
+        let shares = cc.share_keys(sk, 3, 3, SecretShareType::Additive).unwrap();
+        assert_eq!(cc.recover_shared_key(&shares).unwrap().poly(), sk.poly());
+
+        let shares = cc.share_keys(sk, 5, 3, SecretShareType::Shamir).unwrap();
+        let recovered = cc.recover_shared_key(&shares[2..]).unwrap();
+        assert_eq!(recovered.poly(), sk.poly());
+        assert_eq!(recovered.key_tag(), sk.key_tag());
+        let share = cc.recover_key_share(&shares[1..4], 1).unwrap();
+        assert_eq!(share.poly(), shares[0].poly());
+        assert!(matches!(
+            cc.recover_shared_key(&shares[..2]),
+            Err(Error::InvalidParameters(_))
+        ));
+    }
+
+    #[test]
+    fn reshared_keys_recover_the_key() {
+        let cc = context();
+        let keys = cc.key_gen().unwrap();
+        let sk = &keys.secret_key;
+        let shares = cc.share_keys(sk, 5, 3, SecretShareType::Shamir).unwrap();
+        let new = reshare(&cc, &shares, &[1, 3, 5], 4, 2, SecretShareType::Shamir);
+        let recovered = cc.recover_shared_key(&new[2..]).unwrap();
+        assert_eq!(recovered.poly(), sk.poly());
+        assert_eq!(recovered.key_tag(), sk.key_tag());
+
+        // an additive committee holding a Shamir sharing
+        let new = reshare(&cc, &new, &[2, 3], 3, 3, SecretShareType::Additive);
+        assert_eq!(cc.recover_shared_key(&new).unwrap().poly(), sk.poly());
+
+        let pt = cc.make_packed_plaintext(&[4, -5]).unwrap();
+        let ct = cc.encrypt(&keys.public_key, &pt).unwrap();
+        let indices = [1, 2, 3];
+        let shares = [
+            cc.threshold_decrypt_lead(&ct, &new[0], &indices).unwrap(),
+            cc.threshold_decrypt_main(&ct, &new[1], &indices).unwrap(),
+            cc.threshold_decrypt_main(&ct, &new[2], &indices).unwrap(),
+        ];
+        let pt = cc.multiparty_decrypt_fusion(&shares).unwrap();
+        assert_eq!(values(pt, 2), &[4, -5]);
+    }
+
+    #[test]
+    fn resharing_needs_a_qualified_subset() {
+        let cc = context();
+        let keys = cc.key_gen().unwrap();
+        let shares = cc
+            .share_keys(&keys.secret_key, 5, 3, SecretShareType::Shamir)
+            .unwrap();
+        for indices in [&[1, 2][..], &[2, 3, 4], &[1, 1, 2]] {
+            assert!(matches!(
+                cc.reshare_key(&shares[0], indices, 4, 2, SecretShareType::Shamir),
+                Err(Error::InvalidParameters(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn shares_of_different_keys_do_not_mix() {
+        let cc = context();
+        let sk1 = cc.key_gen().unwrap().secret_key;
+        let sk2 = cc.key_gen().unwrap().secret_key;
+        let mut shares = cc.share_keys(&sk1, 3, 2, SecretShareType::Shamir).unwrap();
+        shares[1] = cc.share_keys(&sk2, 3, 2, SecretShareType::Shamir).unwrap()[1].clone();
+        assert!(matches!(
+            cc.recover_shared_key(&shares[..2]),
+            Err(Error::KeyTagMismatch(..))
+        ));
+    }
+
+    #[test]
+    fn sub_shares_of_different_keys_do_not_mix() {
+        let cc = context();
+        let sk1 = cc.key_gen().unwrap().secret_key;
+        let sk2 = cc.key_gen().unwrap().secret_key;
+        let shares1 = cc.share_keys(&sk1, 3, 2, SecretShareType::Shamir).unwrap();
+        let shares2 = cc.share_keys(&sk2, 3, 2, SecretShareType::Shamir).unwrap();
+        let indices = [1, 2];
+        let reshare = |share| {
+            cc.reshare_key(share, &indices, 3, 2, SecretShareType::Shamir)
+                .unwrap()
+        };
+        let (sub1, sub2) = (reshare(&shares1[0]), reshare(&shares2[1]));
+        assert!(matches!(
+            cc.add_reshared_key_shares(&[sub1[0].clone(), sub2[0].clone()]),
+            Err(Error::KeyTagMismatch(..))
+        ));
+        // the shares of distinct keys add up to a share of the joint key, not
+        // of either key
+        let joint = cc
+            .add_key_shares(&[sub1[0].clone(), sub2[0].clone()])
+            .unwrap();
+        assert_ne!(joint.key_tag(), sk1.key_tag());
+        assert_ne!(joint.key_tag(), sk2.key_tag());
+        let twice = cc
+            .add_key_shares(&[sub1[0].clone(), sub1[0].clone()])
+            .unwrap();
+        assert_ne!(twice.key_tag(), sk1.key_tag());
+    }
+
+    #[test]
+    fn any_qualified_subset_decrypts() {
+        let cc = context();
+        let keys = cc.key_gen().unwrap();
+        let pt = cc.make_packed_plaintext(&[6, -7]).unwrap();
+        let ct = cc.encrypt(&keys.public_key, &pt).unwrap();
+        let decrypt = |shares: &[KeyShare], indices: &[usize]| {
+            let mut out = vec![];
+            for (k, &i) in indices.iter().enumerate() {
+                let share = &shares[i - 1];
+                out.push(if k == 0 {
+                    cc.threshold_decrypt_lead(&ct, share, indices).unwrap()
+                } else {
+                    cc.threshold_decrypt_main(&ct, share, indices).unwrap()
+                });
+            }
+            values(cc.multiparty_decrypt_fusion(&out).unwrap(), 2)
+        };
+
+        let shares = cc
+            .share_keys(&keys.secret_key, 5, 3, SecretShareType::Shamir)
+            .unwrap();
+        for indices in [&[1, 2, 3][..], &[5, 2, 4], &[1, 2, 3, 4, 5]] {
+            assert_eq!(decrypt(&shares, indices), &[6, -7], "{indices:?}");
+        }
+        let shares = cc
+            .share_keys(&keys.secret_key, 3, 3, SecretShareType::Additive)
+            .unwrap();
+        assert_eq!(decrypt(&shares, &[1, 2, 3]), &[6, -7]);
+        let mains: Vec<DecryptionShare> = shares
+            .iter()
+            .map(|share| cc.threshold_decrypt_main(&ct, share, &[1, 2, 3]).unwrap())
+            .collect();
+        assert!(matches!(
+            cc.multiparty_decrypt_fusion(&mains),
+            Err(Error::InvalidCiphertext(_))
+        ));
+        assert!(matches!(
+            cc.threshold_decrypt_main(&ct, &shares[0], &[2, 3]),
+            Err(Error::InvalidParameters(_))
+        ));
+    }
+
+    #[test]
+    fn invalid_sharings_are_rejected() {
+        let cc = context();
+        let sk = cc.key_gen().unwrap().secret_key;
+        for (num_parties, threshold, share_type) in [
+            (3, 2, SecretShareType::Additive),
+            (1, 1, SecretShareType::Additive),
+            (3, 4, SecretShareType::Shamir),
+            (3, 0, SecretShareType::Shamir),
+        ] {
+            assert!(matches!(
+                cc.share_keys(&sk, num_parties, threshold, share_type),
+                Err(Error::InvalidParameters(_))
+            ));
+        }
+        let shares = cc.share_keys(&sk, 3, 3, SecretShareType::Additive).unwrap();
+        assert!(matches!(
+            cc.recover_key_share(&shares[1..], 1),
+            Err(Error::InvalidParameters(_))
+        ));
+        assert!(matches!(
+            cc.add_key_shares(&shares[..2]),
+            Err(Error::InvalidKey(_))
+        ));
+    }
+}
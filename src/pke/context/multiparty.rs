@@ -0,0 +1,709 @@
+//! Threshold key generation and decryption: every party holds an additive
+//! share s_j of the joint secret s = s_1 + ... + s_n, the evaluation keys for s
+//! are built party by party from shares over common uniform polynomials and
+//! decryption combines one partial decryption per party
+
+use crate::fhe_core::noise_flooding::MP_SD;
+use crate::fhe_core::{
+    DcrtPoly, DecryptionNoiseMode, MultipartyMode, PkeSchemeFeature, PrngFromHash, UniformPolys,
+    SEED_LEN,
+};
+use crate::pke::context::{check_key_tags, check_linear};
+use crate::pke::keys::add_key_tags;
+use crate::pke::{
+    scheme, Ciphertext, CommonRandomPoly, CryptoContext, DecryptionShare, EvalKey, KeyPair,
+    Plaintext, PrivateKey, PublicKey,
+};
+use crate::{Error, FheResult};
+use rand_core::RngCore;
+use std::collections::BTreeMap;
+
+impl CryptoContext {
+    /// Generate the key pair of the next party: its public key is the joint
+    /// public key `pk` of the previous parties extended with the new share,
+    /// over the same uniform polynomial, and its secret key is tagged as the
+    /// public key of the share alone
+    pub fn multiparty_key_gen(&self, pk: &PublicKey) -> FheResult<KeyPair> {
+        self.require_multiparty()?;
+        self.check_public_key(pk)?;
+        let pair = scheme::key_gen(&self.params, Some(pk.uniform()), &mut self.rng());
+        let public_key = self.add_pub_keys(pk, &pair.public_key)?;
+        Ok(KeyPair {
+            secret_key: pair.secret_key.clone(),
+            public_key,
+        })
+    }
+
+    /// The common reference string of `domain` for the public seed `seed`: a
+    /// uniform polynomial expanded from the first bytes of the stream of the
+    /// seed in the domain, under the hash algorithm of the parameters. Every
+    /// party derives the same polynomial, and distinct domains give
+    /// independent ones.
+    pub fn common_random_poly_from_seed(
+        &self,
+        seed: &[u8; SEED_LEN],
+        domain: &[u8],
+    ) -> FheResult<CommonRandomPoly> {
+        self.require_multiparty()?;
+        let hash_algorithm = self.params.hash_algorithm();
+        let mut derived = [0; SEED_LEN];
+        PrngFromHash::new(hash_algorithm, seed, domain).fill_bytes(&mut derived);
+        Ok(CommonRandomPoly::new(UniformPolys::from_seed(
+            self.params.basis(),
+            1,
+            hash_algorithm,
+            derived,
+        )))
+    }
+
+    /// Generate the key pair of a party over the common reference string
+    /// `crs`, the joint public key being the sum of those of all the parties
+    /// by [`Self::multi_add_pub_keys`]
+    pub fn multiparty_key_gen_with_crs(&self, crs: &CommonRandomPoly) -> FheResult<KeyPair> {
+        self.require_multiparty()?;
+        self.check_random_element(crs)?;
+        Ok(scheme::key_gen(
+            &self.params,
+            Some(crs.uniform()),
+            &mut self.rng(),
+        ))
+    }
+
+    /// The joint public key of two public keys over the same uniform
+    /// polynomial, each produced by [`Self::multiparty_key_gen`] from a common
+    /// one
+    pub fn multi_add_pub_keys(&self, pk1: &PublicKey, pk2: &PublicKey) -> FheResult<PublicKey> {
+        self.require_multiparty()?;
+        self.check_public_key(pk1)?;
+        self.check_public_key(pk2)?;
+        self.add_pub_keys(pk1, pk2)
+    }
+
+    /// The share of a party of the key switching key from `sk_orig` to
+    /// `sk_new`, over the uniform polynomials of `ek`
+    pub fn multi_key_switch_gen(
+        &self,
+        sk_orig: &PrivateKey,
+        sk_new: &PrivateKey,
+        ek: &EvalKey,
+    ) -> FheResult<EvalKey> {
+        self.require_multiparty()?;
+        self.check_private_key(sk_orig)?;
+        self.check_private_key(sk_new)?;
+        self.check_eval_key(ek)?;
+        Ok(scheme::key_switch_gen(
+            &self.params,
+            sk_orig.poly(),
+            sk_new.poly(),
+            Some(ek.uniform()),
+            &mut self.rng(),
+        )
+        .with_key_tags(sk_orig.key_tag(), sk_new.key_tag()))
+    }
+
+    /// Add two key switching key shares over the same uniform polynomials
+    pub fn multi_add_eval_keys(&self, ek1: &EvalKey, ek2: &EvalKey) -> FheResult<EvalKey> {
+        self.require_multiparty()?;
+        self.add_eval_keys(ek1, ek2, false)
+    }
+
+    /// The share of a party of the relinearization key: given the joint key
+    /// switching key (b_i, a_i) from s to s, the pair (b_i * s_j + t * e,
+    /// a_i * s_j + t * e') whose sum over the parties switches s^2 to s
+    pub fn multi_mult_eval_key(&self, sk: &PrivateKey, ek: &EvalKey) -> FheResult<EvalKey> {
+        self.require_multiparty()?;
+        self.check_private_key(sk)?;
+        self.check_eval_key(ek)?;
+        let params = &self.params;
+        let basis = params.basis();
+        let num_towers = basis.num_moduli();
+        let mut rng = self.rng();
+        let mut share = |polys: &[DcrtPoly]| {
+            polys
+                .iter()
+                .map(|p| {
+                    let mut out = scheme::noise(params, num_towers, params.std_dev(), &mut rng);
+                    out.add_assign(basis, &p.mul(basis, sk.poly()));
+                    out
+                })
+                .collect()
+        };
+        let (b, a) = (share(ek.b()), UniformPolys::from_polys(share(ek.a())));
+        Ok(EvalKey::new(b, a, params.hash_algorithm()).with_key_tags(sk.key_tag(), sk.key_tag()))
+    }
+
+    /// Add two relinearization key shares
+    pub fn multi_add_eval_mult_keys(&self, ek1: &EvalKey, ek2: &EvalKey) -> FheResult<EvalKey> {
+        self.require_multiparty()?;
+        self.add_eval_keys(ek1, ek2, true)
+    }
+
+    /// The share of a party of the automorphism keys for `indices`, over the
+    /// uniform polynomials of the keys `keys` of the lead party
+    pub fn multi_eval_automorphism_key_gen(
+        &self,
+        sk: &PrivateKey,
+        keys: &BTreeMap<usize, EvalKey>,
+        indices: &[usize],
+    ) -> FheResult<BTreeMap<usize, EvalKey>> {
+        self.require_multiparty()?;
+        self.check_private_key(sk)?;
+        let ring_dim = self.params.ring_dim();
+        let mut rng = self.rng();
+        indices
+            .iter()
+            .map(|&k| {
+                if k % 2 == 0 || k >= 2 * ring_dim {
+                    return Err(Error::InvalidParameters(format!(
+                        "invalid automorphism index {k}"
+                    )));
+                }
+                let ek = keys
+                    .get(&k)
+                    .ok_or_else(|| Error::MissingEvalKey(format!("automorphism {k}")))?;
+                self.check_eval_key(ek)?;
+                let s_k = scheme::automorphism_poly(&self.params, sk.poly(), k);
+                let share = scheme::key_switch_gen(
+                    &self.params,
+                    &s_k,
+                    sk.poly(),
+                    Some(ek.uniform()),
+                    &mut rng,
+                )
+                .with_key_tags(sk.key_tag(), sk.key_tag());
+                Ok((k, share))
+            })
+            .collect()
+    }
+
+    /// The share of a party of the rotation keys for `indices`
+    pub fn multi_eval_at_index_key_gen(
+        &self,
+        sk: &PrivateKey,
+        keys: &BTreeMap<usize, EvalKey>,
+        indices: &[i32],
+    ) -> FheResult<BTreeMap<usize, EvalKey>> {
+        self.multi_eval_automorphism_key_gen(sk, keys, &self.rotation_indices(indices))
+    }
+
+    /// The share of a party of the keys used by [`Self::eval_sum`]
+    pub fn multi_eval_sum_key_gen(
+        &self,
+        sk: &PrivateKey,
+        keys: &BTreeMap<usize, EvalKey>,
+    ) -> FheResult<BTreeMap<usize, EvalKey>> {
+        self.multi_eval_automorphism_key_gen(sk, keys, &self.sum_indices())
+    }
+
+    /// Add two sets of automorphism key shares index by index
+    pub fn multi_add_eval_automorphism_keys(
+        &self,
+        keys1: &BTreeMap<usize, EvalKey>,
+        keys2: &BTreeMap<usize, EvalKey>,
+    ) -> FheResult<BTreeMap<usize, EvalKey>> {
+        self.require_multiparty()?;
+        keys1
+            .iter()
+            .map(|(&k, ek1)| {
+                let ek2 = keys2
+                    .get(&k)
+                    .ok_or_else(|| Error::MissingEvalKey(format!("automorphism {k}")))?;
+                Ok((k, self.add_eval_keys(ek1, ek2, false)?))
+            })
+            .collect()
+    }
+
+    /// Add two sets of shares of the keys used by [`Self::eval_sum`]
+    pub fn multi_add_eval_sum_keys(
+        &self,
+        keys1: &BTreeMap<usize, EvalKey>,
+        keys2: &BTreeMap<usize, EvalKey>,
+    ) -> FheResult<BTreeMap<usize, EvalKey>> {
+        self.multi_add_eval_automorphism_keys(keys1, keys2)
+    }
+
+    /// The decryption share of the lead party, c_0 + c_1 * s_1 + t * e
+    pub fn multiparty_decrypt_lead(
+        &self,
+        ct: &Ciphertext,
+        sk: &PrivateKey,
+    ) -> FheResult<DecryptionShare> {
+        let mut share = self.decryption_share(ct, sk)?;
+        share.add_assign(self.params.basis(), &ct.elements()[0]);
+        Ok(DecryptionShare::new(
+            share,
+            ct,
+            true,
+            self.params.hash_algorithm(),
+        ))
+    }
+
+    /// The decryption share of any other party, c_1 * s_j + t * e
+    pub fn multiparty_decrypt_main(
+        &self,
+        ct: &Ciphertext,
+        sk: &PrivateKey,
+    ) -> FheResult<DecryptionShare> {
+        let share = self.decryption_share(ct, sk)?;
+        Ok(DecryptionShare::new(
+            share,
+            ct,
+            false,
+            self.params.hash_algorithm(),
+        ))
+    }
+
+    /// Combine the shares of the lead party and of all the others into the
+    /// plaintext, all of them shares of the same ciphertext
+    pub fn multiparty_decrypt_fusion(&self, shares: &[DecryptionShare]) -> FheResult<Plaintext> {
+        self.require_multiparty()?;
+        let (first, rest) = shares.split_first().ok_or_else(|| {
+            Error::InvalidCiphertext("no decryption share to combine".to_string())
+        })?;
+        for share in rest {
+            check_key_tags(first.key_tag(), share.key_tag())?;
+            if share.ciphertext_digest() != first.ciphertext_digest()
+                || share.num_towers() != first.num_towers()
+                || u8::from(share.encoding()) != u8::from(first.encoding())
+            {
+                return Err(Error::InvalidCiphertext(
+                    "decryption shares of different ciphertexts".to_string(),
+                ));
+            }
+        }
+        let leads = shares.iter().filter(|share| share.is_lead()).count();
+        if leads != 1 {
+            return Err(Error::InvalidCiphertext(format!(
+                "expected a single lead decryption share, got {leads}"
+            )));
+        }
+        if !(self.params.min_towers()..=self.params.moduli().len()).contains(&first.num_towers())
+            || !shares
+                .iter()
+                .all(|share| self.is_well_formed(share.share()))
+        {
+            return Err(Error::InvalidCiphertext(
+                "decryption share for other parameters".to_string(),
+            ));
+        }
+
+        let mut phase = first.share().clone();
+        for share in rest {
+            phase.add_assign(self.params.basis(), share.share());
+        }
+        Ok(self.decode(&scheme::decode_phase(&self.params, phase), first.encoding()))
+    }
+
+    /// c_1 * s_j + t * e, the noise being flooded to a width of `MP_SD` in
+    /// [`DecryptionNoiseMode::NoiseFloodingDecrypt`] so the share reveals nothing
+    /// about s_j
+    pub(super) fn decryption_share(&self, ct: &Ciphertext, sk: &PrivateKey) -> FheResult<DcrtPoly> {
+        self.require_multiparty()?;
+        self.check_private_key(sk)?;
+        self.check_ciphertext(ct)?;
+        check_linear(ct)?;
+        let params = &self.params;
+        let basis = params.basis();
+        let std_dev = match params.decryption_noise_mode() {
+            DecryptionNoiseMode::NoiseFloodingDecrypt => MP_SD as f64,
+            DecryptionNoiseMode::FixedNoiseDecrypt => params.std_dev(),
+        };
+        let mut s = sk.poly().clone();
+        s.truncate(ct.num_towers());
+        let mut share = scheme::noise(params, ct.num_towers(), std_dev, &mut self.rng());
+        share.add_assign(basis, &ct.elements()[1].mul(basis, &s));
+        Ok(share)
+    }
+
+    /// Add the b parts of two keys, and the a parts too if `add_a` is set
+    /// instead of keeping the common ones of `ek1`
+    fn add_eval_keys(&self, ek1: &EvalKey, ek2: &EvalKey, add_a: bool) -> FheResult<EvalKey> {
+        self.check_eval_key(ek1)?;
+        self.check_eval_key(ek2)?;
+        let basis = self.params.basis();
+        let add = |x: &[DcrtPoly], y: &[DcrtPoly]| {
+            x.iter()
+                .zip(y)
+                .map(|(x, y)| {
+                    let mut out = x.clone();
+                    out.add_assign(basis, y);
+                    out
+                })
+                .collect()
+        };
+        let a = if add_a {
+            UniformPolys::from_polys(add(ek1.a(), ek2.a()))
+        } else {
+            ek1.uniform().clone()
+        };
+        let source_key_tag = add_key_tags(ek1.source_key_tag(), ek2.source_key_tag())?;
+        let target_key_tag = add_key_tags(ek1.target_key_tag(), ek2.target_key_tag())?;
+        Ok(
+            EvalKey::new(add(ek1.b(), ek2.b()), a, self.params.hash_algorithm())
+                .with_key_tags(&source_key_tag, &target_key_tag),
+        )
+    }
+
+    /// The sum of two public keys over the same uniform polynomial, tagged
+    /// with the sum of their tags
+    fn add_pub_keys(&self, pk1: &PublicKey, pk2: &PublicKey) -> FheResult<PublicKey> {
+        let key_tag = add_key_tags(pk1.key_tag(), pk2.key_tag())?;
+        let mut b = pk1.b().clone();
+        b.add_assign(self.params.basis(), pk2.b());
+        Ok(PublicKey::new(b, pk1.uniform().clone(), self.params.hash_algorithm()).joint(&key_tag))
+    }
+
+    pub(super) fn check_random_element(&self, a: &CommonRandomPoly) -> FheResult<()> {
+        if !self.is_uniform(a.uniform(), 1) {
+            return Err(Error::InvalidParameters(
+                "common random polynomial for other parameters".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub(super) fn require_multiparty(&self) -> FheResult<()> {
+        self.require(PkeSchemeFeature::MultiParty)?;
+        if matches!(
+            self.params.multiparty_mode(),
+            MultipartyMode::InvalidMultipartyMode
+        ) {
+            return Err(Error::InvalidParameters(
+                "no multiparty mode selected".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pke::context::tests::{context_with, values};
+    use crate::pke::{CryptoParams, HashAlgorithm};
+
+    fn context() -> CryptoContext {
+        context_in(MultipartyMode::FixedNoiseMultiparty)
+    }
+
+    fn context_in(mode: MultipartyMode) -> CryptoContext {
+        context_with(
+            CryptoParams::builder()
+                .mod_size(50)
+                .multiplicative_depth(1)
+                .multiparty_mode(mode),
+            &[
+                PkeSchemeFeature::Pke,
+                PkeSchemeFeature::LeveledShe,
+                PkeSchemeFeature::KeySwitch,
+                PkeSchemeFeature::MultiParty,
+            ],
+        )
+    }
+
+    /// Decrypt `ct` with the shares of all the parties, the lead party first
+    fn joint_decrypt(cc: &CryptoContext, sks: &[&PrivateKey], ct: &Ciphertext) -> Plaintext {
+        let mut shares = vec![cc.multiparty_decrypt_lead(ct, sks[0]).unwrap()];
+        for sk in &sks[1..] {
+            shares.push(cc.multiparty_decrypt_main(ct, sk).unwrap());
+        }
+        cc.multiparty_decrypt_fusion(&shares).unwrap()
+    }
+
+    /// The joint relinearization key of `sks`, the lead party first
+    fn joint_mult_key(cc: &CryptoContext, sks: &[&PrivateKey]) -> EvalKey {
+        let lead = cc.key_switch_gen(sks[0], sks[0]).unwrap();
+        let ek = sks[1..].iter().fold(lead.clone(), |ek, sk| {
+            let share = cc.multi_key_switch_gen(sk, sk, &lead).unwrap();
+            cc.multi_add_eval_keys(&ek, &share).unwrap()
+        });
+        // the shares are added in reverse order, which must not change the tag
+        sks.iter()
+            .rev()
+            .map(|sk| cc.multi_mult_eval_key(sk, &ek).unwrap())
+            .reduce(|m1, m2| cc.multi_add_eval_mult_keys(&m1, &m2).unwrap())
+            .unwrap()
+    }
+
+    #[test]
+    fn joint_keys_share_a_tag() {
+        let mut cc = context();
+        let k1 = cc.key_gen().unwrap();
+        let k2 = cc.multiparty_key_gen(&k1.public_key).unwrap();
+        let k3 = cc.multiparty_key_gen(&k2.public_key).unwrap();
+        let sks = [&k1.secret_key, &k2.secret_key, &k3.secret_key];
+        let ek = joint_mult_key(&cc, &sks);
+        assert_eq!(ek.source_key_tag(), k3.public_key.key_tag());
+        assert_eq!(ek.target_key_tag(), k3.public_key.key_tag());
+        // the tag of a key identifies its own polynomials, not its source
+        assert_ne!(ek.key_tag(), ek.source_key_tag());
+        assert!(!ek.key_tag().is_empty());
+        cc.insert_eval_mult_key(ek).unwrap();
+
+        let pt = cc.make_packed_plaintext(&[3, -4]).unwrap();
+        let ct = cc.encrypt(&k3.public_key, &pt).unwrap();
+        let ct = cc.eval_mult(&ct, &ct).unwrap();
+        let shares = [
+            cc.multiparty_decrypt_lead(&ct, sks[0]).unwrap(),
+            cc.multiparty_decrypt_main(&ct, sks[1]).unwrap(),
+            cc.multiparty_decrypt_main(&ct, sks[2]).unwrap(),
+        ];
+        let pt = cc.multiparty_decrypt_fusion(&shares).unwrap();
+        assert_eq!(values(pt, 2), &[9, 16]);
+    }
+
+    #[test]
+    fn joint_keys_with_a_common_reference_string() {
+        let cc = context();
+        let crs = cc
+            .common_random_poly_from_seed(&[1; SEED_LEN], b"pk")
+            .unwrap();
+        let k1 = cc.multiparty_key_gen_with_crs(&crs).unwrap();
+        let k2 = cc.multiparty_key_gen_with_crs(&crs).unwrap();
+        let pk12 = cc
+            .multi_add_pub_keys(&k1.public_key, &k2.public_key)
+            .unwrap();
+        let pk21 = cc
+            .multi_add_pub_keys(&k2.public_key, &k1.public_key)
+            .unwrap();
+        assert_eq!(pk12.key_tag(), pk21.key_tag());
+        let ek = joint_mult_key(&cc, &[&k1.secret_key, &k2.secret_key]);
+        assert_eq!(ek.source_key_tag(), pk12.key_tag());
+    }
+
+    #[test]
+    fn common_reference_strings_from_seeds() {
+        let cc = context();
+        let crs = |cc: &CryptoContext, seed, domain: &[u8]| {
+            cc.common_random_poly_from_seed(&[seed; SEED_LEN], domain)
+                .unwrap()
+                .poly()
+                .clone()
+        };
+        let a = crs(&cc, 1, b"pk");
+        assert_eq!(a, crs(&cc, 1, b"pk"));
+        assert_eq!(a, crs(&context(), 1, b"pk"));
+        assert_ne!(a, crs(&cc, 1, b"rk"));
+        assert_ne!(a, crs(&cc, 2, b"pk"));
+        let shake = context_with(
+            CryptoParams::builder()
+                .mod_size(50)
+                .multiplicative_depth(1)
+                .multiparty_mode(MultipartyMode::FixedNoiseMultiparty)
+                .hash_algorithm(HashAlgorithm::Shake256),
+            &[PkeSchemeFeature::Pke, PkeSchemeFeature::MultiParty],
+        );
+        assert_ne!(a, crs(&shake, 1, b"pk"));
+        assert_eq!(crs(&shake, 1, b"pk"), crs(&shake, 1, b"pk"));
+    }
+
+    #[test]
+    fn partial_joint_keys_are_rejected() {
+        let mut cc = context();
+        let k1 = cc.key_gen().unwrap();
+        let k2 = cc.multiparty_key_gen(&k1.public_key).unwrap();
+        let k3 = cc.multiparty_key_gen(&k2.public_key).unwrap();
+        // a relinearization key missing the last party
+        let ek = joint_mult_key(&cc, &[&k1.secret_key, &k2.secret_key]);
+        assert_eq!(ek.source_key_tag(), k2.public_key.key_tag());
+        cc.insert_eval_mult_key(ek).unwrap();
+        let pt = cc.make_packed_plaintext(&[1]).unwrap();
+        let ct = cc.encrypt(&k3.public_key, &pt).unwrap();
+        assert!(matches!(
+            cc.eval_mult(&ct, &ct),
+            Err(Error::KeyTagMismatch(..))
+        ));
+    }
+
+    #[test]
+    fn untagged_objects_are_rejected() {
+        assert!(matches!(
+            check_key_tags("", ""),
+            Err(Error::KeyTagMismatch(..))
+        ));
+        assert!(matches!(
+            add_key_tags("", "00"),
+            Err(Error::KeyTagMismatch(..))
+        ));
+        assert!(check_key_tags("00", "00").is_ok());
+    }
+
+    #[test]
+    fn tags_add_in_any_order() {
+        let add = |tags: &[&str]| {
+            tags[1..]
+                .iter()
+                .try_fold(tags[0].to_string(), |sum, tag| add_key_tags(&sum, tag))
+                .unwrap()
+        };
+        let (a, b) = ("ff01", "0203");
+        assert_eq!(add(&[a, a, b]), add(&[a, b, a]));
+        assert_eq!(add(&[a, a, b]), add(&[b, a, a]));
+        assert_eq!(add(&[a, a]), "fe03");
+        assert_ne!(add(&[a, b]), add(&[a, a, b]));
+        assert!(matches!(
+            add_key_tags("00", "0000"),
+            Err(Error::KeyTagMismatch(..))
+        ));
+    }
+
+    #[test]
+    fn joint_keys_in_every_multiparty_mode() {
+        for mode in [
+            MultipartyMode::FixedNoiseMultiparty,
+            MultipartyMode::NoiseFloodingMultiparty,
+        ] {
+            let mut cc = context_in(mode);
+            let k1 = cc.key_gen().unwrap();
+            let k2 = cc.multiparty_key_gen(&k1.public_key).unwrap();
+            let sks = [&k1.secret_key, &k2.secret_key];
+            let ek = joint_mult_key(&cc, &sks);
+            cc.insert_eval_mult_key(ek).unwrap();
+            let pt = cc.make_packed_plaintext(&[2, -3, 4]).unwrap();
+            let ct = cc.encrypt(&k2.public_key, &pt).unwrap();
+            // the key of a party alone is not that of the joint key
+            assert!(matches!(
+                cc.decrypt(sks[1], &ct),
+                Err(Error::KeyTagMismatch(..))
+            ));
+            let ct = cc.eval_mult(&ct, &ct).unwrap();
+            assert_eq!(
+                values(joint_decrypt(&cc, &sks, &ct), 3),
+                &[4, 9, 16],
+                "{mode}"
+            );
+        }
+    }
+
+    #[test]
+    fn joint_rotation_keys() {
+        let mut cc = context();
+        let k1 = cc.key_gen().unwrap();
+        let k2 = cc.multiparty_key_gen(&k1.public_key).unwrap();
+        cc.eval_rotate_key_gen(&k1.secret_key, &[1, -2]).unwrap();
+        let lead = cc.get_eval_automorphism_keys().clone();
+        let share = cc
+            .multi_eval_at_index_key_gen(&k2.secret_key, &lead, &[1, -2])
+            .unwrap();
+        let joint = cc.multi_add_eval_automorphism_keys(&lead, &share).unwrap();
+        cc.insert_eval_automorphism_keys(joint).unwrap();
+
+        let pt = cc.make_packed_plaintext(&[1, 2, 3, 4]).unwrap();
+        let ct = cc.encrypt(&k2.public_key, &pt).unwrap();
+        let sks = [&k1.secret_key, &k2.secret_key];
+        let rotated = cc.eval_rotate(&ct, 1).unwrap();
+        assert_eq!(values(joint_decrypt(&cc, &sks, &rotated), 3), &[2, 3, 4]);
+        let rotated = cc.eval_rotate(&rotated, -2).unwrap();
+        assert_eq!(values(joint_decrypt(&cc, &sks, &rotated), 3), &[0, 1, 2]);
+        assert!(matches!(
+            cc.multi_eval_automorphism_key_gen(&k2.secret_key, &lead, &[4]),
+            Err(Error::InvalidParameters(_))
+        ));
+    }
+
+    #[test]
+    fn multiparty_needs_a_mode_and_the_feature() {
+        let cc = context_in(MultipartyMode::InvalidMultipartyMode);
+        let keys = cc.key_gen().unwrap();
+        assert!(matches!(
+            cc.multiparty_key_gen(&keys.public_key),
+            Err(Error::InvalidParameters(_))
+        ));
+        let cc = context_with(
+            CryptoParams::builder()
+                .mod_size(50)
+                .multiparty_mode(MultipartyMode::FixedNoiseMultiparty),
+            &[PkeSchemeFeature::Pke],
+        );
+        let keys = cc.key_gen().unwrap();
+        assert!(matches!(
+            cc.multiparty_key_gen(&keys.public_key),
+            Err(Error::FeatureNotEnabled(PkeSchemeFeature::MultiParty))
+        ));
+    }
+
+    #[test]
+    fn decryption_shares_are_flooded() {
+        for (mode, flooded) in [
+            (DecryptionNoiseMode::FixedNoiseDecrypt, false),
+            (DecryptionNoiseMode::NoiseFloodingDecrypt, true),
+        ] {
+            let cc = context_with(
+                CryptoParams::builder()
+                    .mod_size(50)
+                    .multiparty_mode(MultipartyMode::NoiseFloodingMultiparty)
+                    .decryption_noise_mode(mode),
+                &[PkeSchemeFeature::Pke, PkeSchemeFeature::MultiParty],
+            );
+            let k1 = cc.key_gen().unwrap();
+            let k2 = cc.multiparty_key_gen(&k1.public_key).unwrap();
+            let pt = cc.make_packed_plaintext(&[5, -6]).unwrap();
+            let ct = cc.encrypt(&k2.public_key, &pt).unwrap();
+            let pt = joint_decrypt(&cc, &[&k1.secret_key, &k2.secret_key], &ct);
+            assert_eq!(values(pt, 2), &[5, -6], "{mode}");
+
+            // two shares of the same ciphertext differ by t * (e - e')
+            let h1 = cc.multiparty_decrypt_main(&ct, &k2.secret_key).unwrap();
+            let h2 = cc.multiparty_decrypt_main(&ct, &k2.secret_key).unwrap();
+            let basis = cc.params.basis();
+            let mut difference = h1.share().clone();
+            difference.sub_assign(basis, h2.share());
+            let log_t = (cc.params.plaintext_modulus() as f64).log2();
+            let log_e = difference.log2_norm(basis) - log_t;
+            assert_eq!(log_e > (MP_SD as f64).log2(), flooded, "{mode}: {log_e}");
+        }
+    }
+
+    #[test]
+    fn decryption_shares_must_match() {
+        let cc = context();
+        let k1 = cc.key_gen().unwrap();
+        let k2 = cc.multiparty_key_gen(&k1.public_key).unwrap();
+        assert!(matches!(
+            cc.multiparty_decrypt_fusion(&[]),
+            Err(Error::InvalidCiphertext(_))
+        ));
+        let pt = cc.make_packed_plaintext(&[1]).unwrap();
+        let ct = cc.encrypt(&k2.public_key, &pt).unwrap();
+        let reduced = cc.mod_reduce(&ct).unwrap();
+        let shares = [
+            cc.multiparty_decrypt_lead(&ct, &k1.secret_key).unwrap(),
+            cc.multiparty_decrypt_main(&reduced, &k2.secret_key)
+                .unwrap(),
+        ];
+        assert!(matches!(
+            cc.multiparty_decrypt_fusion(&shares),
+            Err(Error::InvalidCiphertext(_))
+        ));
+
+        let lead = |ct| cc.multiparty_decrypt_lead(ct, &k1.secret_key).unwrap();
+        let main = |ct| cc.multiparty_decrypt_main(ct, &k2.secret_key).unwrap();
+        // another encryption of the same plaintext under the same key
+        let other = cc.encrypt(&k2.public_key, &pt).unwrap();
+        let other_key = cc.encrypt(&k1.public_key, &pt).unwrap();
+        for shares in [
+            [lead(&ct), main(&other)],
+            [lead(&ct), lead(&ct)],
+            [main(&ct), main(&ct)],
+        ] {
+            assert!(matches!(
+                cc.multiparty_decrypt_fusion(&shares),
+                Err(Error::InvalidCiphertext(_))
+            ));
+        }
+        assert!(matches!(
+            cc.multiparty_decrypt_fusion(&[lead(&ct), main(&other_key)]),
+            Err(Error::KeyTagMismatch(..))
+        ));
+        let pt = cc
+            .multiparty_decrypt_fusion(&[main(&ct), lead(&ct)])
+            .unwrap();
+        assert_eq!(values(pt, 1), &[1]);
+
+        let product = scheme::eval_mult(&cc.params, &ct, &ct);
+        assert!(matches!(
+            cc.multiparty_decrypt_main(&product, &k2.secret_key),
+            Err(Error::InvalidCiphertext(_))
+        ));
+    }
+}
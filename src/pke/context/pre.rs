@@ -0,0 +1,326 @@
+//! Proxy re-encryption: the owner of a secret key delegates decryption to the
+//! owner of a public key, and a proxy holding the re-encryption key switches
+//! ciphertexts between them without learning the messages.
+//!
+//! Against honest re-encryption attacks the key switching noise, which depends
+//! on the secret key of the delegator, must not reach the delegatee: the HRA
+//! modes re-randomize the output with an encryption of zero under the public
+//! key of the delegatee, with a fixed noise of width `PRE_SD` in
+//! [`ProxyReEncryptionMode::FixedNoiseHra`], or flood it with a noise
+//! statistically hiding the key switching noise and then drop the primes the
+//! chain was extended with to absorb it in
+//! [`ProxyReEncryptionMode::NoiseFloodingHra`].
+
+use crate::fhe_core::noise_flooding::PRE_SD;
+use crate::fhe_core::{PkeSchemeFeature, ProxyReEncryptionMode};
+use crate::pke::context::{check_key_tags, check_linear};
+use crate::pke::{noise, scheme, Ciphertext, CryptoContext, EvalKey, PrivateKey, PublicKey};
+use crate::{Error, FheResult};
+
+impl CryptoContext {
+    /// Generate the re-encryption key from `old_sk` to the owner of `new_pk`
+    pub fn re_key_gen(&self, old_sk: &PrivateKey, new_pk: &PublicKey) -> FheResult<EvalKey> {
+        self.require_pre()?;
+        self.check_private_key(old_sk)?;
+        self.check_public_key(new_pk)?;
+        Ok(
+            scheme::re_key_gen(&self.params, old_sk.poly(), new_pk, &mut self.rng())
+                .with_key_tags(old_sk.key_tag(), new_pk.key_tag()),
+        )
+    }
+
+    /// Re-encrypt `ct` to the target key of `rekey` in
+    /// [`ProxyReEncryptionMode::IndCpa`], the HRA modes needing
+    /// [`Self::re_encrypt_hra`]
+    pub fn re_encrypt(&self, ct: &Ciphertext, rekey: &EvalKey) -> FheResult<Ciphertext> {
+        self.re_encrypt_with(ct, rekey, None)
+    }
+
+    /// Re-encrypt `ct` to the target key of `rekey` in the HRA modes, the
+    /// result being re-randomized under `new_pk`, the public key of the target
+    pub fn re_encrypt_hra(
+        &self,
+        ct: &Ciphertext,
+        rekey: &EvalKey,
+        new_pk: &PublicKey,
+    ) -> FheResult<Ciphertext> {
+        self.require_pre()?;
+        if matches!(self.params.pre_mode(), ProxyReEncryptionMode::IndCpa) {
+            return Err(Error::InvalidParameters(format!(
+                "{} re-encryption does not re-randomize, use re_encrypt",
+                ProxyReEncryptionMode::IndCpa
+            )));
+        }
+        self.re_encrypt_with(ct, rekey, Some(new_pk))
+    }
+
+    fn re_encrypt_with(
+        &self,
+        ct: &Ciphertext,
+        rekey: &EvalKey,
+        new_pk: Option<&PublicKey>,
+    ) -> FheResult<Ciphertext> {
+        self.require_pre()?;
+        self.check_ciphertext(ct)?;
+        self.check_eval_key(rekey)?;
+        check_linear(ct)?;
+        check_key_tags(rekey.source_key_tag(), ct.key_tag())?;
+        let params = &self.params;
+        let basis = params.basis();
+        let num_towers = ct.num_towers();
+
+        let hra = !matches!(params.pre_mode(), ProxyReEncryptionMode::IndCpa);
+        if hra && ct.hops() >= params.pre_num_hops() {
+            return Err(Error::InvalidCiphertext(format!(
+                "the ciphertext has already been re-encrypted {} times",
+                ct.hops()
+            )));
+        }
+        let drop_towers = match params.pre_mode() {
+            ProxyReEncryptionMode::NoiseFloodingHra => params.pre_hop_towers(),
+            _ => 0,
+        };
+        if num_towers < params.min_towers() + drop_towers {
+            return Err(Error::InvalidCiphertext(
+                "not enough primes left to absorb the flooding noise".to_string(),
+            ));
+        }
+
+        let [mut c0, mut c1] = scheme::key_switch(params, rekey, &ct.elements()[1]);
+        c0.add_assign(basis, &ct.elements()[0]);
+        // the errors of a re-encryption key are those of public key encryptions
+        let rekey_std_dev = params.std_dev() * (1.0 + 4.0 * params.ring_dim() as f64 / 3.0).sqrt();
+        let mut estimate = noise::add(
+            ct.noise(),
+            noise::key_switch(params, num_towers, rekey_std_dev),
+        );
+        if hra {
+            let pk = new_pk.ok_or_else(|| {
+                Error::InvalidParameters(format!(
+                    "{} re-encryption needs the public key of the target, use re_encrypt_hra",
+                    params.pre_mode()
+                ))
+            })?;
+            self.check_public_key(pk)?;
+            check_key_tags(rekey.target_key_tag(), pk.key_tag())?;
+            let std_dev = match params.pre_mode() {
+                ProxyReEncryptionMode::FixedNoiseHra => PRE_SD as f64,
+                _ => params.std_dev(),
+            };
+            let mut rng = self.rng();
+            let [r0, r1] = scheme::encrypt_zero(params, pk, num_towers, std_dev, &mut rng);
+            c0.add_assign(basis, &r0);
+            c1.add_assign(basis, &r1);
+            estimate = noise::add(estimate, noise::fresh(params, std_dev));
+            if drop_towers > 0 {
+                let flood = scheme::flooding_noise(
+                    params,
+                    num_towers,
+                    params.pre_flooding_bits(),
+                    &mut rng,
+                );
+                c0.add_assign(basis, &flood);
+                estimate = noise::add(
+                    estimate,
+                    noise::flooding(params, params.pre_flooding_bits()),
+                );
+            }
+        }
+
+        let mut out = Ciphertext::new(vec![c0, c1], ct.encoding())
+            .with_hops(ct.hops() + 1)
+            .with_key_tag(rekey.target_key_tag())
+            .with_noise(estimate);
+        for _ in 0..drop_towers {
+            scheme::mod_reduce(params, &mut out);
+        }
+        Ok(out)
+    }
+
+    fn require_pre(&self) -> FheResult<()> {
+        self.require(PkeSchemeFeature::Pre)?;
+        if matches!(self.params.pre_mode(), ProxyReEncryptionMode::NotSet) {
+            return Err(Error::InvalidParameters(
+                "no proxy re-encryption mode selected".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pke::context::tests::{context_with, values};
+    use crate::pke::{CryptoParams, KeyPair};
+
+    fn context(mode: ProxyReEncryptionMode, hops: usize) -> CryptoContext {
+        context_with(
+            CryptoParams::builder()
+                .mod_size(50)
+                .pre_mode(mode)
+                .pre_num_hops(hops),
+            &[
+                PkeSchemeFeature::Pke,
+                PkeSchemeFeature::LeveledShe,
+                PkeSchemeFeature::Pre,
+            ],
+        )
+    }
+
+    /// Re-encrypt a message along a chain of `hops` + 1 key pairs, checking
+    /// every hop
+    fn run_hops(
+        mode: ProxyReEncryptionMode,
+        hops: usize,
+    ) -> (CryptoContext, Vec<KeyPair>, Ciphertext) {
+        let cc = context(mode, hops);
+        let keys: Vec<_> = (0..=hops).map(|_| cc.key_gen().unwrap()).collect();
+        let pt = cc.make_packed_plaintext(&[1, -2, 3]).unwrap();
+        let mut ct = cc.encrypt(&keys[0].public_key, &pt).unwrap();
+        for h in 0..hops {
+            let (old, new) = (&keys[h], &keys[h + 1]);
+            let rekey = cc.re_key_gen(&old.secret_key, &new.public_key).unwrap();
+            ct = match mode {
+                ProxyReEncryptionMode::IndCpa => cc.re_encrypt(&ct, &rekey).unwrap(),
+                _ => cc.re_encrypt_hra(&ct, &rekey, &new.public_key).unwrap(),
+            };
+            assert_eq!(ct.hops(), h + 1);
+            assert_eq!(ct.key_tag(), new.public_key.key_tag());
+            let pt = cc.decrypt(&new.secret_key, &ct).unwrap();
+            assert_eq!(values(pt, 3), &[1, -2, 3], "{mode} hop {h}");
+        }
+        (cc, keys, ct)
+    }
+
+    #[test]
+    fn ind_cpa_re_encryption() {
+        let (cc, keys, ct) = run_hops(ProxyReEncryptionMode::IndCpa, 2);
+        let rekey = cc
+            .re_key_gen(&keys[2].secret_key, &keys[0].public_key)
+            .unwrap();
+        assert!(matches!(
+            cc.re_encrypt_hra(&ct, &rekey, &keys[0].public_key),
+            Err(Error::InvalidParameters(_))
+        ));
+        // a ciphertext of another key
+        assert!(matches!(
+            cc.re_encrypt(
+                &ct,
+                &cc.re_key_gen(&keys[0].secret_key, &keys[1].public_key)
+                    .unwrap()
+            ),
+            Err(Error::KeyTagMismatch(..))
+        ));
+    }
+
+    #[test]
+    fn hra_re_encryption() {
+        for mode in [
+            ProxyReEncryptionMode::FixedNoiseHra,
+            ProxyReEncryptionMode::NoiseFloodingHra,
+        ] {
+            let (cc, keys, ct) = run_hops(mode, 2);
+            let rekey = cc
+                .re_key_gen(&keys[2].secret_key, &keys[0].public_key)
+                .unwrap();
+            let pt = cc.make_packed_plaintext(&[1]).unwrap();
+            let fresh = cc.encrypt(&keys[2].public_key, &pt).unwrap();
+            assert!(matches!(
+                cc.re_encrypt(&fresh, &rekey),
+                Err(Error::InvalidParameters(_))
+            ));
+            // the hops of the parameters are used up
+            assert!(matches!(
+                cc.re_encrypt_hra(&ct, &rekey, &keys[0].public_key),
+                Err(Error::InvalidCiphertext(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn long_hra_chains() {
+        for mode in [
+            ProxyReEncryptionMode::FixedNoiseHra,
+            ProxyReEncryptionMode::NoiseFloodingHra,
+        ] {
+            for hops in [5, 6] {
+                run_hops(mode, hops);
+            }
+        }
+    }
+
+    #[test]
+    fn hra_chains_pick_a_digit_size_that_fits() {
+        let builder = CryptoParams::builder()
+            .pre_mode(ProxyReEncryptionMode::NoiseFloodingHra)
+            .pre_num_hops(6);
+        let params = builder.build().unwrap();
+        assert!(params.digit_size() > 0);
+        let cc = CryptoContext::new(params);
+        assert_eq!(cc.params.pre_num_hops(), 6);
+        // a digit size of the user's is kept
+        assert_eq!(builder.digit_size(20).build().unwrap().digit_size(), 20);
+        assert!(matches!(
+            builder.digit_size(59).build(),
+            Err(Error::InvalidParameters(e)) if e.contains("lower the digit size")
+        ));
+        assert!(matches!(
+            builder.pre_num_hops(100).build(),
+            Err(Error::InvalidParameters(e)) if e.contains("lower the number of hops")
+        ));
+    }
+
+    #[test]
+    fn re_encryption_keys_link_both_keys() {
+        let cc = context(ProxyReEncryptionMode::IndCpa, 1);
+        let (alice, bob) = (cc.key_gen().unwrap(), cc.key_gen().unwrap());
+        let rekey = cc.re_key_gen(&alice.secret_key, &bob.public_key).unwrap();
+        assert_eq!(rekey.source_key_tag(), alice.public_key.key_tag());
+        assert_eq!(rekey.target_key_tag(), bob.public_key.key_tag());
+        let pt = cc.make_coef_packed_plaintext(&[3, -1]).unwrap();
+        let ct = cc.encrypt(&alice.public_key, &pt).unwrap();
+        let ct = cc.re_encrypt(&ct, &rekey).unwrap();
+        let sum = cc.eval_add(&ct, &ct).unwrap();
+        assert_eq!(
+            values(cc.decrypt(&bob.secret_key, &sum).unwrap(), 2),
+            &[6, -2]
+        );
+        assert!(matches!(
+            cc.decrypt(&alice.secret_key, &ct),
+            Err(Error::KeyTagMismatch(..))
+        ));
+    }
+
+    #[test]
+    fn re_encryption_needs_a_mode_the_feature_and_a_linear_ciphertext() {
+        let cc = context(ProxyReEncryptionMode::NotSet, 1);
+        let keys = cc.key_gen().unwrap();
+        assert!(matches!(
+            cc.re_key_gen(&keys.secret_key, &keys.public_key),
+            Err(Error::InvalidParameters(_))
+        ));
+        let cc = context_with(
+            CryptoParams::builder()
+                .mod_size(50)
+                .pre_mode(ProxyReEncryptionMode::IndCpa),
+            &[PkeSchemeFeature::Pke],
+        );
+        let keys = cc.key_gen().unwrap();
+        assert!(matches!(
+            cc.re_key_gen(&keys.secret_key, &keys.public_key),
+            Err(Error::FeatureNotEnabled(PkeSchemeFeature::Pre))
+        ));
+
+        let cc = context(ProxyReEncryptionMode::IndCpa, 1);
+        let keys = cc.key_gen().unwrap();
+        let rekey = cc.re_key_gen(&keys.secret_key, &keys.public_key).unwrap();
+        let pt = cc.make_packed_plaintext(&[1]).unwrap();
+        let ct = cc.encrypt(&keys.public_key, &pt).unwrap();
+        let product = scheme::eval_mult(&cc.params, &ct, &ct);
+        assert!(matches!(
+            cc.re_encrypt(&product, &rekey),
+            Err(Error::InvalidCiphertext(_))
+        ));
+    }
+}
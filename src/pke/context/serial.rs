@@ -0,0 +1,609 @@
+//! Versioned binary format of the contexts and of the objects they produce.
+//!
+//! Every encoding starts with a header of the magic `OFHE`, the format version
+//! as a little-endian u16, the [`Scheme`] and the kind of the object as one
+//! byte each, and the SHA-256 hash of the parameters the object belongs to. The
+//! body follows in bincode, where the configuration enums take their compact u8
+//! form. Changing the layout of any serialized type needs a new format version,
+//! keeping the decoders of the previous ones so stored objects remain readable.
+//!
+//! The uniform polynomials of the keys serialize as the seed they are expanded
+//! from with the hash algorithm of the parameters, which halves the size of the
+//! public and evaluation keys, and so does c_1 of the fresh secret key
+//! encryptions. The ciphertexts carry the estimate of their noise.
+
+use crate::fhe_core::{DcrtPoly, PkeSchemeFeature, PlaintextEncodings, Scheme, UniformPolys};
+use crate::pke::{
+    Ciphertext, CommonRandomPoly, CryptoContext, CryptoParams, CryptoParamsBuilder,
+    DecryptionShare, EvalKey, KeyShare, Plaintext, PrivateKey, PublicKey,
+};
+use crate::{Error, FheResult};
+use bincode::Options;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+/// The latest format version, the one objects are serialized in
+pub const FORMAT_VERSION: u16 = 1;
+
+const MAGIC: [u8; 4] = *b"OFHE";
+const HEADER_LEN: usize = MAGIC.len() + 2 + 1 + 1 + 32;
+
+const KIND_CONTEXT: u8 = 0;
+const KIND_PUBLIC_KEY: u8 = 1;
+const KIND_PRIVATE_KEY: u8 = 2;
+const KIND_EVAL_KEY: u8 = 3;
+const KIND_AUTOMORPHISM_KEYS: u8 = 4;
+const KIND_CIPHERTEXT: u8 = 5;
+const KIND_PLAINTEXT: u8 = 6;
+const KIND_KEY_SHARE: u8 = 7;
+const KIND_DECRYPTION_SHARE: u8 = 8;
+const KIND_COMMON_RANDOM_POLY: u8 = 9;
+
+mod sealed {
+    use crate::pke::CryptoContext;
+    use crate::FheResult;
+    use serde::de::DeserializeOwned;
+
+    pub trait Sealed: Sized {
+        /// The kind byte of the header
+        const KIND: u8;
+
+        /// Decode the body of format version `version`, whose layout is the
+        /// latest one unless overridden
+        fn decode(_version: u16, body: &[u8], _cc: &CryptoContext) -> FheResult<Self>
+        where
+            Self: DeserializeOwned,
+        {
+            super::deserialize(body)
+        }
+
+        /// Expand the uniform polynomials serialized as their seed
+        fn expand(&mut self, _cc: &CryptoContext) -> FheResult<()> {
+            Ok(())
+        }
+
+        /// Check a decoded object belongs to the context
+        fn validate(&self, cc: &CryptoContext) -> FheResult<()>;
+    }
+}
+
+/// An object with a versioned binary encoding, see
+/// [`CryptoContext::object_to_bytes`]
+pub trait SerialObject: sealed::Sealed + Serialize + DeserializeOwned {}
+
+/// The body of a serialized context
+#[derive(Serialize, Deserialize)]
+struct ContextBody {
+    settings: CryptoParamsBuilder,
+    features: u8,
+}
+
+impl CryptoContext {
+    /// Serialize the parameters and the enabled features, the evaluation keys
+    /// being serialized on their own. The generator is not: two contexts
+    /// decoded from the same bytes must not draw the same randomness.
+    pub fn to_bytes(&self) -> FheResult<Vec<u8>> {
+        let body = ContextBody {
+            settings: *self.params.settings(),
+            features: self.features,
+        };
+        encode(KIND_CONTEXT, &params_hash(&self.params)?, &body)
+    }
+
+    /// Deserialize a context serialized by [`Self::to_bytes`], rebuilding its
+    /// parameters, with a generator keyed from the operating system
+    pub fn from_bytes(bytes: &[u8]) -> FheResult<Self> {
+        let (_, hash, body) = decode_header(bytes, KIND_CONTEXT)?;
+        let body: ContextBody = deserialize(body)?;
+        let params = body.settings.build()?;
+        if params_hash(&params)? != hash {
+            return Err(Error::ParametersMismatch);
+        }
+        let mut cc = CryptoContext::new(params);
+        for bit in (0..u8::BITS).map(|i| 1u8 << i) {
+            if body.features & bit != 0 {
+                cc.enable(PkeSchemeFeature::try_from(bit)?)?;
+            }
+        }
+        Ok(cc)
+    }
+
+    /// Serialize an object of the context
+    pub fn object_to_bytes<T: SerialObject>(&self, object: &T) -> FheResult<Vec<u8>> {
+        object.validate(self)?;
+        encode(T::KIND, &params_hash(&self.params)?, object)
+    }
+
+    /// Deserialize an object serialized by [`Self::object_to_bytes`] with the
+    /// same parameters
+    pub fn object_from_bytes<T: SerialObject>(&self, bytes: &[u8]) -> FheResult<T> {
+        let (version, hash, body) = decode_header(bytes, T::KIND)?;
+        if hash != params_hash(&self.params)? {
+            return Err(Error::ParametersMismatch);
+        }
+        let mut object = T::decode(version, body, self)?;
+        object.expand(self)?;
+        object.validate(self)?;
+        Ok(object)
+    }
+
+    /// Expand the `len` uniform polynomials of a decoded object
+    fn expand_uniform(&self, a: &mut UniformPolys, len: usize) -> FheResult<()> {
+        if a.len() != len {
+            return Err(Error::Serialization(format!(
+                "expected {len} uniform polynomials, got {}",
+                a.len()
+            )));
+        }
+        a.expand(self.params.basis(), self.params.hash_algorithm());
+        Ok(())
+    }
+
+    fn check_reduced<'a>(&self, polys: impl IntoIterator<Item = &'a DcrtPoly>) -> FheResult<()> {
+        if !polys.into_iter().all(|p| p.is_reduced(self.params.basis())) {
+            return Err(Error::Serialization("residues are not reduced".to_string()));
+        }
+        Ok(())
+    }
+}
+
+impl sealed::Sealed for PublicKey {
+    const KIND: u8 = KIND_PUBLIC_KEY;
+
+    fn expand(&mut self, cc: &CryptoContext) -> FheResult<()> {
+        cc.expand_uniform(self.uniform_mut(), 1)
+    }
+
+    fn validate(&self, cc: &CryptoContext) -> FheResult<()> {
+        cc.check_public_key(self)?;
+        cc.check_reduced([self.b(), self.a()])?;
+        if !self.has_own_tag(cc.params.hash_algorithm()) {
+            return Err(Error::InvalidKey(
+                "public key tag does not match the key".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl sealed::Sealed for PrivateKey {
+    const KIND: u8 = KIND_PRIVATE_KEY;
+
+    fn validate(&self, cc: &CryptoContext) -> FheResult<()> {
+        cc.check_private_key(self)?;
+        cc.check_reduced([self.poly()])
+    }
+}
+
+impl sealed::Sealed for EvalKey {
+    const KIND: u8 = KIND_EVAL_KEY;
+
+    fn expand(&mut self, cc: &CryptoContext) -> FheResult<()> {
+        cc.expand_uniform(self.uniform_mut(), cc.num_digits())
+    }
+
+    fn validate(&self, cc: &CryptoContext) -> FheResult<()> {
+        cc.check_eval_key(self)?;
+        cc.check_reduced(self.b().iter().chain(self.a()))?;
+        if !self.has_own_tag(cc.params.hash_algorithm()) {
+            return Err(Error::InvalidKey(
+                "evaluation key tag does not match the key".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl sealed::Sealed for BTreeMap<usize, EvalKey> {
+    const KIND: u8 = KIND_AUTOMORPHISM_KEYS;
+
+    fn expand(&mut self, cc: &CryptoContext) -> FheResult<()> {
+        self.values_mut().try_for_each(|ek| ek.expand(cc))
+    }
+
+    fn validate(&self, cc: &CryptoContext) -> FheResult<()> {
+        let ring_dim = cc.params.ring_dim();
+        for (&k, ek) in self {
+            if k % 2 == 0 || k >= 2 * ring_dim {
+                return Err(Error::InvalidKey(format!("invalid automorphism index {k}")));
+            }
+            ek.validate(cc)?;
+        }
+        Ok(())
+    }
+}
+
+impl sealed::Sealed for Ciphertext {
+    const KIND: u8 = KIND_CIPHERTEXT;
+
+    fn expand(&mut self, cc: &CryptoContext) -> FheResult<()> {
+        let Some(&seed) = self.seed() else {
+            return Ok(());
+        };
+        if self.num_elements() != 1 {
+            return Err(Error::Serialization(
+                "a seeded ciphertext holds c_0 alone".to_string(),
+            ));
+        }
+        let params = &cc.params;
+        let a = UniformPolys::from_seed(params.basis(), 1, params.hash_algorithm(), seed);
+        let mut c1 = a.polys()[0].clone();
+        c1.truncate(self.num_towers());
+        self.restore_uniform(c1);
+        Ok(())
+    }
+
+    fn validate(&self, cc: &CryptoContext) -> FheResult<()> {
+        cc.check_ciphertext(self)?;
+        cc.check_reduced(self.elements())
+    }
+}
+
+impl sealed::Sealed for Plaintext {
+    const KIND: u8 = KIND_PLAINTEXT;
+
+    fn validate(&self, cc: &CryptoContext) -> FheResult<()> {
+        cc.check_length(self.values())?;
+        match (self.encoding(), cc.params.packing()) {
+            (PlaintextEncodings::Packed, Some(_)) | (PlaintextEncodings::CoefPacked, _) => Ok(()),
+            (encoding, _) => Err(Error::InvalidParameters(format!(
+                "unsupported plaintext encoding {encoding}"
+            ))),
+        }
+    }
+}
+
+impl sealed::Sealed for KeyShare {
+    const KIND: u8 = KIND_KEY_SHARE;
+
+    fn validate(&self, cc: &CryptoContext) -> FheResult<()> {
+        if !(1..=self.num_parties()).contains(&self.index())
+            || !(1..=self.num_parties()).contains(&self.threshold())
+            || !cc.is_key_poly(self.poly())
+        {
+            return Err(Error::InvalidKey(
+                "key share for other parameters".to_string(),
+            ));
+        }
+        cc.check_reduced([self.poly()])
+    }
+}
+
+impl sealed::Sealed for DecryptionShare {
+    const KIND: u8 = KIND_DECRYPTION_SHARE;
+
+    fn validate(&self, cc: &CryptoContext) -> FheResult<()> {
+        if !(cc.params.min_towers()..=cc.params.moduli().len()).contains(&self.num_towers())
+            || !cc.is_well_formed(self.share())
+        {
+            return Err(Error::InvalidCiphertext(
+                "decryption share for other parameters".to_string(),
+            ));
+        }
+        cc.check_reduced([self.share()])
+    }
+}
+
+impl sealed::Sealed for CommonRandomPoly {
+    const KIND: u8 = KIND_COMMON_RANDOM_POLY;
+
+    fn expand(&mut self, cc: &CryptoContext) -> FheResult<()> {
+        cc.expand_uniform(self.uniform_mut(), 1)
+    }
+
+    fn validate(&self, cc: &CryptoContext) -> FheResult<()> {
+        cc.check_random_element(self)?;
+        cc.check_reduced([self.poly()])
+    }
+}
+
+impl SerialObject for PublicKey {}
+impl SerialObject for PrivateKey {}
+impl SerialObject for EvalKey {}
+impl SerialObject for BTreeMap<usize, EvalKey> {}
+impl SerialObject for Ciphertext {}
+impl SerialObject for Plaintext {}
+impl SerialObject for KeyShare {}
+impl SerialObject for DecryptionShare {}
+impl SerialObject for CommonRandomPoly {}
+
+/// Fixed size little-endian integers, rejecting trailing bytes
+fn options() -> impl Options {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .with_little_endian()
+        .reject_trailing_bytes()
+}
+
+/// The SHA-256 hash of the settings and the primes of `params`
+fn params_hash(params: &CryptoParams) -> FheResult<[u8; 32]> {
+    let bytes = options()
+        .serialize(&(params.settings(), params.moduli()))
+        .map_err(|e| Error::Serialization(e.to_string()))?;
+    Ok(Sha256::digest(bytes).into())
+}
+
+fn encode<T: Serialize>(kind: u8, hash: &[u8; 32], body: &T) -> FheResult<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN);
+    bytes.extend_from_slice(&MAGIC);
+    bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    bytes.push(u8::from(Scheme::BgvRns));
+    bytes.push(kind);
+    bytes.extend_from_slice(hash);
+    options()
+        .serialize_into(&mut bytes, body)
+        .map_err(|e| Error::Serialization(e.to_string()))?;
+    Ok(bytes)
+}
+
+/// Check the header of `bytes` and split it into the format version, the
+/// parameters hash and the body
+fn decode_header(bytes: &[u8], kind: u8) -> FheResult<(u16, [u8; 32], &[u8])> {
+    if bytes.len() < HEADER_LEN || bytes[..MAGIC.len()] != MAGIC {
+        return Err(Error::Serialization("missing header".to_string()));
+    }
+    let (header, body) = bytes.split_at(HEADER_LEN);
+    let version = u16::from_le_bytes([header[4], header[5]]);
+    if version == 0 || version > FORMAT_VERSION {
+        return Err(Error::UnsupportedFormatVersion {
+            found: version,
+            supported: FORMAT_VERSION,
+        });
+    }
+    let scheme = Scheme::try_from(header[6])?;
+    if !matches!(scheme, Scheme::BgvRns) {
+        return Err(Error::Serialization(format!(
+            "expected a {} object, got a {} one",
+            Scheme::BgvRns,
+            scheme
+        )));
+    }
+    if header[7] != kind {
+        return Err(Error::Serialization(format!(
+            "expected an object of kind {kind}, got {}",
+            header[7]
+        )));
+    }
+    let mut hash = [0; 32];
+    hash.copy_from_slice(&header[8..]);
+    Ok((version, hash, body))
+}
+
+/// Decode a body in the layout of the latest format version
+fn deserialize<T: DeserializeOwned>(body: &[u8]) -> FheResult<T> {
+    options()
+        .deserialize(body)
+        .map_err(|e| Error::Serialization(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fhe_core::{MultipartyMode, SecretShareType, SEED_LEN};
+    use crate::pke::context::tests::{context_with, values};
+
+    fn context() -> CryptoContext {
+        context_with(
+            CryptoParams::builder().mod_size(50),
+            &[PkeSchemeFeature::Pke, PkeSchemeFeature::LeveledShe],
+        )
+    }
+
+    #[test]
+    fn decoded_contexts_draw_fresh_randomness() {
+        let mut cc = context();
+        cc.set_rng_seed([9; SEED_LEN]);
+        let bytes = cc.to_bytes().unwrap();
+        let cc1 = CryptoContext::from_bytes(&bytes).unwrap();
+        let cc2 = CryptoContext::from_bytes(&bytes).unwrap();
+        assert!(cc1.rng_seed().is_none());
+        let keys = cc1.key_gen().unwrap();
+        let pt = cc1.make_packed_plaintext(&[1]).unwrap();
+        let ct1 = cc1.encrypt(&keys.public_key, &pt).unwrap();
+        let ct2 = cc2.encrypt(&keys.public_key, &pt).unwrap();
+        assert_ne!(ct1.elements(), ct2.elements());
+    }
+
+    #[test]
+    fn keys_serialize_their_uniform_polynomials_as_seeds() {
+        let cc = context();
+        let keys = cc.key_gen().unwrap();
+        let poly_len = 8 * cc.params.ring_dim() * cc.params.moduli().len();
+        let bytes = cc.object_to_bytes(&keys.public_key).unwrap();
+        assert!(bytes.len() < HEADER_LEN + poly_len + 1024);
+        let pk: PublicKey = cc.object_from_bytes(&bytes).unwrap();
+        assert_eq!(pk.a(), keys.public_key.a());
+        assert_eq!(pk.key_tag(), keys.public_key.key_tag());
+    }
+
+    #[test]
+    fn other_format_versions_are_rejected() {
+        let cc = context();
+        let mut bytes = cc.to_bytes().unwrap();
+        for version in [0, FORMAT_VERSION + 1] {
+            bytes[4..6].copy_from_slice(&version.to_le_bytes());
+            assert!(matches!(
+                CryptoContext::from_bytes(&bytes),
+                Err(Error::UnsupportedFormatVersion { found, .. }) if found == version
+            ));
+        }
+    }
+
+    /// Encode `object` and decode it back
+    fn round_trip<T: SerialObject>(cc: &CryptoContext, object: &T) -> T {
+        cc.object_from_bytes(&cc.object_to_bytes(object).unwrap())
+            .unwrap()
+    }
+
+    #[test]
+    fn every_object_round_trips() {
+        let mut cc = context_with(
+            CryptoParams::builder()
+                .mod_size(50)
+                .multiplicative_depth(1)
+                .multiparty_mode(MultipartyMode::FixedNoiseMultiparty),
+            &[
+                PkeSchemeFeature::Pke,
+                PkeSchemeFeature::LeveledShe,
+                PkeSchemeFeature::KeySwitch,
+                PkeSchemeFeature::MultiParty,
+            ],
+        );
+        let decoded = CryptoContext::from_bytes(&cc.to_bytes().unwrap()).unwrap();
+        assert_eq!(decoded.params().moduli(), cc.params().moduli());
+        assert!(decoded.is_enabled(PkeSchemeFeature::MultiParty));
+
+        let keys = cc.key_gen().unwrap();
+        let sk = &keys.secret_key;
+        let pk: PublicKey = round_trip(&cc, &keys.public_key);
+        assert_eq!((pk.b(), pk.a()), (keys.public_key.b(), keys.public_key.a()));
+        let decoded_sk: PrivateKey = round_trip(&cc, sk);
+        assert_eq!(decoded_sk.poly(), sk.poly());
+        assert_eq!(decoded_sk.key_tag(), sk.key_tag());
+
+        cc.eval_mult_key_gen(sk).unwrap();
+        let ek = cc.get_eval_mult_key().unwrap().clone();
+        let decoded_ek: EvalKey = round_trip(&cc, &ek);
+        assert_eq!((decoded_ek.b(), decoded_ek.a()), (ek.b(), ek.a()));
+        assert_eq!(decoded_ek.key_tag(), ek.key_tag());
+        assert_eq!(decoded_ek.source_key_tag(), ek.source_key_tag());
+        cc.eval_rotate_key_gen(sk, &[1]).unwrap();
+        let keys_map = cc.get_eval_automorphism_keys().clone();
+        let decoded_map: BTreeMap<usize, EvalKey> = round_trip(&cc, &keys_map);
+        assert!(keys_map.keys().eq(decoded_map.keys()));
+        assert!(keys_map
+            .values()
+            .zip(decoded_map.values())
+            .all(|(x, y)| x.b() == y.b()));
+
+        let pt = cc.make_packed_plaintext(&[1, -2, 3]).unwrap();
+        let decoded_pt: Plaintext = round_trip(&cc, &pt);
+        assert_eq!(decoded_pt.values(), pt.values());
+        for ct in [
+            cc.encrypt(&keys.public_key, &pt).unwrap(),
+            cc.encrypt_with_secret_key(sk, &pt).unwrap(),
+            cc.eval_mult(
+                &cc.encrypt(&keys.public_key, &pt).unwrap(),
+                &pt_ct(&cc, &keys.public_key),
+            )
+            .unwrap(),
+        ] {
+            let decoded: Ciphertext = round_trip(&cc, &ct);
+            assert_eq!(decoded.elements(), ct.elements());
+            assert_eq!(decoded.key_tag(), ct.key_tag());
+            assert_eq!(decoded.noise(), ct.noise());
+        }
+        let ct = cc.encrypt(&keys.public_key, &pt).unwrap();
+        let decoded: Ciphertext = round_trip(&cc, &ct);
+        assert_eq!(values(cc.decrypt(sk, &decoded).unwrap(), 3), &[1, -2, 3]);
+
+        let shares = cc.share_keys(sk, 3, 2, SecretShareType::Shamir).unwrap();
+        let share: KeyShare = round_trip(&cc, &shares[1]);
+        assert_eq!(share.poly(), shares[1].poly());
+        assert_eq!(share.index(), 2);
+        let dec_share = cc.multiparty_decrypt_main(&ct, sk).unwrap();
+        let decoded_share: DecryptionShare = round_trip(&cc, &dec_share);
+        assert_eq!(decoded_share.share(), dec_share.share());
+        let crs = cc
+            .common_random_poly_from_seed(&[3; SEED_LEN], b"crs")
+            .unwrap();
+        let decoded_crs: CommonRandomPoly = round_trip(&cc, &crs);
+        assert_eq!(decoded_crs.poly(), crs.poly());
+    }
+
+    /// An encryption of ones
+    fn pt_ct(cc: &CryptoContext, pk: &PublicKey) -> Ciphertext {
+        let pt = cc.make_packed_plaintext(&[1; 3]).unwrap();
+        cc.encrypt(pk, &pt).unwrap()
+    }
+
+    #[test]
+    fn malformed_encodings_are_rejected() {
+        let cc = context();
+        let keys = cc.key_gen().unwrap();
+        let bytes = cc.object_to_bytes(&keys.public_key).unwrap();
+
+        let other = context_with(
+            CryptoParams::builder().mod_size(50).multiplicative_depth(2),
+            &[PkeSchemeFeature::Pke],
+        );
+        assert!(matches!(
+            other.object_from_bytes::<PublicKey>(&bytes),
+            Err(Error::ParametersMismatch)
+        ));
+        assert!(matches!(
+            cc.object_from_bytes::<PrivateKey>(&bytes),
+            Err(Error::Serialization(_))
+        ));
+        assert!(matches!(
+            cc.object_from_bytes::<PublicKey>(&bytes[..HEADER_LEN - 1]),
+            Err(Error::Serialization(_))
+        ));
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(matches!(
+            cc.object_from_bytes::<PublicKey>(&trailing),
+            Err(Error::Serialization(_))
+        ));
+        let mut magic = bytes.clone();
+        magic[0] = b'X';
+        assert!(matches!(
+            cc.object_from_bytes::<PublicKey>(&magic),
+            Err(Error::Serialization(_))
+        ));
+    }
+
+    /// `bytes` with the first occurrence of the tag `tag` replaced by `other`
+    fn swap_tag(bytes: &[u8], tag: &str, other: &str) -> Vec<u8> {
+        let at = bytes
+            .windows(tag.len())
+            .position(|w| w == tag.as_bytes())
+            .unwrap();
+        let mut swapped = bytes.to_vec();
+        swapped[at..at + tag.len()].copy_from_slice(other.as_bytes());
+        swapped
+    }
+
+    #[test]
+    fn keys_whose_tag_does_not_match_are_rejected() {
+        let mut cc = context_with(
+            CryptoParams::builder()
+                .mod_size(50)
+                .multiparty_mode(MultipartyMode::FixedNoiseMultiparty),
+            &[
+                PkeSchemeFeature::Pke,
+                PkeSchemeFeature::LeveledShe,
+                PkeSchemeFeature::MultiParty,
+            ],
+        );
+        let keys = cc.key_gen().unwrap();
+        let other = cc.key_gen().unwrap().public_key;
+        let pk = &keys.public_key;
+        let bytes = cc.object_to_bytes(pk).unwrap();
+        assert!(matches!(
+            cc.object_from_bytes::<PublicKey>(&swap_tag(&bytes, pk.key_tag(), other.key_tag())),
+            Err(Error::InvalidKey(_))
+        ));
+
+        cc.eval_mult_key_gen(&keys.secret_key).unwrap();
+        let ek = cc.get_eval_mult_key().unwrap();
+        let bytes = cc.object_to_bytes(ek).unwrap();
+        assert!(matches!(
+            cc.object_from_bytes::<EvalKey>(&swap_tag(&bytes, ek.key_tag(), other.key_tag())),
+            Err(Error::InvalidKey(_))
+        ));
+
+        let joint = cc.multiparty_key_gen(pk).unwrap().public_key;
+        let mut bytes = cc.object_to_bytes(&joint).unwrap();
+        let decoded: PublicKey = cc.object_from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.key_tag(), joint.key_tag());
+        let last = bytes.len() - 1;
+        bytes[last] = 0;
+        assert!(matches!(
+            cc.object_from_bytes::<PublicKey>(&bytes),
+            Err(Error::InvalidKey(_))
+        ));
+    }
+}
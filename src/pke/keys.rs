@@ -0,0 +1,306 @@
+use crate::fhe_core::{DcrtPoly, HashAlgorithm, SecretShareType, UniformPolys};
+use crate::{Error, FheResult};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Debug, Formatter};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// An RLWE secret key s with ternary coefficients, kept in evaluation format
+/// over the whole modulus chain, tagged as its public key, or as the joint
+/// public key of the keys it is the sum of
+#[derive(Clone, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+pub struct PrivateKey {
+    s: DcrtPoly,
+    #[zeroize(skip)]
+    key_tag: String,
+}
+
+impl Debug for PrivateKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrivateKey")
+            .field("towers", &self.s.num_towers())
+            .field("key_tag", &self.key_tag)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PrivateKey {
+    pub(crate) fn new(s: DcrtPoly) -> Self {
+        Self {
+            s,
+            key_tag: String::new(),
+        }
+    }
+
+    pub(crate) fn with_key_tag(mut self, key_tag: &str) -> Self {
+        self.key_tag = key_tag.to_string();
+        self
+    }
+
+    /// The tag of the public key of the key pair
+    pub fn key_tag(&self) -> &str {
+        &self.key_tag
+    }
+
+    pub(crate) fn poly(&self) -> &DcrtPoly {
+        &self.s
+    }
+}
+
+/// An RLWE public key (b, a) with b = -a * s + t * e, tagged with the hex
+/// hash of the little-endian residues of b and a. A joint public key, the sum
+/// of those of several parties, is marked as such and tagged with the sum of
+/// their tags as little-endian integers. The uniform a serializes as its seed
+/// when it has one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublicKey {
+    b: DcrtPoly,
+    a: UniformPolys,
+    key_tag: String,
+    joint: bool,
+}
+
+impl PublicKey {
+    pub(crate) fn new(b: DcrtPoly, a: UniformPolys, hash_algorithm: HashAlgorithm) -> Self {
+        let key_tag = hash_polys([&b, &a.polys()[0]], hash_algorithm);
+        Self {
+            b,
+            a,
+            key_tag,
+            joint: false,
+        }
+    }
+
+    /// Tag a joint public key with the sum of the tags of its parts
+    pub(crate) fn joint(mut self, key_tag: &str) -> Self {
+        self.key_tag = key_tag.to_string();
+        self.joint = true;
+        self
+    }
+
+    /// The tag identifying the key pair
+    pub fn key_tag(&self) -> &str {
+        &self.key_tag
+    }
+
+    /// Whether the tag is the hash of b and a, the tag of a joint key being a
+    /// sum that cannot be recomputed from the key alone
+    pub(crate) fn has_own_tag(&self, hash_algorithm: HashAlgorithm) -> bool {
+        self.joint || self.key_tag == hash_polys([&self.b, self.a()], hash_algorithm)
+    }
+
+    pub(crate) fn b(&self) -> &DcrtPoly {
+        &self.b
+    }
+
+    pub(crate) fn a(&self) -> &DcrtPoly {
+        &self.a.polys()[0]
+    }
+
+    pub(crate) fn uniform(&self) -> &UniformPolys {
+        &self.a
+    }
+
+    pub(crate) fn uniform_mut(&mut self) -> &mut UniformPolys {
+        &mut self.a
+    }
+}
+
+/// A BV key switching key from a secret s' to a secret s: for every digit g_i
+/// of the RNS decomposition, a pair (b_i, a_i) with b_i = -a_i * s + t * e_i +
+/// g_i * s'. It is tagged with the hex hash of the little-endian residues of
+/// the b_i and a_i, applies to the ciphertexts of the key tag of s' and yields
+/// ciphertexts of the key tag of s. The a_i serialize as their seed when they
+/// are uniform.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalKey {
+    b: Vec<DcrtPoly>,
+    a: UniformPolys,
+    key_tag: String,
+    source_key_tag: String,
+    target_key_tag: String,
+}
+
+impl EvalKey {
+    pub(crate) fn new(b: Vec<DcrtPoly>, a: UniformPolys, hash_algorithm: HashAlgorithm) -> Self {
+        let key_tag = hash_polys(b.iter().chain(a.polys()), hash_algorithm);
+        Self {
+            b,
+            a,
+            key_tag,
+            source_key_tag: String::new(),
+            target_key_tag: String::new(),
+        }
+    }
+
+    pub(crate) fn with_key_tags(mut self, source_key_tag: &str, target_key_tag: &str) -> Self {
+        self.source_key_tag = source_key_tag.to_string();
+        self.target_key_tag = target_key_tag.to_string();
+        self
+    }
+
+    /// The tag identifying the key
+    pub fn key_tag(&self) -> &str {
+        &self.key_tag
+    }
+
+    /// The key tag of the ciphertexts the key applies to
+    pub fn source_key_tag(&self) -> &str {
+        &self.source_key_tag
+    }
+
+    /// The key tag of the ciphertexts the key produces
+    pub fn target_key_tag(&self) -> &str {
+        &self.target_key_tag
+    }
+
+    /// Whether the tag is the hash of the b_i and a_i
+    pub(crate) fn has_own_tag(&self, hash_algorithm: HashAlgorithm) -> bool {
+        self.key_tag == hash_polys(self.b.iter().chain(self.a.polys()), hash_algorithm)
+    }
+
+    /// The number of digits of the key
+    pub fn num_digits(&self) -> usize {
+        self.b.len()
+    }
+
+    pub(crate) fn b(&self) -> &[DcrtPoly] {
+        &self.b
+    }
+
+    pub(crate) fn a(&self) -> &[DcrtPoly] {
+        self.a.polys()
+    }
+
+    pub(crate) fn uniform(&self) -> &UniformPolys {
+        &self.a
+    }
+
+    pub(crate) fn uniform_mut(&mut self) -> &mut UniformPolys {
+        &mut self.a
+    }
+}
+
+/// The share of the party `index`, numbered from 1, of a secret key or of the
+/// sum of the secret keys of several parties, tagged as the shared key
+#[derive(Clone, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+pub struct KeyShare {
+    index: usize,
+    num_parties: usize,
+    threshold: usize,
+    #[zeroize(skip)]
+    share_type: SecretShareType,
+    share: DcrtPoly,
+    #[zeroize(skip)]
+    key_tag: String,
+}
+
+impl Debug for KeyShare {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyShare")
+            .field("index", &self.index)
+            .field("num_parties", &self.num_parties)
+            .field("threshold", &self.threshold)
+            .field("share_type", &self.share_type)
+            .field("key_tag", &self.key_tag)
+            .finish_non_exhaustive()
+    }
+}
+
+impl KeyShare {
+    pub(crate) fn new(
+        index: usize,
+        num_parties: usize,
+        threshold: usize,
+        share_type: SecretShareType,
+        share: DcrtPoly,
+        key_tag: &str,
+    ) -> Self {
+        Self {
+            index,
+            num_parties,
+            threshold,
+            share_type,
+            share,
+            key_tag: key_tag.to_string(),
+        }
+    }
+
+    /// The index of the party holding the share
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The number of parties the key is shared between
+    pub fn num_parties(&self) -> usize {
+        self.num_parties
+    }
+
+    /// The number of shares needed to use the key
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// How the key is shared
+    pub fn share_type(&self) -> SecretShareType {
+        self.share_type
+    }
+
+    /// The tag of the shared key
+    pub fn key_tag(&self) -> &str {
+        &self.key_tag
+    }
+
+    pub(crate) fn poly(&self) -> &DcrtPoly {
+        &self.share
+    }
+}
+
+/// A public key together with its secret key
+#[derive(Debug, Clone)]
+pub struct KeyPair {
+    /// The public key
+    pub public_key: PublicKey,
+    /// The secret key
+    pub secret_key: PrivateKey,
+}
+
+/// The tag of the sum of the shares of keys tagged `tag1` and `tag2`: the sum
+/// of the tags as little-endian integers modulo 2^(8 * digest length). The
+/// tags add up like the shares, so a joint key gets the same tag whatever the
+/// order its shares are added in.
+pub(crate) fn add_key_tags(tag1: &str, tag2: &str) -> FheResult<String> {
+    let mismatch = || Error::KeyTagMismatch(tag1.to_string(), tag2.to_string());
+    if tag1.is_empty() || tag2.is_empty() {
+        return Err(mismatch());
+    }
+    let x = hex::decode(tag1).map_err(|_| mismatch())?;
+    let y = hex::decode(tag2).map_err(|_| mismatch())?;
+    if x.len() != y.len() {
+        return Err(mismatch());
+    }
+    let mut carry = 0;
+    let sum: Vec<u8> = x
+        .iter()
+        .zip(&y)
+        .map(|(&x, &y)| {
+            let sum = u16::from(x) + u16::from(y) + carry;
+            carry = sum >> 8;
+            sum as u8
+        })
+        .collect();
+    Ok(hex::encode(sum))
+}
+
+/// The hex hash of the little-endian residues of `polys`
+pub(crate) fn hash_polys<'a>(
+    polys: impl IntoIterator<Item = &'a DcrtPoly>,
+    hash_algorithm: HashAlgorithm,
+) -> String {
+    let mut bytes = Vec::new();
+    for p in polys {
+        for i in 0..p.num_towers() {
+            bytes.extend(p.tower(i).iter().flat_map(|x| x.to_le_bytes()));
+        }
+    }
+    hex::encode(hash_algorithm.digest(&bytes))
+}
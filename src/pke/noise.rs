@@ -0,0 +1,68 @@
+//! Heuristic estimates of log2 of the infinity norm of the phase m + t * e of
+//! ciphertexts, tracked through every operation. The errors are taken as sums
+//! of independent terms bounded by 6 standard deviations, so that the product
+//! of two polynomials grows by sqrt(N) rather than by the worst case N used to
+//! choose the parameters.
+
+use crate::pke::CryptoParams;
+
+/// The number of standard deviations bounding an error
+const TAIL: f64 = 6.0;
+
+/// log2(2^a + 2^b)
+pub(crate) fn add(a: f64, b: f64) -> f64 {
+    let (hi, lo) = if a > b { (a, b) } else { (b, a) };
+    if lo == f64::NEG_INFINITY {
+        return hi;
+    }
+    hi + (lo - hi).exp2().ln_1p() / std::f64::consts::LN_2
+}
+
+/// A message and t * e for a Gaussian e of width `std_dev`
+pub(crate) fn gaussian(params: &CryptoParams, std_dev: f64) -> f64 {
+    let t = (params.plaintext_modulus() as f64).log2();
+    add(t - 1.0, t + (TAIL * std_dev).log2())
+}
+
+/// A public key encryption with errors of width `std_dev`: the message and
+/// t * (e * u + e_0 + e_1 * s) for ternary u and s
+pub(crate) fn fresh(params: &CryptoParams, std_dev: f64) -> f64 {
+    let ring_dim = params.ring_dim() as f64;
+    gaussian(params, std_dev * (1.0 + 4.0 * ring_dim / 3.0).sqrt())
+}
+
+/// The product of two phases
+pub(crate) fn mult(params: &CryptoParams, a: f64, b: f64) -> f64 {
+    a + b + 0.5 * (params.ring_dim() as f64).log2()
+}
+
+/// The noise sum_i d_i * t * e_i of a key switching over the first
+/// `num_towers` primes, with digits d_i uniform over their range and key
+/// errors of width `std_dev`
+pub(crate) fn key_switch(params: &CryptoParams, num_towers: usize, std_dev: f64) -> f64 {
+    let variance: f64 = (0..num_towers)
+        .map(|i| {
+            let width = match params.digit_size() {
+                0 => params.moduli()[i] as f64,
+                size => (size as f64).exp2(),
+            };
+            params.digits(i) as f64 * width * width / 12.0
+        })
+        .sum();
+    let t = (params.plaintext_modulus() as f64).log2();
+    t + (TAIL * std_dev * (params.ring_dim() as f64 * variance).sqrt()).log2()
+}
+
+/// The phase `a` after dropping the prime `q_l`: divided by it, plus the
+/// rounding (delta_0 + delta_1 * s) / q_l with coefficients up to t / 2
+pub(crate) fn mod_reduce(params: &CryptoParams, a: f64, q_l: u64) -> f64 {
+    let ring_dim = params.ring_dim() as f64;
+    let t = params.plaintext_modulus() as f64;
+    let rounding = TAIL * t * ((1.0 + 2.0 * ring_dim / 3.0) / 12.0).sqrt();
+    add(a - (q_l as f64).log2(), rounding.log2())
+}
+
+/// t * e for e uniform of `bits` bits
+pub(crate) fn flooding(params: &CryptoParams, bits: u32) -> f64 {
+    (params.plaintext_modulus() as f64).log2() + bits as f64 - 1.0
+}
@@ -0,0 +1,562 @@
+use crate::fhe_core::noise_flooding::{MULTIPART_MOD_SIZE, NUM_MODULI_MULTIPARTY, PRE_SD};
+use crate::fhe_core::{
+    last_prime, previous_prime, DecryptionNoiseMode, HashAlgorithm, KeySwitchTechnique, Modulus,
+    MultipartyMode, ProxyReEncryptionMode, RnsBasis, MAX_MODULUS_SIZE,
+};
+use crate::pke::plaintext::PackedEncoding;
+use crate::{Error, FheResult};
+use serde::{Deserialize, Serialize};
+
+/// Largest log2 Q with 128-bit security for ternary secrets, per ring dimension,
+/// as tabulated by the homomorphic encryption standard
+const MAX_LOG_Q_128: [(usize, usize); 6] = [
+    (1 << 10, 27),
+    (1 << 11, 54),
+    (1 << 12, 109),
+    (1 << 13, 218),
+    (1 << 14, 438),
+    (1 << 15, 881),
+];
+
+/// Builder for [`CryptoParams`], with OpenFHE's defaults for BGV
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct CryptoParamsBuilder {
+    plaintext_modulus: u64,
+    multiplicative_depth: usize,
+    ring_dim: Option<usize>,
+    mod_size: usize,
+    std_dev: f64,
+    key_switch_technique: KeySwitchTechnique,
+    digit_size: u32,
+    multiparty_mode: MultipartyMode,
+    decryption_noise_mode: DecryptionNoiseMode,
+    pre_mode: ProxyReEncryptionMode,
+    pre_num_hops: usize,
+    statistical_security: u32,
+    hash_algorithm: HashAlgorithm,
+}
+
+impl Default for CryptoParamsBuilder {
+    fn default() -> Self {
+        Self {
+            plaintext_modulus: 65537,
+            multiplicative_depth: 1,
+            ring_dim: None,
+            mod_size: MAX_MODULUS_SIZE,
+            std_dev: 3.19,
+            key_switch_technique: KeySwitchTechnique::Bv,
+            digit_size: 0,
+            multiparty_mode: MultipartyMode::FixedNoiseMultiparty,
+            decryption_noise_mode: DecryptionNoiseMode::FixedNoiseDecrypt,
+            pre_mode: ProxyReEncryptionMode::IndCpa,
+            pre_num_hops: 1,
+            statistical_security: 30,
+            hash_algorithm: HashAlgorithm::Sha256,
+        }
+    }
+}
+
+impl CryptoParamsBuilder {
+    /// The plaintext modulus t, a prime `1 mod 2N` enables packed encoding
+    pub fn plaintext_modulus(mut self, plaintext_modulus: u64) -> Self {
+        self.plaintext_modulus = plaintext_modulus;
+        self
+    }
+
+    /// The number of multiplications followed by a modulus reduction the
+    /// ciphertexts support, i.e. the number of primes beyond the first one
+    pub fn multiplicative_depth(mut self, multiplicative_depth: usize) -> Self {
+        self.multiplicative_depth = multiplicative_depth;
+        self
+    }
+
+    /// The ring dimension N, by default the smallest one that is 128-bit secure
+    /// for the modulus chain
+    pub fn ring_dim(mut self, ring_dim: usize) -> Self {
+        self.ring_dim = Some(ring_dim);
+        self
+    }
+
+    /// The size in bits of every prime of the modulus chain
+    pub fn mod_size(mut self, mod_size: usize) -> Self {
+        self.mod_size = mod_size;
+        self
+    }
+
+    /// The standard deviation of the error distribution
+    pub fn std_dev(mut self, std_dev: f64) -> Self {
+        self.std_dev = std_dev;
+        self
+    }
+
+    /// The key switching technique, only [`KeySwitchTechnique::Bv`] is
+    /// supported
+    pub fn key_switch_technique(mut self, key_switch_technique: KeySwitchTechnique) -> Self {
+        self.key_switch_technique = key_switch_technique;
+        self
+    }
+
+    /// The size in bits of the digits every residue is decomposed into during
+    /// BV key switching, 0 keeping a single digit per prime unless the hops of
+    /// the HRA modes only fit with smaller digits, the largest that fit being
+    /// picked then
+    pub fn digit_size(mut self, digit_size: u32) -> Self {
+        self.digit_size = digit_size;
+        self
+    }
+
+    /// The multiparty mode used by the threshold protocols,
+    /// [`MultipartyMode::NoiseFloodingMultiparty`] extending the chain with
+    /// `NUM_MODULI_MULTIPARTY` primes of `MULTIPART_MOD_SIZE` bits
+    pub fn multiparty_mode(mut self, multiparty_mode: MultipartyMode) -> Self {
+        self.multiparty_mode = multiparty_mode;
+        self
+    }
+
+    /// The noise added to the partial decryptions of the threshold protocols
+    pub fn decryption_noise_mode(mut self, decryption_noise_mode: DecryptionNoiseMode) -> Self {
+        self.decryption_noise_mode = decryption_noise_mode;
+        self
+    }
+
+    /// The security of proxy re-encryption, the HRA modes extending the chain
+    /// so that [`Self::pre_num_hops`] re-encryptions decrypt correctly
+    pub fn pre_mode(mut self, pre_mode: ProxyReEncryptionMode) -> Self {
+        self.pre_mode = pre_mode;
+        self
+    }
+
+    /// The number of successive re-encryptions the HRA proxy re-encryption
+    /// modes guarantee to decrypt correctly
+    pub fn pre_num_hops(mut self, pre_num_hops: usize) -> Self {
+        self.pre_num_hops = pre_num_hops;
+        self
+    }
+
+    /// The statistical security in bits of noise flooding in
+    /// [`ProxyReEncryptionMode::NoiseFloodingHra`]
+    pub fn statistical_security(mut self, statistical_security: u32) -> Self {
+        self.statistical_security = statistical_security;
+        self
+    }
+
+    /// The hash of the key tags
+    pub fn hash_algorithm(mut self, hash_algorithm: HashAlgorithm) -> Self {
+        self.hash_algorithm = hash_algorithm;
+        self
+    }
+
+    /// Validate the settings and generate the modulus chain
+    pub fn build(self) -> FheResult<CryptoParams> {
+        let t = self.plaintext_modulus;
+        if !matches!(self.key_switch_technique, KeySwitchTechnique::Bv) {
+            return Err(Error::InvalidParameters(format!(
+                "unsupported key switching technique {}",
+                self.key_switch_technique
+            )));
+        }
+        if self.mod_size < 20 || self.mod_size > MAX_MODULUS_SIZE {
+            return Err(Error::InvalidParameters(format!(
+                "modulus size {} must lie in [20, {}]",
+                self.mod_size, MAX_MODULUS_SIZE
+            )));
+        }
+        if t < 2 || t.ilog2() as usize + 2 > self.mod_size {
+            return Err(Error::InvalidParameters(format!(
+                "plaintext modulus {} does not fit {}-bit primes",
+                t, self.mod_size
+            )));
+        }
+        let hra_hops = match self.pre_mode {
+            ProxyReEncryptionMode::FixedNoiseHra | ProxyReEncryptionMode::NoiseFloodingHra => {
+                if self.pre_num_hops == 0 {
+                    return Err(Error::InvalidParameters(format!(
+                        "{} proxy re-encryption needs at least one hop",
+                        self.pre_mode
+                    )));
+                }
+                self.pre_num_hops
+            }
+            _ => 0,
+        };
+        if self.digit_size as usize >= self.mod_size {
+            return Err(Error::InvalidParameters(format!(
+                "digit size {} must be smaller than the modulus size {}",
+                self.digit_size, self.mod_size
+            )));
+        }
+
+        // the key switching noise every HRA hop adds grows with the digits, so
+        // without a digit size of the user's pick the largest one that fits
+        let (builder, chain) = match self.chain(hra_hops) {
+            Err(Error::InvalidParameters(e)) if hra_hops > 0 => {
+                let fitting = (1..self.mod_size as u32)
+                    .rev()
+                    .filter(|_| self.digit_size == 0)
+                    .map(|digit_size| Self { digit_size, ..self })
+                    .find_map(|builder| Some((builder, builder.chain(hra_hops).ok()?)));
+                fitting.ok_or_else(|| {
+                    let fix = match self.digit_size {
+                        0 => "the number of hops",
+                        _ => "the digit size or the number of hops",
+                    };
+                    Error::InvalidParameters(format!("{e}, lower {fix}"))
+                })?
+            }
+            chain => (self, chain?),
+        };
+        let Chain {
+            num_extra_moduli,
+            num_moduli,
+            ring_dim,
+            pre_hop_towers,
+        } = chain;
+
+        // primes 1 mod t keep the message unchanged by modulus switching
+        let step = lcm(2 * ring_dim as u64, t).ok_or_else(|| {
+            Error::InvalidParameters(format!("plaintext modulus {t} is too large"))
+        })?;
+        // the extra primes come first as modulus reduction never drops them
+        let mut moduli = generate_primes(MULTIPART_MOD_SIZE, num_extra_moduli, step, &[])?;
+        let primes = generate_primes(
+            builder.mod_size,
+            num_moduli - num_extra_moduli,
+            step,
+            &moduli,
+        )?;
+        moduli.extend(primes);
+        let basis = RnsBasis::new(ring_dim, &moduli)
+            .ok_or_else(|| Error::InvalidParameters("moduli do not support the NTT".to_string()))?;
+
+        // t^-1 mod q_l and q_l^-1 mod q_j for j < l, used to drop q_l
+        let invalid = || Error::InvalidParameters("moduli are not coprime".to_string());
+        let mut t_inv = Vec::with_capacity(num_moduli);
+        let mut q_inv = Vec::with_capacity(num_moduli);
+        for l in 0..num_moduli {
+            let q_l = basis.modulus(l);
+            t_inv.push(q_l.inv(t).ok_or_else(invalid)?);
+            q_inv.push(
+                (0..l)
+                    .map(|j| basis.modulus(j).inv(q_l.value()))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(invalid)?,
+            );
+        }
+
+        Ok(CryptoParams {
+            settings: CryptoParamsBuilder {
+                ring_dim: Some(ring_dim),
+                ..builder
+            },
+            plaintext_modulus: Modulus::new(t),
+            multiplicative_depth: builder.multiplicative_depth,
+            std_dev: builder.std_dev,
+            key_switch_technique: builder.key_switch_technique,
+            digit_size: builder.digit_size,
+            multiparty_mode: builder.multiparty_mode,
+            decryption_noise_mode: builder.decryption_noise_mode,
+            pre_mode: builder.pre_mode,
+            pre_num_hops: builder.pre_num_hops,
+            statistical_security: builder.statistical_security,
+            hash_algorithm: builder.hash_algorithm,
+            pre_hop_towers,
+            pre_flooding_bits: (builder.key_switch_noise_bits(
+                ring_dim,
+                num_moduli,
+                num_extra_moduli,
+            ) - (t as f64).log2()
+                + builder.statistical_security as f64)
+                .ceil() as u32,
+            num_extra_moduli,
+            moduli,
+            packing: PackedEncoding::new(ring_dim, t),
+            basis,
+            t_inv,
+            q_inv,
+        })
+    }
+}
+
+impl CryptoParamsBuilder {
+    /// The chain of `hra_hops` HRA hops: the primes proxy re-encryption needs
+    /// depend on its noise, which grows with the ring dimension and the chain,
+    /// so grow both until they agree
+    fn chain(&self, hra_hops: usize) -> FheResult<Chain> {
+        let num_multiparty_moduli = match self.multiparty_mode {
+            MultipartyMode::NoiseFloodingMultiparty => NUM_MODULI_MULTIPARTY,
+            _ => 0,
+        };
+        let mut num_pre_moduli = 0;
+        let mut pre_hop_towers = 0;
+        loop {
+            let num_extra_moduli = num_multiparty_moduli + num_pre_moduli;
+            let num_primes = self.multiplicative_depth + 1 + hra_hops * pre_hop_towers;
+            let log_q = num_extra_moduli * MULTIPART_MOD_SIZE + num_primes * self.mod_size;
+            let min_ring_dim = MAX_LOG_Q_128
+                .iter()
+                .find(|&&(_, max_log_q)| log_q <= max_log_q)
+                .map(|&(ring_dim, _)| ring_dim)
+                .ok_or_else(|| {
+                    Error::InvalidParameters(format!(
+                        "no secure ring dimension for log2 Q = {log_q}"
+                    ))
+                })?;
+            let ring_dim = self.ring_dim.unwrap_or(min_ring_dim);
+            if !ring_dim.is_power_of_two() || ring_dim < min_ring_dim {
+                return Err(Error::InvalidParameters(format!(
+                    "ring dimension {ring_dim} is not a secure power of two for log2 Q = {log_q}"
+                )));
+            }
+            let num_moduli = num_extra_moduli + num_primes;
+            let key_switch_bits =
+                self.key_switch_noise_bits(ring_dim, num_moduli, num_extra_moduli);
+            let (pre_moduli, hop_towers) = match self.pre_mode {
+                // every hop adds the key switching noise and a fresh encryption
+                // of zero, which the first primes must absorb
+                ProxyReEncryptionMode::FixedNoiseHra => {
+                    let hop_bits = key_switch_bits.exp2()
+                        + self.encryption_noise_bits(ring_dim, PRE_SD as f64).exp2();
+                    let bits = hop_bits.log2() + (hra_hops as f64).log2() + 2.0;
+                    let available = (self.mod_size - 1
+                        + num_multiparty_moduli * (MULTIPART_MOD_SIZE - 1))
+                        as f64;
+                    let missing = ((bits - available) / (MULTIPART_MOD_SIZE - 1) as f64).ceil();
+                    (missing.max(0.0) as usize, 0)
+                }
+                // every hop floods the key switching noise and drops primes
+                // until the flooding noise is below one
+                ProxyReEncryptionMode::NoiseFloodingHra => {
+                    let bits = key_switch_bits + self.statistical_security as f64 + 2.0;
+                    (0, (bits / (self.mod_size - 1) as f64).ceil() as usize)
+                }
+                _ => (0, 0),
+            };
+            if pre_moduli <= num_pre_moduli && hop_towers <= pre_hop_towers {
+                return Ok(Chain {
+                    num_extra_moduli,
+                    num_moduli,
+                    ring_dim,
+                    pre_hop_towers,
+                });
+            }
+            num_pre_moduli = num_pre_moduli.max(pre_moduli);
+            pre_hop_towers = pre_hop_towers.max(hop_towers);
+        }
+    }
+
+    /// log2 of a bound on the noise one BV key switching adds over
+    /// `num_moduli` primes: N * 2^w * t * 6 sigma per digit of w bits
+    fn key_switch_noise_bits(&self, ring_dim: usize, num_moduli: usize, num_extra: usize) -> f64 {
+        let prime_bits = if num_extra > 0 {
+            self.mod_size.max(MULTIPART_MOD_SIZE)
+        } else {
+            self.mod_size
+        };
+        let (digit_bits, digits) = match self.digit_size as usize {
+            0 => (prime_bits, 1),
+            size => (size, prime_bits.div_ceil(size)),
+        };
+        digit_bits as f64
+            + ((num_moduli * digits) as f64).log2()
+            + (ring_dim as f64).log2()
+            + (self.plaintext_modulus as f64).log2()
+            + (6.0 * self.std_dev).log2()
+    }
+
+    /// log2 of a bound on the noise t * (e * u + e_0 + e_1 * s) of a public key
+    /// encryption with errors of width `std_dev`
+    fn encryption_noise_bits(&self, ring_dim: usize, std_dev: f64) -> f64 {
+        (self.plaintext_modulus as f64).log2()
+            + (6.0 * std_dev).log2()
+            + (2.0 * ring_dim as f64 + 1.0).log2()
+    }
+}
+
+/// The sizes of a modulus chain found by [`CryptoParamsBuilder::chain`]
+struct Chain {
+    num_extra_moduli: usize,
+    num_moduli: usize,
+    ring_dim: usize,
+    pre_hop_towers: usize,
+}
+
+/// Parameters of the BGV scheme over an RNS chain of primes q_0, ..., q_L, all
+/// `1 mod 2N` and `1 mod t`. Modulus reduction drops the last prime of the chain,
+/// down to the first one or to the extra primes that absorb the flooding noise of
+/// the partial decryptions of [`MultipartyMode::NoiseFloodingMultiparty`] and
+/// the noise of the hops of [`ProxyReEncryptionMode::FixedNoiseHra`].
+/// [`ProxyReEncryptionMode::NoiseFloodingHra`] rather extends the end of the
+/// chain with the primes every hop drops.
+#[derive(Debug, Clone)]
+pub struct CryptoParams {
+    settings: CryptoParamsBuilder,
+    plaintext_modulus: Modulus,
+    multiplicative_depth: usize,
+    std_dev: f64,
+    key_switch_technique: KeySwitchTechnique,
+    digit_size: u32,
+    multiparty_mode: MultipartyMode,
+    decryption_noise_mode: DecryptionNoiseMode,
+    pre_mode: ProxyReEncryptionMode,
+    pre_num_hops: usize,
+    statistical_security: u32,
+    hash_algorithm: HashAlgorithm,
+    pre_hop_towers: usize,
+    pre_flooding_bits: u32,
+    num_extra_moduli: usize,
+    moduli: Vec<u64>,
+    packing: Option<PackedEncoding>,
+    basis: RnsBasis,
+    t_inv: Vec<u64>,
+    q_inv: Vec<Vec<u64>>,
+}
+
+impl CryptoParams {
+    /// Start building parameters from the defaults
+    pub fn builder() -> CryptoParamsBuilder {
+        CryptoParamsBuilder::default()
+    }
+
+    /// The plaintext modulus t
+    pub fn plaintext_modulus(&self) -> u64 {
+        self.plaintext_modulus.value()
+    }
+
+    /// The ring dimension N
+    pub fn ring_dim(&self) -> usize {
+        self.basis.ring_dim()
+    }
+
+    /// The multiplicative depth of fresh ciphertexts
+    pub fn multiplicative_depth(&self) -> usize {
+        self.multiplicative_depth
+    }
+
+    /// The primes of the modulus chain
+    pub fn moduli(&self) -> &[u64] {
+        &self.moduli
+    }
+
+    /// The standard deviation of the error distribution
+    pub fn std_dev(&self) -> f64 {
+        self.std_dev
+    }
+
+    /// The key switching technique
+    pub fn key_switch_technique(&self) -> KeySwitchTechnique {
+        self.key_switch_technique
+    }
+
+    /// The size in bits of the key switching digits, 0 for one digit per prime
+    pub fn digit_size(&self) -> u32 {
+        self.digit_size
+    }
+
+    /// The multiparty mode
+    pub fn multiparty_mode(&self) -> MultipartyMode {
+        self.multiparty_mode
+    }
+
+    /// The decryption noise mode
+    pub fn decryption_noise_mode(&self) -> DecryptionNoiseMode {
+        self.decryption_noise_mode
+    }
+
+    /// The proxy re-encryption mode
+    pub fn pre_mode(&self) -> ProxyReEncryptionMode {
+        self.pre_mode
+    }
+
+    /// The number of re-encryptions the HRA proxy re-encryption modes support
+    pub fn pre_num_hops(&self) -> usize {
+        self.pre_num_hops
+    }
+
+    /// The statistical security of noise flooding in bits
+    pub fn statistical_security(&self) -> u32 {
+        self.statistical_security
+    }
+
+    /// The hash of the key tags
+    pub fn hash_algorithm(&self) -> HashAlgorithm {
+        self.hash_algorithm
+    }
+
+    /// The number of plaintext slots, N if packed encoding is available
+    pub fn batch_size(&self) -> Option<usize> {
+        self.packing.as_ref().map(|_| self.ring_dim())
+    }
+
+    /// The settings the parameters were built from, with the ring dimension
+    pub(crate) fn settings(&self) -> &CryptoParamsBuilder {
+        &self.settings
+    }
+
+    pub(crate) fn plaintext_modulus_ref(&self) -> &Modulus {
+        &self.plaintext_modulus
+    }
+
+    pub(crate) fn basis(&self) -> &RnsBasis {
+        &self.basis
+    }
+
+    pub(crate) fn packing(&self) -> Option<&PackedEncoding> {
+        self.packing.as_ref()
+    }
+
+    /// The smallest number of primes a ciphertext can be reduced to
+    pub(crate) fn min_towers(&self) -> usize {
+        self.num_extra_moduli + 1
+    }
+
+    /// The number of primes a re-encryption drops in
+    /// [`ProxyReEncryptionMode::NoiseFloodingHra`]
+    pub(crate) fn pre_hop_towers(&self) -> usize {
+        self.pre_hop_towers
+    }
+
+    /// The size in bits of the uniform noise e such that t * e floods the key
+    /// switching noise of a re-encryption
+    pub(crate) fn pre_flooding_bits(&self) -> u32 {
+        self.pre_flooding_bits
+    }
+
+    /// t^-1 mod q_l and q_l^-1 mod q_j for every j < l
+    pub(crate) fn mod_reduce_constants(&self, l: usize) -> (u64, &[u64]) {
+        (self.t_inv[l], &self.q_inv[l])
+    }
+
+    /// The number of key switching digits of the i-th residue
+    pub(crate) fn digits(&self, i: usize) -> usize {
+        match self.digit_size {
+            0 => 1,
+            size => (self.moduli[i].ilog2() + 1).div_ceil(size) as usize,
+        }
+    }
+}
+
+/// The `count` largest `bits`-bit primes `1 mod step` not in `exclude`
+fn generate_primes(bits: usize, count: usize, step: u64, exclude: &[u64]) -> FheResult<Vec<u64>> {
+    let mut primes = Vec::with_capacity(count);
+    let mut q = last_prime(bits, step);
+    while let Some(prime) = q.filter(|_| primes.len() < count) {
+        if !exclude.contains(&prime) {
+            primes.push(prime);
+        }
+        q = previous_prime(prime, step);
+    }
+    if primes.len() < count {
+        return Err(Error::InvalidParameters(format!(
+            "not enough {bits}-bit primes 1 mod {step}"
+        )));
+    }
+    Ok(primes)
+}
+
+fn lcm(a: u64, b: u64) -> Option<u64> {
+    let (mut x, mut y) = (a, b);
+    while y != 0 {
+        (x, y) = (y, x % y);
+    }
+    (a / x).checked_mul(b)
+}
@@ -0,0 +1,104 @@
+use crate::fhe_core::{is_prime, Modulus, NttTables, PlaintextEncodings};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A decoded or to be encoded plaintext: a vector of integers modulo t in the
+/// centered interval, either one per slot or one per coefficient
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Plaintext {
+    values: Vec<i64>,
+    encoding: PlaintextEncodings,
+}
+
+impl Plaintext {
+    pub(crate) fn new(values: Vec<i64>, encoding: PlaintextEncodings) -> Self {
+        Self { values, encoding }
+    }
+
+    /// The slot or coefficient values
+    pub fn values(&self) -> &[i64] {
+        &self.values
+    }
+
+    /// The encoding of the plaintext
+    pub fn encoding(&self) -> PlaintextEncodings {
+        self.encoding
+    }
+
+    /// Keep only the first `length` values, decryption returns all N of them
+    pub fn set_length(&mut self, length: usize) {
+        self.values.truncate(length);
+    }
+}
+
+/// The isomorphism between Z_t[X]/(X^N + 1) and N slots of Z_t for a prime
+/// `t = 1 mod 2N`, arranged as two rows of N/2 slots that the automorphisms
+/// X -> X^(5^k) rotate and X -> X^(2N - 1) swaps
+#[derive(Debug, Clone)]
+pub(crate) struct PackedEncoding {
+    ntt: NttTables,
+    slots: Vec<usize>,
+}
+
+impl PackedEncoding {
+    /// The encoding for ring dimension `ring_dim` >= 2, if `t` allows it
+    pub(crate) fn new(ring_dim: usize, t: u64) -> Option<Self> {
+        if !is_prime(t) || !(t - 1).is_multiple_of(2 * ring_dim as u64) {
+            return None;
+        }
+        let ntt = NttTables::new(ring_dim, Modulus::new(t))?;
+        let modulus = ntt.modulus();
+        let two_n = 2 * ring_dim;
+
+        // the transform of X holds zeta^e at every position for the odd
+        // exponents e, which identifies the evaluation point of each position
+        let mut x = vec![0; ring_dim];
+        x[1] = 1;
+        ntt.forward(&mut x);
+        let zeta = x[0];
+        let mut exponents = HashMap::with_capacity(ring_dim);
+        let mut power = zeta;
+        let zeta_sq = modulus.mul(zeta, zeta);
+        for e in (1..two_n).step_by(2) {
+            exponents.insert(power, e);
+            power = modulus.mul(power, zeta_sq);
+        }
+        let mut position = vec![0; two_n];
+        for (j, value) in x.iter().enumerate() {
+            position[exponents[value]] = j;
+        }
+
+        let half = ring_dim / 2;
+        let mut slots = vec![0; ring_dim];
+        let mut g = 1;
+        for i in 0..half {
+            slots[i] = position[g];
+            slots[i + half] = position[two_n - g];
+            g = g * 5 % two_n;
+        }
+        Some(Self { ntt, slots })
+    }
+
+    /// The coefficients modulo t of the polynomial holding `values` in its first
+    /// slots and zeros elsewhere
+    pub(crate) fn encode(&self, values: &[i64]) -> Vec<i64> {
+        let t = self.ntt.modulus();
+        let mut evaluations = vec![0; self.slots.len()];
+        for (&slot, &v) in self.slots.iter().zip(values) {
+            evaluations[slot] = t.reduce_i64(v);
+        }
+        self.ntt.inverse(&mut evaluations);
+        evaluations.into_iter().map(|c| t.center(c)).collect()
+    }
+
+    /// The slot values of the polynomial with coefficients `coefficients` mod t
+    pub(crate) fn decode(&self, coefficients: &[u64]) -> Vec<i64> {
+        let t = self.ntt.modulus();
+        let mut evaluations = coefficients.to_vec();
+        self.ntt.forward(&mut evaluations);
+        self.slots
+            .iter()
+            .map(|&slot| t.center(evaluations[slot]))
+            .collect()
+    }
+}
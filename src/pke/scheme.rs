@@ -0,0 +1,423 @@
+use crate::fhe_core::{
+    parallel, sample_seed, signed_digits, DcrtPoly, DiscreteGaussianGenerator, PlaintextEncodings,
+    PolynomialFormat, UniformPolys,
+};
+use crate::pke::{noise, Ciphertext, CryptoParams, EvalKey, KeyPair, PrivateKey, PublicKey};
+use rand_core::CryptoRngCore;
+
+/// Generate a key pair whose public key uses the uniform polynomial `a`,
+/// expanded from a fresh seed if `None`
+pub(crate) fn key_gen(
+    params: &CryptoParams,
+    a: Option<&UniformPolys>,
+    rng: &mut dyn CryptoRngCore,
+) -> KeyPair {
+    let basis = params.basis();
+    let num_towers = basis.num_moduli();
+    let s =
+        DcrtPoly::ternary(basis, num_towers, rng).to_format(basis, PolynomialFormat::Evaluation);
+    let a = a
+        .cloned()
+        .unwrap_or_else(|| UniformPolys::sample(basis, 1, params.hash_algorithm(), rng));
+
+    let mut b = noise(params, num_towers, params.std_dev(), rng);
+    b.sub_assign(basis, &a.polys()[0].mul(basis, &s));
+    let public_key = PublicKey::new(b, a, params.hash_algorithm());
+    KeyPair {
+        secret_key: PrivateKey::new(s).with_key_tag(public_key.key_tag()),
+        public_key,
+    }
+}
+
+/// Encrypt the plaintext polynomial with small coefficients `m` under `pk`:
+/// (b * u + t * e_0 + m, a * u + t * e_1) for a ternary u
+pub(crate) fn encrypt(
+    params: &CryptoParams,
+    pk: &PublicKey,
+    m: &[i64],
+    encoding: PlaintextEncodings,
+    rng: &mut dyn CryptoRngCore,
+) -> Ciphertext {
+    let basis = params.basis();
+    let num_towers = pk.a().num_towers();
+    let [mut c0, c1] = encrypt_zero(params, pk, num_towers, params.std_dev(), rng);
+    c0.add_assign(
+        basis,
+        &DcrtPoly::from_signed(basis, num_towers, m).to_format(basis, PolynomialFormat::Evaluation),
+    );
+    Ciphertext::new(vec![c0, c1], encoding)
+        .with_key_tag(pk.key_tag())
+        .with_noise(noise::fresh(params, params.std_dev()))
+}
+
+/// Encrypt the plaintext polynomial with small coefficients `m` under the
+/// secret `s`: (-a * s + t * e + m, a) for a uniform a expanded from a fresh
+/// seed, whose noise is that of a single error
+pub(crate) fn encrypt_with_secret_key(
+    params: &CryptoParams,
+    s: &DcrtPoly,
+    m: &[i64],
+    encoding: PlaintextEncodings,
+    rng: &mut dyn CryptoRngCore,
+) -> Ciphertext {
+    let basis = params.basis();
+    let num_towers = basis.num_moduli();
+    let seed = sample_seed(rng);
+    let a = UniformPolys::from_seed(basis, 1, params.hash_algorithm(), seed);
+    let c1 = a.polys()[0].clone();
+
+    let mut c0 = noise(params, num_towers, params.std_dev(), rng);
+    c0.sub_assign(basis, &c1.mul(basis, s));
+    c0.add_assign(
+        basis,
+        &DcrtPoly::from_signed(basis, num_towers, m).to_format(basis, PolynomialFormat::Evaluation),
+    );
+    Ciphertext::new(vec![c0, c1], encoding)
+        .with_seed(seed)
+        .with_noise(noise::gaussian(params, params.std_dev()))
+}
+
+/// An encryption of zero under `pk` over its first `num_towers` primes, with
+/// errors of width `std_dev`
+pub(crate) fn encrypt_zero(
+    params: &CryptoParams,
+    pk: &PublicKey,
+    num_towers: usize,
+    std_dev: f64,
+    rng: &mut dyn CryptoRngCore,
+) -> [DcrtPoly; 2] {
+    let basis = params.basis();
+    let u =
+        DcrtPoly::ternary(basis, num_towers, rng).to_format(basis, PolynomialFormat::Evaluation);
+    let mut b = pk.b().clone();
+    let mut a = pk.a().clone();
+    b.truncate(num_towers);
+    a.truncate(num_towers);
+
+    let mut c0 = noise(params, num_towers, std_dev, rng);
+    c0.add_assign(basis, &b.mul(basis, &u));
+    let mut c1 = noise(params, num_towers, std_dev, rng);
+    c1.add_assign(basis, &a.mul(basis, &u));
+    [c0, c1]
+}
+
+/// The phase c_0 + c_1 * s + ... + c_k * s^k of `ct` under the secret `s`
+pub(crate) fn phase(params: &CryptoParams, s: &DcrtPoly, ct: &Ciphertext) -> DcrtPoly {
+    let basis = params.basis();
+    let mut s = s.clone();
+    s.truncate(ct.num_towers());
+
+    // Horner evaluation from the highest power of s
+    let mut elements = ct.elements().iter().rev();
+    let mut out = elements
+        .next()
+        .cloned()
+        .unwrap_or_else(|| DcrtPoly::zero(basis, ct.num_towers(), PolynomialFormat::Evaluation));
+    for c in elements {
+        out.mul_assign(basis, &s);
+        out.add_assign(basis, c);
+    }
+    out
+}
+
+/// The coefficients in [0, t) of the plaintext polynomial behind a phase
+/// m + t * e, which is switched down to the first prime
+pub(crate) fn decode_phase(params: &CryptoParams, mut phase: DcrtPoly) -> Vec<u64> {
+    let basis = params.basis();
+    let t = params.plaintext_modulus_ref();
+    while phase.num_towers() > 1 {
+        mod_reduce_poly(params, &mut phase);
+    }
+    phase.set_format(basis, PolynomialFormat::Coefficient);
+    let q = basis.modulus(0);
+    phase
+        .tower(0)
+        .iter()
+        .map(|&c| t.reduce_i64(q.center(c)))
+        .collect()
+}
+
+/// Drop the last prime q_l of a polynomial congruent to m modulo t, keeping it
+/// congruent to m: p <- (p - delta) / q_l for the multiple delta of t that is
+/// congruent to p modulo q_l
+pub(crate) fn mod_reduce_poly(params: &CryptoParams, p: &mut DcrtPoly) {
+    let basis = params.basis();
+    let t = params.plaintext_modulus();
+    let l = p.num_towers() - 1;
+    let q_l = basis.modulus(l);
+    let (t_inv, q_inv) = params.mod_reduce_constants(l);
+
+    let mut last = p.tower(l).to_vec();
+    if matches!(p.format(), PolynomialFormat::Evaluation) {
+        basis.ntt(l).inverse(&mut last);
+    }
+    let delta: Vec<i64> = last
+        .iter()
+        .map(|&c| q_l.center(q_l.mul(c, t_inv)))
+        .collect();
+
+    p.truncate(l);
+    for (j, &inv) in q_inv.iter().enumerate() {
+        let q_j = basis.modulus(j);
+        let t_j = q_j.reduce(t);
+        let mut d: Vec<u64> = delta
+            .iter()
+            .map(|&x| q_j.mul(q_j.reduce_i64(x), t_j))
+            .collect();
+        if matches!(p.format(), PolynomialFormat::Evaluation) {
+            basis.ntt(j).forward(&mut d);
+        }
+        for (x, y) in p.tower_mut(j).iter_mut().zip(d) {
+            *x = q_j.mul(q_j.sub(*x, y), inv);
+        }
+    }
+}
+
+/// Drop the last prime of every element of `ct`
+pub(crate) fn mod_reduce(params: &CryptoParams, ct: &mut Ciphertext) {
+    let q_l = params.moduli()[ct.num_towers() - 1];
+    for c in ct.elements_mut() {
+        mod_reduce_poly(params, c);
+    }
+    ct.set_noise(noise::mod_reduce(params, ct.noise(), q_l));
+}
+
+/// Generate the key switching key from `s_from` to `s_to`, reusing the uniform
+/// polynomials `a` of another key if given so the keys of several parties can
+/// be added, else expanding them from a fresh seed
+pub(crate) fn key_switch_gen(
+    params: &CryptoParams,
+    s_from: &DcrtPoly,
+    s_to: &DcrtPoly,
+    a: Option<&UniformPolys>,
+    rng: &mut dyn CryptoRngCore,
+) -> EvalKey {
+    let basis = params.basis();
+    let num_towers = basis.num_moduli();
+    let digit_size = params.digit_size();
+    let num_digits = (0..num_towers).map(|i| params.digits(i)).sum();
+    let a = a
+        .cloned()
+        .unwrap_or_else(|| UniformPolys::sample(basis, num_digits, params.hash_algorithm(), rng));
+
+    let mut keys_b = Vec::new();
+    for i in 0..num_towers {
+        let q_i = basis.modulus(i);
+        for k in 0..params.digits(i) {
+            let mut b = noise(params, num_towers, params.std_dev(), rng);
+            b.sub_assign(basis, &a.polys()[keys_b.len()].mul(basis, s_to));
+            // g_{i,k} is 2^(w k) modulo q_i and 0 modulo the other primes
+            let g = q_i.pow(2, digit_size as u64 * k as u64);
+            for (x, &y) in b.tower_mut(i).iter_mut().zip(s_from.tower(i)) {
+                *x = q_i.add(*x, q_i.mul(y, g));
+            }
+            keys_b.push(b);
+        }
+    }
+    EvalKey::new(keys_b, a, params.hash_algorithm())
+}
+
+/// Generate the re-encryption key from `s_from` to the owner of `pk`: the
+/// encryptions under `pk` of g_i * s_from, which switch keys like the output of
+/// [`key_switch_gen`] without knowledge of the target secret
+pub(crate) fn re_key_gen(
+    params: &CryptoParams,
+    s_from: &DcrtPoly,
+    pk: &PublicKey,
+    rng: &mut dyn CryptoRngCore,
+) -> EvalKey {
+    let basis = params.basis();
+    let num_towers = basis.num_moduli();
+    let digit_size = params.digit_size();
+
+    let mut keys_b = Vec::new();
+    let mut keys_a = Vec::new();
+    for i in 0..num_towers {
+        let q_i = basis.modulus(i);
+        for k in 0..params.digits(i) {
+            let u = DcrtPoly::ternary(basis, num_towers, rng)
+                .to_format(basis, PolynomialFormat::Evaluation);
+            let mut b = noise(params, num_towers, params.std_dev(), rng);
+            b.add_assign(basis, &pk.b().mul(basis, &u));
+            let g = q_i.pow(2, digit_size as u64 * k as u64);
+            for (x, &y) in b.tower_mut(i).iter_mut().zip(s_from.tower(i)) {
+                *x = q_i.add(*x, q_i.mul(y, g));
+            }
+            let mut a = noise(params, num_towers, params.std_dev(), rng);
+            a.add_assign(basis, &pk.a().mul(basis, &u));
+            keys_b.push(b);
+            keys_a.push(a);
+        }
+    }
+    EvalKey::new(
+        keys_b,
+        UniformPolys::from_polys(keys_a),
+        params.hash_algorithm(),
+    )
+}
+
+/// Switch the polynomial `c`, multiplied by the source secret s' in a phase, to
+/// the pair (k_0, k_1) with k_0 + k_1 * s = c * s' + t * e
+pub(crate) fn key_switch(params: &CryptoParams, ek: &EvalKey, c: &DcrtPoly) -> [DcrtPoly; 2] {
+    let basis = params.basis();
+    let num_towers = c.num_towers();
+    let c = c.to_format(basis, PolynomialFormat::Coefficient);
+
+    // the digits of every tower, in the order of the key polynomials
+    let towers: Vec<usize> = (0..num_towers).collect();
+    let digits: Vec<Vec<i64>> = parallel::map(&towers, |_, &i| decompose(params, &c, i))
+        .into_iter()
+        .flatten()
+        .collect();
+    let zero = || {
+        [
+            DcrtPoly::zero(basis, num_towers, PolynomialFormat::Evaluation),
+            DcrtPoly::zero(basis, num_towers, PolynomialFormat::Evaluation),
+        ]
+    };
+    parallel::map_reduce(
+        &digits,
+        zero,
+        |index, d| {
+            let d = DcrtPoly::from_signed(basis, num_towers, d)
+                .to_format(basis, PolynomialFormat::Evaluation);
+            [&ek.b()[index], &ek.a()[index]].map(|key| {
+                let mut key = key.clone();
+                key.truncate(num_towers);
+                key.mul_assign(basis, &d);
+                key
+            })
+        },
+        |mut out, products| {
+            for (o, p) in out.iter_mut().zip(&products) {
+                o.add_assign(basis, p);
+            }
+            out
+        },
+    )
+}
+
+/// The centered residues modulo q_i of the i-th tower of `c`, in coefficient
+/// format, split into the signed digits of the key switching keys
+fn decompose(params: &CryptoParams, c: &DcrtPoly, i: usize) -> Vec<Vec<i64>> {
+    let q_i = params.basis().modulus(i);
+    let digits = params.digits(i);
+    let mut decomposition = vec![vec![0; params.ring_dim()]; digits];
+    let mut buffer = vec![0; digits];
+    for (j, &x) in c.tower(i).iter().enumerate() {
+        if digits == 1 {
+            decomposition[0][j] = q_i.center(x);
+        } else {
+            signed_digits(q_i.center(x), params.digit_size(), &mut buffer);
+            for (d, &v) in decomposition.iter_mut().zip(&buffer) {
+                d[j] = v;
+            }
+        }
+    }
+    decomposition
+}
+
+/// The product of two ciphertexts of two elements, a ciphertext of three
+/// elements under (1, s, s^2)
+pub(crate) fn eval_mult(params: &CryptoParams, ct1: &Ciphertext, ct2: &Ciphertext) -> Ciphertext {
+    let basis = params.basis();
+    let (c, d) = (ct1.elements(), ct2.elements());
+    let mut cross = c[0].mul(basis, &d[1]);
+    cross.add_assign(basis, &c[1].mul(basis, &d[0]));
+    Ciphertext::new(
+        vec![c[0].mul(basis, &d[0]), cross, c[1].mul(basis, &d[1])],
+        ct1.encoding(),
+    )
+    .with_hops(ct1.hops().max(ct2.hops()))
+    .with_key_tag(ct1.key_tag())
+    .with_noise(noise::mult(params, ct1.noise(), ct2.noise()))
+}
+
+/// Bring a ciphertext of three elements back to two with the key switching key
+/// from s^2 to s
+pub(crate) fn relinearize(params: &CryptoParams, ek: &EvalKey, ct: &mut Ciphertext) {
+    let basis = params.basis();
+    let num_towers = ct.num_towers();
+    let elements = ct.elements_mut();
+    if let Some(c2) = elements.pop() {
+        let [k0, k1] = key_switch(params, ek, &c2);
+        elements[0].add_assign(basis, &k0);
+        elements[1].add_assign(basis, &k1);
+        let key_switch = noise::key_switch(params, num_towers, params.std_dev());
+        ct.set_noise(noise::add(ct.noise(), key_switch));
+    }
+}
+
+/// s(X^k) for a secret in evaluation format
+pub(crate) fn automorphism_poly(params: &CryptoParams, s: &DcrtPoly, k: usize) -> DcrtPoly {
+    let basis = params.basis();
+    s.to_format(basis, PolynomialFormat::Coefficient)
+        .automorphism(basis, k)
+        .to_format(basis, PolynomialFormat::Evaluation)
+}
+
+/// Apply X -> X^k to a ciphertext of two elements and switch it back to s with
+/// the key switching key from s(X^k) to s
+pub(crate) fn automorphism(
+    params: &CryptoParams,
+    ek: &EvalKey,
+    ct: &Ciphertext,
+    k: usize,
+) -> Ciphertext {
+    let basis = params.basis();
+    let c0 = automorphism_poly(params, &ct.elements()[0], k);
+    let c1 = automorphism_poly(params, &ct.elements()[1], k);
+    let [mut k0, k1] = key_switch(params, ek, &c1);
+    k0.add_assign(basis, &c0);
+    let key_switch = noise::key_switch(params, ct.num_towers(), params.std_dev());
+    Ciphertext::new(vec![k0, k1], ct.encoding())
+        .with_hops(ct.hops())
+        .with_key_tag(ct.key_tag())
+        .with_noise(noise::add(ct.noise(), key_switch))
+}
+
+/// The automorphism index 5^r mod 2N rotating the slot rows left by `r`
+pub(crate) fn rotation_index(params: &CryptoParams, r: i32) -> usize {
+    let ring_dim = params.ring_dim() as i64;
+    let two_n = 2 * ring_dim as u64;
+    let r = (r as i64).rem_euclid(ring_dim / 2) as u64;
+    let mut k = 1;
+    for _ in 0..r {
+        k = k * 5 % two_n;
+    }
+    k as usize
+}
+
+/// The automorphism index swapping the two slot rows
+pub(crate) fn row_swap_index(params: &CryptoParams) -> usize {
+    2 * params.ring_dim() - 1
+}
+
+/// t * e for a Gaussian e of standard deviation `std_dev`, in evaluation format
+pub(crate) fn noise(
+    params: &CryptoParams,
+    num_towers: usize,
+    std_dev: f64,
+    rng: &mut dyn CryptoRngCore,
+) -> DcrtPoly {
+    let basis = params.basis();
+    let dgg = DiscreteGaussianGenerator::new(std_dev);
+    let mut e = DcrtPoly::gaussian(basis, num_towers, &dgg, rng);
+    e.set_format(basis, PolynomialFormat::Evaluation);
+    e.mul_scalar_assign(basis, params.plaintext_modulus());
+    e
+}
+
+/// t * e for e uniform of `bits` bits, in evaluation format
+pub(crate) fn flooding_noise(
+    params: &CryptoParams,
+    num_towers: usize,
+    bits: u32,
+    rng: &mut dyn CryptoRngCore,
+) -> DcrtPoly {
+    let basis = params.basis();
+    let mut e = DcrtPoly::uniform_bits(basis, num_towers, bits, rng);
+    e.set_format(basis, PolynomialFormat::Evaluation);
+    e.mul_scalar_assign(basis, params.plaintext_modulus());
+    e
+}